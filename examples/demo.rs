@@ -0,0 +1,41 @@
+use rusty_nn::prelude::*;
+
+use std::env;
+
+// usage: cargo run --example demo -- <input file> <output file>
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let input_path = args.get(1).cloned().unwrap_or("input.txt".to_string());
+    let output_path = args.get(2).cloned().unwrap_or("output.txt".to_string());
+
+    if let Err(e) = run(input_path, output_path) {
+        println!("Error: {e}");
+    }
+}
+
+fn run(input_path: String, output_path: String) -> NnResult<()> {
+
+    let mut session = Session::new(input_path, output_path, 1000, 0.1, 0.01, false)?;
+
+    if session.dataset.is_empty() {
+        return Err(NnError::InvalidDataset("the dataset holds no sample".to_string()));
+    }
+
+    let nb_inputs = session.dataset[0].input.y_length;
+    let nb_outputs = session.dataset[0].output.y_length;
+
+    let shapes = vec![
+        DenseShape::new(nb_inputs, 1, 1),
+        DenseShape::new(4, 1, 1),
+        DenseShape::new(nb_outputs, 1, 1)
+    ];
+
+    let activations = vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid];
+
+    let mut model = DenseModel::new(activations, DenseLosses::MeanSquaredError, shapes);
+
+    session.train(&mut model)?;
+
+    model.save("demo")
+}
@@ -0,0 +1,53 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+
+use super::dense_activation::DenseActivation;
+
+// An activation applied to the raw values of a layer, one column per sample.
+// Implement it to plug a custom activation into a DenseModel, and register its name
+// in a Registry so that models using it can be loaded back.
+pub trait Activation {
+    // name written in the .arch file, it must not contain whitespace
+    fn name(&self) -> String;
+
+    // activates the raw values in place, parameters being the learnable parameters
+    // of the layer (empty when the activation has none, see initial_parameters)
+    fn forward(&self, mat: &mut Matrix, parameters: &Matrix) -> NnResult<()>;
+
+    // element-wise derivative at the raw value x, used by the default backward
+    fn derivative(&self, _x: f64) -> NnResult<f64> {
+        Err(NnError::UnsupportedActivation(self.name()))
+    }
+
+    // given the gradient w.r.t. the activated values, returns the gradient w.r.t. the raw values
+    // and the gradient w.r.t. the parameters (averaged over the samples, empty when there are none)
+    fn backward(&self, raw_values: &Matrix, values: &Matrix, _parameters: &Matrix, gradient: &Matrix)
+        -> NnResult<(Matrix, Matrix)> {
+
+        if gradient.x_length != values.x_length || gradient.y_length != values.y_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "Activation::backward",
+                left: (values.y_length, values.x_length),
+                right: (gradient.y_length, gradient.x_length)
+            });
+        }
+
+        let mut result = gradient.copy();
+
+        for i in 0..result.values.len() {
+            result.values[i] *= self.derivative(raw_values.values[i])?;
+        }
+        Ok((result, Matrix::new(0, 0)))
+    }
+
+    // learnable parameters of a layer of nb_neurons neurons, before any training
+    fn initial_parameters(&self, _nb_neurons: usize) -> Matrix {
+        Matrix::new(0, 0)
+    }
+
+    // the built-in activation, if this is one (used for the fused softmax / cross-entropy
+    // gradient and to pick a default initializer)
+    fn as_dense(&self) -> Option<DenseActivation> {
+        None
+    }
+}
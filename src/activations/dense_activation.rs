@@ -1,31 +1,145 @@
+use crate::derivations::dense_derivation::{apply_backward, apply_derivation, prelu_backward};
+use crate::errors::nn_error::{NnError, NnResult};
 use crate::maths::matrices::Matrix;
-use super::LEAKY_RELU_VALUE;
+use crate::maths::special_functions::normal_cdf;
+use crate::utils::parsing::parse_argument;
+use super::activation::Activation;
+use super::{DEFAULT_ELU_ALPHA, DEFAULT_LEAKY_RELU_ALPHA, PRELU_INITIAL_SLOPE, SELU_ALPHA, SELU_LAMBDA};
 
-use std::process;
+use std::f64::consts::PI;
+use std::fmt;
 use std::str::FromStr;
 
-#[derive(strum_macros::Display)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum DenseActivation {
     NoActivation, // for safety
     Sigmoid,
     Relu,
-    LeakyRelu,
+    LeakyRelu(f64), // slope of the negative side
+    PRelu, // one learnable slope per neuron, held by the model
     Softmax, // output layer only
-    Tanh
+    Tanh,
+    Gelu,
+    GeluTanh, // tanh approximation of GELU
+    Elu(f64),
+    Selu,
+    Swish, // also known as SiLU
+    Mish,
+    Softplus,
+    Softsign,
+    HardSigmoid,
+    HardTanh,
+    Identity // linear output, for regression
+}
+
+impl DenseActivation {
+    // whether the activation has parameters trained along with the weights
+    pub fn is_learnable(&self) -> bool {
+        matches!(self, DenseActivation::PRelu)
+    }
+}
+
+impl fmt::Display for DenseActivation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DenseActivation::NoActivation => write!(f, "NoActivation"),
+            DenseActivation::Sigmoid => write!(f, "Sigmoid"),
+            DenseActivation::Relu => write!(f, "Relu"),
+            DenseActivation::LeakyRelu(alpha) => write!(f, "LeakyRelu({alpha})"),
+            DenseActivation::PRelu => write!(f, "PRelu"),
+            DenseActivation::Softmax => write!(f, "Softmax"),
+            DenseActivation::Tanh => write!(f, "Tanh"),
+            DenseActivation::Gelu => write!(f, "Gelu"),
+            DenseActivation::GeluTanh => write!(f, "GeluTanh"),
+            DenseActivation::Elu(alpha) => write!(f, "Elu({alpha})"),
+            DenseActivation::Selu => write!(f, "Selu"),
+            DenseActivation::Swish => write!(f, "Swish"),
+            DenseActivation::Mish => write!(f, "Mish"),
+            DenseActivation::Softplus => write!(f, "Softplus"),
+            DenseActivation::Softsign => write!(f, "Softsign"),
+            DenseActivation::HardSigmoid => write!(f, "HardSigmoid"),
+            DenseActivation::HardTanh => write!(f, "HardTanh"),
+            DenseActivation::Identity => write!(f, "Identity")
+        }
+    }
+}
+
+impl Activation for DenseActivation {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn forward(&self, mat: &mut Matrix, parameters: &Matrix) -> NnResult<()> {
+        match self {
+            DenseActivation::PRelu => apply_prelu(mat, parameters),
+            _ => apply_activation(self, mat)
+        }
+    }
+
+    fn derivative(&self, x: f64) -> NnResult<f64> {
+        apply_derivation(self, x)
+    }
+
+    fn backward(&self, raw_values: &Matrix, values: &Matrix, parameters: &Matrix, gradient: &Matrix)
+        -> NnResult<(Matrix, Matrix)> {
+
+        match self {
+            DenseActivation::PRelu => prelu_backward(raw_values, parameters, gradient),
+            _ => Ok((apply_backward(self, raw_values, values, gradient)?, Matrix::new(0, 0)))
+        }
+    }
+
+    // one slope per neuron for PRelu
+    fn initial_parameters(&self, nb_neurons: usize) -> Matrix {
+        match self {
+            DenseActivation::PRelu => {
+                let mut slopes = Matrix::new(1, nb_neurons);
+                slopes.add_real(PRELU_INITIAL_SLOPE);
+                slopes
+            },
+            _ => Matrix::new(0, 0)
+        }
+    }
+
+    fn as_dense(&self) -> Option<DenseActivation> {
+        Some(*self)
+    }
 }
 
 impl FromStr for DenseActivation {
-    type Err = ();
+    type Err = NnError;
 
     fn from_str(input: &str) -> Result<DenseActivation, Self::Err> {
         match input {
             "NoActivation"  => Ok(DenseActivation::NoActivation),
             "Sigmoid"       => Ok(DenseActivation::Sigmoid),
             "Relu"          => Ok(DenseActivation::Relu),
-            "LeakyRelu"     => Ok(DenseActivation::LeakyRelu),
+            "LeakyRelu"     => Ok(DenseActivation::LeakyRelu(DEFAULT_LEAKY_RELU_ALPHA)),
+            "PRelu"         => Ok(DenseActivation::PRelu),
             "Softmax"       => Ok(DenseActivation::Softmax),
             "Tanh"          => Ok(DenseActivation::Tanh),
-            _ => Err(())
+            "Gelu"          => Ok(DenseActivation::Gelu),
+            "GeluTanh"      => Ok(DenseActivation::GeluTanh),
+            "Elu"           => Ok(DenseActivation::Elu(DEFAULT_ELU_ALPHA)),
+            "Selu"          => Ok(DenseActivation::Selu),
+            "Swish" | "Silu" => Ok(DenseActivation::Swish),
+            "Mish"          => Ok(DenseActivation::Mish),
+            "Softplus"      => Ok(DenseActivation::Softplus),
+            "Softsign"      => Ok(DenseActivation::Softsign),
+            "HardSigmoid"   => Ok(DenseActivation::HardSigmoid),
+            "HardTanh"      => Ok(DenseActivation::HardTanh),
+            "Identity"      => Ok(DenseActivation::Identity),
+            _ => {
+                if let Some(alpha) = parse_argument(input, "LeakyRelu") {
+                    Ok(DenseActivation::LeakyRelu(alpha))
+                }
+                else if let Some(alpha) = parse_argument(input, "Elu") {
+                    Ok(DenseActivation::Elu(alpha))
+                }
+                else {
+                    Err(NnError::UnsupportedActivation(input.to_string()))
+                }
+            }
         }
     }
 }
@@ -50,22 +164,53 @@ fn relu(mat: &mut Matrix){
     }
 }
 
-pub fn __leaky_relu(x: f64) -> f64 {
-    if x > 0.0 {x * LEAKY_RELU_VALUE} else {0.0}
+pub fn __leaky_relu(x: f64, alpha: f64) -> f64 {
+    if x > 0.0 {x} else {alpha * x}
 }
 
-fn leaky_relu(mat: &mut Matrix){
+fn leaky_relu(mat: &mut Matrix, alpha: f64){
     for i in 0..mat.values.len() {
-        mat.values[i] = __leaky_relu(mat.values[i]);
+        mat.values[i] = __leaky_relu(mat.values[i], alpha);
+    }
+}
+
+// slopes is a column vector holding the slope of each neuron (row) of mat
+pub fn apply_prelu(mat: &mut Matrix, slopes: &Matrix) -> NnResult<()> {
+    if slopes.x_length != 1 || slopes.y_length != mat.y_length {
+        return Err(NnError::ShapeMismatch {
+            operation: "apply_prelu",
+            left: (mat.y_length, mat.x_length),
+            right: (slopes.y_length, slopes.x_length)
+        });
+    }
+
+    for y in 0..mat.y_length {
+        for x in 0..mat.x_length {
+            let i: usize = y * mat.x_length + x;
+            mat.values[i] = __leaky_relu(mat.values[i], slopes.values[y]);
+        }
     }
+    Ok(())
 }
 
+// applied to each column (sample) independently,
+// the maximum is subtracted first so that exp cannot overflow
 fn softmax(mat: &mut Matrix){
 
-    let sum: f64 = mat.values.iter().sum();
+    for x in 0..mat.x_length {
+        let max: f64 = (0..mat.y_length).map(|y| mat.get(y, x)).fold(f64::NEG_INFINITY, f64::max);
 
-    for i in 0..mat.values.len() {
-        mat.values[i] = mat.values[i].exp() / sum;
+        let mut sum: f64 = 0.0;
+
+        for y in 0..mat.y_length {
+            let value: f64 = (mat.get(y, x) - max).exp();
+            mat.set(y, x, value);
+            sum += value;
+        }
+
+        for y in 0..mat.y_length {
+            mat.set(y, x, mat.get(y, x) / sum);
+        }
     }
 }
 
@@ -80,17 +225,79 @@ fn tanh(mat: &mut Matrix){
     }
 }
 
-pub fn apply_activation(activation: &DenseActivation, mat: &mut Matrix ) {
+// 0.5 * x * (1 + erf(x / sqrt(2)))
+pub fn __gelu(x: f64) -> f64 {
+    x * normal_cdf(x)
+}
+
+// 0.5 * x * (1 + tanh(sqrt(2 / pi) * (x + 0.044715 * x^3)))
+pub fn __gelu_tanh(x: f64) -> f64 {
+    0.5 * x * (1.0 + ((2.0 / PI).sqrt() * (x + 0.044715 * x.powi(3))).tanh())
+}
+
+pub fn __elu(x: f64, alpha: f64) -> f64 {
+    if x > 0.0 {x} else {alpha * x.exp_m1()}
+}
+
+pub fn __selu(x: f64) -> f64 {
+    SELU_LAMBDA * if x > 0.0 {x} else {SELU_ALPHA * x.exp_m1()}
+}
+
+pub fn __swish(x: f64) -> f64 {
+    x * __sigmoid(x)
+}
+
+// ln(1 + e^x), written so that e^x cannot overflow
+pub fn __softplus(x: f64) -> f64 {
+    x.max(0.0) + (-x.abs()).exp().ln_1p()
+}
+
+pub fn __mish(x: f64) -> f64 {
+    x * __softplus(x).tanh()
+}
+
+pub fn __softsign(x: f64) -> f64 {
+    x / (1.0 + x.abs())
+}
+
+// piecewise linear approximation of the sigmoid: clamp(x / 6 + 1 / 2, 0, 1)
+pub fn __hard_sigmoid(x: f64) -> f64 {
+    (x / 6.0 + 0.5).clamp(0.0, 1.0)
+}
+
+pub fn __hard_tanh(x: f64) -> f64 {
+    x.clamp(-1.0, 1.0)
+}
+
+fn apply_element_wise(mat: &mut Matrix, function: impl Fn(f64) -> f64) {
+    for value in mat.values.iter_mut() {
+        *value = function(*value);
+    }
+}
+
+// PRelu depends on the slopes held by the model: see apply_prelu
+pub fn apply_activation(activation: &DenseActivation, mat: &mut Matrix ) -> NnResult<()> {
     match activation {
-        DenseActivation::NoActivation =>  {
-            println!("No Activation function, exiting..."); 
-            process::exit(1);
+        DenseActivation::NoActivation | DenseActivation::PRelu =>  {
+            return Err(NnError::UnsupportedActivation(activation.to_string()));
         },
         DenseActivation::Sigmoid => sigmoid(mat),
         DenseActivation::Relu => relu(mat),
-        DenseActivation::LeakyRelu => leaky_relu(mat),
+        DenseActivation::LeakyRelu(alpha) => leaky_relu(mat, *alpha),
         DenseActivation::Softmax => softmax(mat),
-        DenseActivation::Tanh => tanh(mat)
+        DenseActivation::Tanh => tanh(mat),
+        DenseActivation::Gelu => apply_element_wise(mat, __gelu),
+        DenseActivation::GeluTanh => apply_element_wise(mat, __gelu_tanh),
+        DenseActivation::Elu(alpha) => apply_element_wise(mat, |x| __elu(x, *alpha)),
+        DenseActivation::Selu => apply_element_wise(mat, __selu),
+        DenseActivation::Swish => apply_element_wise(mat, __swish),
+        DenseActivation::Mish => apply_element_wise(mat, __mish),
+        DenseActivation::Softplus => apply_element_wise(mat, __softplus),
+        DenseActivation::Softsign => apply_element_wise(mat, __softsign),
+        DenseActivation::HardSigmoid => apply_element_wise(mat, __hard_sigmoid),
+        DenseActivation::HardTanh => apply_element_wise(mat, __hard_tanh),
+        DenseActivation::Identity => ()
     }
+    Ok(())
 }
 
@@ -1,3 +1,13 @@
+pub mod activation;
 pub mod dense_activation;
 
-pub const LEAKY_RELU_VALUE: f64 = 0.01;
\ No newline at end of file
+// slopes used when LeakyRelu or Elu are given without one (e.g. in older .arch files)
+pub const DEFAULT_LEAKY_RELU_ALPHA: f64 = 0.01;
+pub const DEFAULT_ELU_ALPHA: f64 = 1.0;
+
+// initial slope of every PRelu neuron (He et al., 2015)
+pub const PRELU_INITIAL_SLOPE: f64 = 0.25;
+
+// self-normalizing constants of SELU (Klambauer et al., 2017)
+pub const SELU_ALPHA: f64 = 1.6732632423543772;
+pub const SELU_LAMBDA: f64 = 1.0507009873554805;
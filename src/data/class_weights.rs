@@ -0,0 +1,65 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+
+use super::create_data::Sample;
+use super::split_data::Labels;
+
+// Weights given to each class (see Labels::class_of) in the loss minimized by Session::train.
+// They multiply the weight of every sample of the class.
+pub enum ClassWeights {
+    Uniform,
+
+    // n_samples / (n_classes * n_samples_of_the_class), so every class weighs as much in total
+    Balanced,
+
+    // one weight per class
+    Manual(Vec<f64>)
+}
+
+impl ClassWeights {
+    pub fn compute(&self, samples: &[Sample], labels: Labels) -> NnResult<Vec<f64>> {
+        let nb_classes: usize = samples.first().map_or(0, |sample| labels.nb_classes(&sample.output));
+
+        match self {
+            ClassWeights::Uniform => Ok(vec![1.0; nb_classes]),
+            ClassWeights::Balanced => {
+                let mut counts: Vec<usize> = vec![0; nb_classes];
+
+                for sample in samples {
+                    counts[labels.class_of(&sample.output)?.min(nb_classes - 1)] += 1;
+                }
+
+                // absent classes keep a weight of 1, it never applies
+                Ok(counts.iter().map(|count| match count {
+                    0 => 1.0,
+                    _ => samples.len() as f64 / (nb_classes * count) as f64
+                }).collect())
+            },
+            ClassWeights::Manual(weights) => {
+                if weights.len() != nb_classes {
+                    return Err(NnError::InvalidDataset(format!(
+                        "{} class weights given for {nb_classes} classes", weights.len())));
+                }
+                Ok(weights.clone())
+            }
+        }
+    }
+}
+
+// row vector holding the weight of each sample, multiplied by the weight of its class if given
+pub fn sample_weights(samples: &[Sample], class_weights: Option<&[f64]>, labels: Labels) -> NnResult<Matrix> {
+    let mut weights = Matrix::new(samples.len(), 1);
+
+    for (x, sample) in samples.iter().enumerate() {
+        let class_weight: f64 = match class_weights {
+            Some(class_weights) => {
+                let class: usize = labels.class_of(&sample.output)?;
+                *class_weights.get(class).ok_or_else(|| NnError::InvalidDataset(format!("no weight for the class {class}")))?
+            },
+            None => 1.0
+        };
+
+        weights.values[x] = sample.weight * class_weight;
+    }
+    Ok(weights)
+}
@@ -1,13 +1,16 @@
 use std::fs::File;
 use std::io::{BufReader, BufRead};
-use std::{process, usize};
 
+use crate::errors::nn_error::{NnError, NnResult};
 use crate::maths::matrices::Matrix;
 
 
 pub struct Sample {
     pub input: Matrix,
-    pub output: Matrix
+    pub output: Matrix,
+
+    // relative importance of the sample in the loss minimized by Session::train (1 by default)
+    pub weight: f64
 }
 
 impl Sample {
@@ -15,8 +18,31 @@ impl Sample {
     pub fn new(input: Vec<f64>, output: Vec<f64>) -> Sample{
         Sample {
             input: Matrix::vec_to_col_mat(&input),
-            output: Matrix::vec_to_col_mat(&output)
+            output: Matrix::vec_to_col_mat(&output),
+            weight: 1.0
+        }
+    }
+
+    pub fn weighted(mut self, weight: f64) -> Sample {
+        self.weight = weight;
+        self
+    }
+
+    // a sequence for the recurrent layers (see Recurrent), step after step, padded at the end with
+    // padding up to timesteps steps so that sequences of different lengths can be batched together;
+    // recurrent layers masked with the padding value skip the padded steps
+    pub fn sequence(steps: &[Vec<f64>], timesteps: usize, padding: f64, output: Vec<f64>) -> NnResult<Sample> {
+        let features: usize = steps.first().map_or(0, |step| step.len());
+
+        if features == 0 || steps.len() > timesteps || steps.iter().any(|step| step.len() != features) {
+            return Err(NnError::InvalidDataset(format!(
+                "expected a sequence of at most {timesteps} steps with the same number of features")));
         }
+
+        let mut input: Vec<f64> = steps.concat();
+        input.resize(features * timesteps, padding);
+
+        Ok(Sample::new(input, output))
     }
 
     pub fn generate_sample_vec(input_vec: &mut Vec<Vec<f64>>, output_vec: &mut Vec<Vec<f64>>) -> Vec<Sample> {
@@ -36,38 +62,49 @@ impl Sample {
             let x = self.input.get(i,0);
             print!("{x}, ");
         }
-        println!("");
+        println!();
 
         println!("---Output---");
         for i in 0..self.output.y_length {
             let x = self.output.get(i,0);
             print!("{x}, ");
         }
-        println!("");
+        println!();
     }
 }
 
 
-pub fn load_data(input_path: &String, output_path: &String) -> Vec<Sample>{
+pub fn load_data(input_path: &str, output_path: &str) -> NnResult<Vec<Sample>> {
     
-    let (mut input, x_in, y_in, z_in) = generate_data_vec(input_path);
-    let (mut output,x_out,y_out,z_out) = generate_data_vec(output_path);
+    let (mut input, _x_in, _y_in, _z_in) = generate_data_vec(input_path)?;
+    let (mut output, _x_out, _y_out, _z_out) = generate_data_vec(output_path)?;
+
+    if input.len() != output.len() {
+        let nb_inputs = input.len();
+        let nb_outputs = output.len();
 
-    assert_eq!(input.len(), output.len());
+        return Err(NnError::InvalidDataset(format!(
+            "{input_path} holds {nb_inputs} samples but {output_path} holds {nb_outputs}")));
+    }
 
-    Sample::generate_sample_vec(&mut input, &mut output)
+    Ok(Sample::generate_sample_vec(&mut input, &mut output))
 }
 
 
-fn generate_data_vec(filepath: &String) -> (Vec<Vec<f64>>, usize, usize, usize){
+fn generate_data_vec(filepath: &str) -> NnResult<(Vec<Vec<f64>>, usize, usize, usize)> {
 
-    let file: File =  File::open(filepath).expect("File {filepath} not found");
+    let file: File = File::open(filepath).map_err(|e| NnError::io(filepath, e))?;
 
     let mut reader = BufReader::new(file);
 
     let mut options_str: String = String::new();
-    reader.read_line(&mut options_str).expect("An error occured while reading the first line of the file.");
-    let options = parse_option(options_str, &filepath, &"An error occured while parsing options.".to_string());
+    reader.read_line(&mut options_str).map_err(|e| NnError::io(filepath, e))?;
+    let options = parse_option(options_str, filepath)?;
+
+    if options.len() < 4 {
+        return Err(NnError::parse(filepath, 1,
+            "expected the number of lines and the x, y, z dimensions of the data."));
+    }
 
     let nb_lines: usize = options[0];
     let x: usize = options[1];
@@ -76,48 +113,35 @@ fn generate_data_vec(filepath: &String) -> (Vec<Vec<f64>>, usize, usize, usize){
 
     let mut data_vec: Vec<Vec<f64>> = Vec::with_capacity(nb_lines);
 
-    read_data(&filepath, &mut reader, &mut data_vec, &"An error occured while reading the data.".to_string());
+    read_data(filepath, &mut reader, &mut data_vec)?;
 
-    (data_vec,x,y,z)
+    Ok((data_vec,x,y,z))
 }
 
-fn parse_option(options_str: String, filepath: &String, error_code: &String) -> Vec<usize> {
-    let iterator = options_str.trim().split(' ').map(|x| {
-        let this = x.parse::<usize>();
-        match this {
-            Ok(t) => t,
-            Err(_) => { unwrap_failed(&error_code, &filepath)},
-        }
-    } as usize);
-
-    Vec::from_iter(iterator)
+fn parse_option(options_str: String, filepath: &str) -> NnResult<Vec<usize>> {
+    options_str.trim().split(' ').map(|x| {
+        x.parse::<usize>().map_err(|e| NnError::parse(filepath, 1,
+            &format!("An error occured while parsing options: {e}")))
+    }).collect()
 }
 
-fn read_data(filepath: &String, reader: &mut BufReader<File>, vec_to_fill: &mut Vec<Vec<f64>>, error_code: &String) {
+fn read_data(filepath: &str, reader: &mut BufReader<File>, vec_to_fill: &mut Vec<Vec<f64>>) -> NnResult<()> {
 
-    for line in reader.lines() {
+    // the first line of the file holds the options
+    for (index, line) in reader.lines().enumerate() {
+        let line_nb = index + 2;
 
-        let content = Vec::from_iter(line
-            .expect(&error_code)
+        let content = line
+            .map_err(|e| NnError::io(filepath, e))?
             .trim()
             .split(' ')
             .map(|x| {
-                let this = x.parse::<f64>();
-                match this {
-                    Ok(t) => t,
-                    Err(e) => {
-                        println!("{e}");
-                        unwrap_failed(&error_code, &filepath) as f64
-                    },
-                }
-            } as f64));
+                x.parse::<f64>().map_err(|e| NnError::parse(filepath, line_nb,
+                    &format!("An error occured while reading the data: {e}")))
+            })
+            .collect::<NnResult<Vec<f64>>>()?;
         
             vec_to_fill.push(content);
     }
+    Ok(())
 } 
-
-fn unwrap_failed(error_msg: &String, filepath: &String) -> usize {
-    println!("{error_msg}");
-    println!("This error has occured while reading the file: {filepath}");
-    process::exit(1);
-}
\ No newline at end of file
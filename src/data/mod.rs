@@ -1 +1,3 @@
-pub mod create_data;
\ No newline at end of file
+pub mod class_weights;
+pub mod create_data;
+pub mod split_data;
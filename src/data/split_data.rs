@@ -0,0 +1,121 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+
+use super::create_data::Sample;
+
+// class of a desired output: the index of the hot value for one-hot outputs,
+// or 0 / 1 for a single binary output
+pub fn class_of(output: &Matrix) -> usize {
+    if output.y_length == 1 {
+        return if output.get(0, 0) >= 0.5 {1} else {0};
+    }
+    output.argmax(0)
+}
+
+// How the desired outputs hold the class of a sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Labels {
+    // one-hot outputs, or a single binary output (see class_of)
+    Encoded,
+
+    // a single output holding the index of the class, out of the given number of classes
+    // (the labels read by SparseCategoricalCrossEntropy)
+    Sparse(usize)
+}
+
+impl Labels {
+    pub fn class_of(&self, output: &Matrix) -> NnResult<usize> {
+        match *self {
+            Labels::Encoded => Ok(class_of(output)),
+            Labels::Sparse(nb_classes) => {
+                let label: f64 = output.values.first().copied().unwrap_or(f64::NAN);
+
+                if output.values.len() != 1 || label.fract() != 0.0 || label < 0.0 || label >= nb_classes as f64 {
+                    return Err(NnError::InvalidDataset(
+                        format!("{:?} is not the index of one of the {nb_classes} classes", output.values)));
+                }
+                Ok(label as usize)
+            }
+        }
+    }
+
+    // a single encoded output holds two classes (0 / 1), otherwise each output is a class
+    pub fn nb_classes(&self, output: &Matrix) -> usize {
+        match *self {
+            Labels::Encoded => if output.y_length == 1 {2} else {output.y_length},
+            Labels::Sparse(nb_classes) => nb_classes
+        }
+    }
+
+    // the desired outputs (one column per sample) as one-hot columns
+    pub fn one_hot(&self, desired_output: &Matrix) -> NnResult<Matrix> {
+        match *self {
+            Labels::Encoded => Ok(desired_output.copy()),
+            Labels::Sparse(nb_classes) => {
+                let mut encoded = Matrix::new(desired_output.x_length, nb_classes);
+
+                for x in 0..desired_output.x_length {
+                    encoded.set(self.class_of(&desired_output.column(x))?, x, 1.0);
+                }
+                Ok(encoded)
+            }
+        }
+    }
+}
+
+// errors when split_dataset would reject these samples, without taking them
+pub fn check_split(samples: &[Sample], fraction: f64, stratified: Option<Labels>) -> NnResult<()> {
+    if !(0.0..1.0).contains(&fraction) {
+        return Err(NnError::InvalidDataset(format!("cannot split off a fraction of {fraction}")));
+    }
+
+    if let Some(labels) = stratified {
+        for sample in samples {
+            labels.class_of(&sample.output)?;
+        }
+    }
+    Ok(())
+}
+
+// moves a fraction of the samples into a second set, after shuffling them
+// when stratified, the fraction is taken from each class so both sets keep the class distribution
+pub fn split_dataset<R: Rng>(samples: Vec<Sample>, fraction: f64, stratified: Option<Labels>, rng: &mut R)
+    -> NnResult<(Vec<Sample>, Vec<Sample>)> {
+
+    check_split(&samples, fraction, stratified)?;
+
+    let mut groups: Vec<Vec<Sample>> = Vec::new();
+
+    for sample in samples {
+        let group: usize = match stratified {
+            Some(labels) => labels.class_of(&sample.output)?,
+            None => 0
+        };
+
+        while groups.len() <= group {
+            groups.push(Vec::new());
+        }
+        groups[group].push(sample);
+    }
+
+    let mut kept: Vec<Sample> = Vec::new();
+    let mut split: Vec<Sample> = Vec::new();
+
+    for mut group in groups {
+        group.shuffle(rng);
+
+        let nb_split: usize = (group.len() as f64 * fraction).round() as usize;
+        let rest = group.split_off(nb_split);
+
+        split.extend(group);
+        kept.extend(rest);
+    }
+
+    kept.shuffle(rng);
+    split.shuffle(rng);
+
+    Ok((kept, split))
+}
@@ -1,7 +1,10 @@
-use crate::activations::{dense_activation, LEAKY_RELU_VALUE};
+use crate::activations::{dense_activation, SELU_ALPHA, SELU_LAMBDA};
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+use crate::maths::special_functions::{normal_cdf, normal_pdf};
 use dense_activation::DenseActivation;
 
-use std::process;
+use std::f64::consts::PI;
 
 
 pub fn d_sigmoid(x: f64) -> f64 {
@@ -12,8 +15,8 @@ pub fn d_relu(x: f64) -> f64 {
     if x < 0.0 {0.0} else {1.0}
 }
 
-pub fn d_lealy_relu(x: f64) -> f64 {
-    if x < 0.0 {0.0} else {LEAKY_RELU_VALUE}
+pub fn d_leaky_relu(x: f64, alpha: f64) -> f64 {
+    if x < 0.0 {alpha} else {1.0}
 }
 
 pub fn d_tanh(x: f64) -> f64 {
@@ -22,17 +25,148 @@ pub fn d_tanh(x: f64) -> f64 {
     1.0 - y.powi(2)
 }
 
-pub fn apply_derivation(activation: &DenseActivation, x: f64) -> f64 {
+pub fn d_gelu(x: f64) -> f64 {
+    normal_cdf(x) + x * normal_pdf(x)
+}
+
+pub fn d_gelu_tanh(x: f64) -> f64 {
+    let k: f64 = (2.0 / PI).sqrt();
+    let t: f64 = (k * (x + 0.044715 * x.powi(3))).tanh();
+
+    0.5 * (1.0 + t) + 0.5 * x * (1.0 - t * t) * k * (1.0 + 3.0 * 0.044715 * x * x)
+}
+
+pub fn d_elu(x: f64, alpha: f64) -> f64 {
+    if x > 0.0 {1.0} else {alpha * x.exp()}
+}
+
+pub fn d_selu(x: f64) -> f64 {
+    SELU_LAMBDA * if x > 0.0 {1.0} else {SELU_ALPHA * x.exp()}
+}
+
+pub fn d_swish(x: f64) -> f64 {
+    let s: f64 = dense_activation::__sigmoid(x);
+
+    s + x * s * (1.0 - s)
+}
+
+pub fn d_mish(x: f64) -> f64 {
+    let t: f64 = dense_activation::__softplus(x).tanh();
+
+    t + x * (1.0 - t * t) * dense_activation::__sigmoid(x)
+}
+
+pub fn d_softplus(x: f64) -> f64 {
+    dense_activation::__sigmoid(x)
+}
+
+pub fn d_softsign(x: f64) -> f64 {
+    1.0 / (1.0 + x.abs()).powi(2)
+}
+
+pub fn d_hard_sigmoid(x: f64) -> f64 {
+    if x > -3.0 && x < 3.0 {1.0 / 6.0} else {0.0}
+}
+
+pub fn d_hard_tanh(x: f64) -> f64 {
+    if x > -1.0 && x < 1.0 {1.0} else {0.0}
+}
+
+// element-wise derivative of the activation at x
+// softmax has none, as each of its outputs depends on the whole column: see apply_backward
+// PRelu depends on the slopes held by the model: see prelu_backward
+pub fn apply_derivation(activation: &DenseActivation, x: f64) -> NnResult<f64> {
 
     match activation {
-        DenseActivation::NoActivation =>  {
-            println!("No Activation function, exiting..."); 
-            process::exit(1);
+        DenseActivation::NoActivation | DenseActivation::Softmax | DenseActivation::PRelu =>  {
+            Err(NnError::UnsupportedActivation(activation.to_string()))
         },
-        DenseActivation::Sigmoid => d_sigmoid(x),
-        DenseActivation::Relu => d_relu(x),
-        DenseActivation::LeakyRelu => d_lealy_relu(x),
-        DenseActivation::Tanh => d_tanh(x),
-        DenseActivation::Softmax => d_sigmoid(x),
+        DenseActivation::Sigmoid => Ok(d_sigmoid(x)),
+        DenseActivation::Relu => Ok(d_relu(x)),
+        DenseActivation::LeakyRelu(alpha) => Ok(d_leaky_relu(x, *alpha)),
+        DenseActivation::Tanh => Ok(d_tanh(x)),
+        DenseActivation::Gelu => Ok(d_gelu(x)),
+        DenseActivation::GeluTanh => Ok(d_gelu_tanh(x)),
+        DenseActivation::Elu(alpha) => Ok(d_elu(x, *alpha)),
+        DenseActivation::Selu => Ok(d_selu(x)),
+        DenseActivation::Swish => Ok(d_swish(x)),
+        DenseActivation::Mish => Ok(d_mish(x)),
+        DenseActivation::Softplus => Ok(d_softplus(x)),
+        DenseActivation::Softsign => Ok(d_softsign(x)),
+        DenseActivation::HardSigmoid => Ok(d_hard_sigmoid(x)),
+        DenseActivation::HardTanh => Ok(d_hard_tanh(x)),
+        DenseActivation::Identity => Ok(1.0),
     }
-}
\ No newline at end of file
+}
+
+// vector-Jacobian product of softmax for each column:
+// with s = softmax(z) and g the gradient w.r.t. s, dL/dz = s * (g - sum(s * g))
+fn softmax_backward(values: &Matrix, gradient: &Matrix) -> Matrix {
+    let mut result = Matrix::new(values.x_length, values.y_length);
+
+    for x in 0..values.x_length {
+        let dot: f64 = (0..values.y_length).map(|y| values.get(y, x) * gradient.get(y, x)).sum();
+
+        for y in 0..values.y_length {
+            result.set(y, x, values.get(y, x) * (gradient.get(y, x) - dot));
+        }
+    }
+    result
+}
+
+// gradient w.r.t. the raw values (before activation) of a layer, given the gradient
+// w.r.t. its activated values
+pub fn apply_backward(activation: &DenseActivation, raw_values: &Matrix, values: &Matrix,
+    gradient: &Matrix) -> NnResult<Matrix> {
+
+    if gradient.x_length != values.x_length || gradient.y_length != values.y_length {
+        return Err(NnError::ShapeMismatch {
+            operation: "apply_backward",
+            left: (values.y_length, values.x_length),
+            right: (gradient.y_length, gradient.x_length)
+        });
+    }
+
+    if let DenseActivation::Softmax = activation {
+        return Ok(softmax_backward(values, gradient));
+    }
+
+    let mut result = gradient.copy();
+
+    for i in 0..result.values.len() {
+        result.values[i] *= apply_derivation(activation, raw_values.values[i])?;
+    }
+    Ok(result)
+}
+
+// PRelu counterpart of apply_backward: returns the gradient w.r.t. the raw values and the gradient
+// w.r.t. the slopes (a column vector, averaged over the samples like the other gradients)
+pub fn prelu_backward(raw_values: &Matrix, slopes: &Matrix, gradient: &Matrix) -> NnResult<(Matrix, Matrix)> {
+
+    if gradient.x_length != raw_values.x_length || gradient.y_length != raw_values.y_length
+        || slopes.x_length != 1 || slopes.y_length != raw_values.y_length {
+        return Err(NnError::ShapeMismatch {
+            operation: "prelu_backward",
+            left: (raw_values.y_length, raw_values.x_length),
+            right: (gradient.y_length, gradient.x_length)
+        });
+    }
+
+    let mut result = gradient.copy();
+    let mut d_slopes = Matrix::new(1, slopes.y_length);
+    let batch_size: f64 = raw_values.x_length.max(1) as f64;
+
+    for y in 0..raw_values.y_length {
+        for x in 0..raw_values.x_length {
+            let i: usize = y * raw_values.x_length + x;
+            let raw: f64 = raw_values.values[i];
+
+            result.values[i] *= d_leaky_relu(raw, slopes.values[y]);
+
+            if raw < 0.0 {
+                d_slopes.values[y] += gradient.values[i] * raw / batch_size;
+            }
+        }
+    }
+    Ok((result, d_slopes))
+}
@@ -0,0 +1 @@
+pub mod nn_error;
\ No newline at end of file
@@ -0,0 +1,103 @@
+use std::fmt;
+use std::io;
+
+pub type NnResult<T> = Result<T, NnError>;
+
+#[derive(Debug)]
+pub enum NnError {
+    // dimensions are given as (y_length, x_length)
+    ShapeMismatch {
+        operation: &'static str,
+        left: (usize, usize),
+        right: (usize, usize)
+    },
+    IndexOutOfRange {
+        operation: &'static str,
+        y: usize,
+        x: usize,
+        y_length: usize,
+        x_length: usize
+    },
+    // line is 1-based, 0 means the error is not tied to a specific line
+    Parse {
+        file: String,
+        line: usize,
+        message: String
+    },
+    InvalidDataset(String),
+    UnsupportedActivation(String),
+    UnsupportedLoss(String),
+    UnsupportedOptimizer(String),
+    UnsupportedInitializer(String),
+    UnsupportedRegularizer(String),
+    UnsupportedConstraint(String),
+    UnsupportedNormalization(String),
+    UnsupportedLayer(String),
+    UnsupportedClipping(String),
+    Io {
+        file: String,
+        source: io::Error
+    }
+}
+
+impl NnError {
+    pub fn io(file: &str, source: io::Error) -> NnError {
+        NnError::Io {
+            file: file.to_string(),
+            source
+        }
+    }
+
+    pub fn parse(file: &str, line: usize, message: &str) -> NnError {
+        NnError::Parse {
+            file: file.to_string(),
+            line,
+            message: message.to_string()
+        }
+    }
+}
+
+impl fmt::Display for NnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NnError::ShapeMismatch { operation, left, right } => write!(f,
+                "{operation}: shape mismatch between a {}x{} and a {}x{} matrix",
+                left.0, left.1, right.0, right.1),
+            NnError::IndexOutOfRange { operation, y, x, y_length, x_length } => write!(f,
+                "{operation}: index (y: {y}, x: {x}) out of range for a {y_length}x{x_length} matrix"),
+            NnError::Parse { file, line, message } => write!(f,
+                "{file}:{line}: {message}"),
+            NnError::InvalidDataset(message) => write!(f,
+                "invalid dataset: {message}"),
+            NnError::UnsupportedActivation(name) => write!(f,
+                "unsupported activation function: {name}"),
+            NnError::UnsupportedLoss(name) => write!(f,
+                "unsupported loss function: {name}"),
+            NnError::UnsupportedOptimizer(name) => write!(f,
+                "unsupported optimizer: {name}"),
+            NnError::UnsupportedInitializer(name) => write!(f,
+                "unsupported initializer: {name}"),
+            NnError::UnsupportedRegularizer(name) => write!(f,
+                "unsupported regularizer: {name}"),
+            NnError::UnsupportedConstraint(name) => write!(f,
+                "unsupported constraint: {name}"),
+            NnError::UnsupportedNormalization(name) => write!(f,
+                "unsupported normalization: {name}"),
+            NnError::UnsupportedLayer(name) => write!(f,
+                "unsupported layer: {name}"),
+            NnError::UnsupportedClipping(name) => write!(f,
+                "unsupported gradient clipping: {name}"),
+            NnError::Io { file, source } => write!(f,
+                "{file}: {source}")
+        }
+    }
+}
+
+impl std::error::Error for NnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NnError::Io { source, .. } => Some(source),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use rand::Rng;
+
+use crate::activations::dense_activation::DenseActivation;
+use crate::errors::nn_error::NnError;
+use crate::maths::matrices::Matrix;
+use crate::utils::parsing::parse_argument;
+
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+// fan_in is the number of inputs of a layer (x_length of its weights),
+// fan_out its number of neurons (y_length of its weights)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DenseInitializer {
+    Uniform, // [0, 1), the historical behaviour of Matrix::shuffle
+    XavierUniform,
+    XavierNormal,
+    HeUniform,
+    HeNormal,
+    LeCunUniform,
+    LeCunNormal,
+    Orthogonal,
+    Zeros,
+    Constant(f64)
+}
+
+impl DenseInitializer {
+    // sensible weights initializer for a layer followed by the given activation
+    pub fn default_for(activation: &DenseActivation) -> DenseInitializer {
+        match activation {
+            DenseActivation::Relu | DenseActivation::LeakyRelu(_) | DenseActivation::PRelu | DenseActivation::Elu(_)
+            | DenseActivation::Gelu | DenseActivation::GeluTanh | DenseActivation::Swish
+            | DenseActivation::Mish => DenseInitializer::HeUniform,
+            DenseActivation::Selu => DenseInitializer::LeCunNormal,
+            _ => DenseInitializer::XavierUniform
+        }
+    }
+
+    pub fn initialize<R: Rng>(&self, mat: &mut Matrix, fan_in: usize, fan_out: usize, rng: &mut R) {
+        let fan_in: f64 = fan_in.max(1) as f64;
+        let fan_out: f64 = fan_out.max(1) as f64;
+
+        match self {
+            DenseInitializer::Uniform => fill_uniform(mat, 0.0, 1.0, rng),
+            DenseInitializer::XavierUniform => {
+                let limit: f64 = (6.0 / (fan_in + fan_out)).sqrt();
+                fill_uniform(mat, -limit, limit, rng);
+            },
+            DenseInitializer::XavierNormal => fill_normal(mat, (2.0 / (fan_in + fan_out)).sqrt(), rng),
+            DenseInitializer::HeUniform => {
+                let limit: f64 = (6.0 / fan_in).sqrt();
+                fill_uniform(mat, -limit, limit, rng);
+            },
+            DenseInitializer::HeNormal => fill_normal(mat, (2.0 / fan_in).sqrt(), rng),
+            DenseInitializer::LeCunUniform => {
+                let limit: f64 = (3.0 / fan_in).sqrt();
+                fill_uniform(mat, -limit, limit, rng);
+            },
+            DenseInitializer::LeCunNormal => fill_normal(mat, (1.0 / fan_in).sqrt(), rng),
+            DenseInitializer::Orthogonal => fill_orthogonal(mat, rng),
+            DenseInitializer::Zeros => fill_constant(mat, 0.0),
+            DenseInitializer::Constant(value) => fill_constant(mat, *value)
+        }
+    }
+}
+
+impl fmt::Display for DenseInitializer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DenseInitializer::Uniform => write!(f, "Uniform"),
+            DenseInitializer::XavierUniform => write!(f, "XavierUniform"),
+            DenseInitializer::XavierNormal => write!(f, "XavierNormal"),
+            DenseInitializer::HeUniform => write!(f, "HeUniform"),
+            DenseInitializer::HeNormal => write!(f, "HeNormal"),
+            DenseInitializer::LeCunUniform => write!(f, "LeCunUniform"),
+            DenseInitializer::LeCunNormal => write!(f, "LeCunNormal"),
+            DenseInitializer::Orthogonal => write!(f, "Orthogonal"),
+            DenseInitializer::Zeros => write!(f, "Zeros"),
+            DenseInitializer::Constant(value) => write!(f, "Constant({value})")
+        }
+    }
+}
+
+impl FromStr for DenseInitializer {
+    type Err = NnError;
+
+    fn from_str(input: &str) -> Result<DenseInitializer, Self::Err> {
+        match input {
+            "Uniform"       => Ok(DenseInitializer::Uniform),
+            "XavierUniform" => Ok(DenseInitializer::XavierUniform),
+            "XavierNormal"  => Ok(DenseInitializer::XavierNormal),
+            "HeUniform"     => Ok(DenseInitializer::HeUniform),
+            "HeNormal"      => Ok(DenseInitializer::HeNormal),
+            "LeCunUniform"  => Ok(DenseInitializer::LeCunUniform),
+            "LeCunNormal"   => Ok(DenseInitializer::LeCunNormal),
+            "Orthogonal"    => Ok(DenseInitializer::Orthogonal),
+            "Zeros"         => Ok(DenseInitializer::Zeros),
+            _ => parse_argument(input, "Constant")
+                .map(DenseInitializer::Constant)
+                .ok_or(NnError::UnsupportedInitializer(input.to_string()))
+        }
+    }
+}
+
+fn fill_constant(mat: &mut Matrix, value: f64) {
+    for x in mat.values.iter_mut() {
+        *x = value;
+    }
+}
+
+fn fill_uniform<R: Rng>(mat: &mut Matrix, low: f64, high: f64, rng: &mut R) {
+    for x in mat.values.iter_mut() {
+        *x = low + (high - low) * rng.gen::<f64>();
+    }
+}
+
+// Box-Muller transform
+pub fn sample_normal<R: Rng>(rng: &mut R) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>(); // (0, 1], so that ln(u1) is finite
+    let u2: f64 = rng.gen::<f64>();
+
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+fn fill_normal<R: Rng>(mat: &mut Matrix, std_dev: f64, rng: &mut R) {
+    for x in mat.values.iter_mut() {
+        *x = std_dev * sample_normal(rng);
+    }
+}
+
+// orthonormal rows (or columns, whichever are fewer) obtained by Gram-Schmidt on a gaussian matrix
+fn fill_orthogonal<R: Rng>(mat: &mut Matrix, rng: &mut R) {
+    let transposed: bool = mat.y_length > mat.x_length;
+
+    // vectors to orthonormalize, stored as rows of length `length`
+    let (nb_vectors, length) = if transposed {
+        (mat.x_length, mat.y_length)
+    } else {
+        (mat.y_length, mat.x_length)
+    };
+
+    let mut vectors: Vec<Vec<f64>> = Vec::with_capacity(nb_vectors);
+
+    while vectors.len() < nb_vectors {
+        let mut vector: Vec<f64> = (0..length).map(|_| sample_normal(rng)).collect();
+
+        for other in vectors.iter() {
+            let projection: f64 = vector.iter().zip(other.iter()).map(|(a, b)| a * b).sum();
+
+            for (v, o) in vector.iter_mut().zip(other.iter()) {
+                *v -= projection * o;
+            }
+        }
+
+        let norm: f64 = vector.iter().map(|v| v * v).sum::<f64>().sqrt();
+
+        // draw again in the (unlikely) case of a degenerate vector
+        if norm > 1e-10 {
+            vectors.push(vector.iter().map(|v| v / norm).collect());
+        }
+    }
+
+    for (i, vector) in vectors.iter().enumerate() {
+        for (j, value) in vector.iter().enumerate() {
+            if transposed {
+                mat.set(j, i, *value);
+            } else {
+                mat.set(i, j, *value);
+            }
+        }
+    }
+}
@@ -0,0 +1 @@
+pub mod dense_initializer;
\ No newline at end of file
@@ -0,0 +1,74 @@
+use crate::activations::activation::Activation;
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+// Applies an activation to its input, keeping its size. Learnable activations (e.g. PRelu)
+// expose their parameters as the parameters of the layer.
+pub struct ActivationLayer {
+    activation: Box<dyn Activation>,
+    parameters: Matrix,
+    d_parameters: Matrix,
+
+    // input and output of the last forward
+    raw_values: Matrix,
+    values: Matrix
+}
+
+impl ActivationLayer {
+    pub fn new(activation: Box<dyn Activation>) -> ActivationLayer {
+        ActivationLayer {
+            activation,
+            parameters: Matrix::new(0, 0),
+            d_parameters: Matrix::new(0, 0),
+            raw_values: Matrix::new(0, 0),
+            values: Matrix::new(0, 0)
+        }
+    }
+}
+
+impl Layer for ActivationLayer {
+    fn tag(&self) -> String {
+        format!("Activation {}", self.activation.name())
+    }
+
+    fn build(&mut self, input: &DenseShape, _rng: &mut NnRng) -> NnResult<DenseShape> {
+        self.parameters = self.activation.initial_parameters(input.range);
+        Ok(*input)
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        let mut values = input.copy();
+        self.activation.forward(&mut values, &self.parameters)?;
+
+        self.raw_values = input.copy();
+        self.values = values.copy();
+        Ok(values)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        let (delta, d_parameters) = self.activation.backward(&self.raw_values, &self.values, &self.parameters, gradient)?;
+
+        self.d_parameters = d_parameters;
+        Ok(delta)
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        if self.parameters.values.is_empty() {Vec::new()} else {vec![&self.parameters]}
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        if self.parameters.values.is_empty() {Vec::new()} else {vec![&mut self.parameters]}
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        if self.parameters.values.is_empty() {Vec::new()} else {vec![self.d_parameters.copy()]}
+    }
+
+    fn activation(&self) -> Option<&dyn Activation> {
+        Some(self.activation.as_ref())
+    }
+}
@@ -0,0 +1,295 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::initializers::dense_initializer::DenseInitializer;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+// softmax(query . key^T / sqrt(key size)) . value, with one query, key and value per row (queries and keys
+// having the same size); returns the output (one row per query) and the attention weights
+// (one row per query, one column per key, each row summing to 1)
+pub fn scaled_dot_product_attention(query: &Matrix, key: &Matrix, value: &Matrix) -> NnResult<(Matrix, Matrix)> {
+    if query.x_length != key.x_length || key.y_length != value.y_length {
+        return Err(NnError::ShapeMismatch {
+            operation: "scaled_dot_product_attention",
+            left: (query.y_length, query.x_length),
+            right: (key.y_length, key.x_length)
+        });
+    }
+
+    let mut weights = Matrix::try_dot(query, &key.transpose())?;
+    weights.scale(1.0 / (query.x_length.max(1) as f64).sqrt());
+
+    for row in weights.values.chunks_mut(key.y_length.max(1)) {
+        // shifted by the maximum so that exp does not overflow
+        let maximum: f64 = row.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        row.iter_mut().for_each(|x| *x = (*x - maximum).exp());
+
+        let sum: f64 = row.iter().sum();
+        row.iter_mut().for_each(|x| *x /= sum);
+    }
+
+    Ok((Matrix::try_dot(&weights, value)?, weights))
+}
+
+// gradients w.r.t. the query, the key and the value of scaled_dot_product_attention,
+// given its attention weights and the gradient w.r.t. its output
+fn attention_backward(query: &Matrix, key: &Matrix, value: &Matrix, weights: &Matrix,
+    d_output: &Matrix) -> NnResult<(Matrix, Matrix, Matrix)> {
+
+    let d_value = Matrix::try_dot(&weights.transpose(), d_output)?;
+    let d_weights = Matrix::try_dot(d_output, &value.transpose())?;
+
+    let scale: f64 = 1.0 / (query.x_length.max(1) as f64).sqrt();
+    let mut d_scores = Matrix::new(weights.x_length, weights.y_length);
+
+    // softmax Jacobian of each row
+    for ((d_score, weight), d_weight) in d_scores.values.chunks_mut(weights.x_length.max(1))
+        .zip(weights.values.chunks(weights.x_length.max(1))).zip(d_weights.values.chunks(weights.x_length.max(1))) {
+
+        let dot: f64 = weight.iter().zip(d_weight.iter()).map(|(w, d)| w * d).sum();
+
+        for ((s, w), d) in d_score.iter_mut().zip(weight.iter()).zip(d_weight.iter()) {
+            *s = w * (d - dot) * scale;
+        }
+    }
+
+    Ok((Matrix::try_dot(&d_scores, key)?, Matrix::try_dot(&d_scores.transpose(), query)?, d_value))
+}
+
+// adds block to the rows of mat starting at the row start
+fn add_rows(mat: &mut Matrix, start: usize, block: &Matrix) {
+    let offset: usize = start * mat.x_length;
+
+    for (x, b) in mat.values[offset..offset + block.values.len()].iter_mut().zip(block.values.iter()) {
+        *x += b;
+    }
+}
+
+// kept by the forward pass for each head of each sample
+struct Head {
+    query: Matrix,
+    key: Matrix,
+    value: Matrix,
+    weights: Matrix,
+    output: Matrix
+}
+
+// Multi-head self-attention over sequences shaped (features, timesteps, 1) (see Recurrent): each head
+// projects every step to a query, a key and a value of key_size values and attends from every step to
+// every step of the same sample, then the outputs of the heads are projected back to features values per step.
+pub struct MultiHeadAttention {
+    nb_heads: usize,
+    key_size: usize,
+    initializer: DenseInitializer,
+
+    features: usize,
+    timesteps: usize,
+
+    // one row per value of each head (head after head), one column per feature
+    query_weights: Matrix,
+    key_weights: Matrix,
+    value_weights: Matrix,
+    output_weights: Matrix,
+
+    // added to each step of the output
+    output_biases: Matrix,
+
+    d_query_weights: Matrix,
+    d_key_weights: Matrix,
+    d_value_weights: Matrix,
+    d_output_weights: Matrix,
+    d_output_biases: Matrix,
+
+    // steps of each sample of the last forward (one per row), and the cache of each of their heads
+    inputs: Vec<Matrix>,
+    heads: Vec<Vec<Head>>
+}
+
+impl MultiHeadAttention {
+    // weights drawn with XavierUniform, biases starting at zero
+    pub fn new(nb_heads: usize, key_size: usize) -> MultiHeadAttention {
+        MultiHeadAttention {
+            nb_heads,
+            key_size,
+            initializer: DenseInitializer::XavierUniform,
+            features: 0,
+            timesteps: 0,
+            query_weights: Matrix::new(0, 0),
+            key_weights: Matrix::new(0, 0),
+            value_weights: Matrix::new(0, 0),
+            output_weights: Matrix::new(0, 0),
+            output_biases: Matrix::new(0, 0),
+            d_query_weights: Matrix::new(0, 0),
+            d_key_weights: Matrix::new(0, 0),
+            d_value_weights: Matrix::new(0, 0),
+            d_output_weights: Matrix::new(0, 0),
+            d_output_biases: Matrix::new(0, 0),
+            inputs: Vec::new(),
+            heads: Vec::new()
+        }
+    }
+
+    pub fn initialized(mut self, initializer: DenseInitializer) -> MultiHeadAttention {
+        self.initializer = initializer;
+        self
+    }
+
+    fn check(&self, mat: &Matrix, batch_size: Option<usize>, operation: &'static str) -> NnResult<()> {
+        let range: usize = self.features * self.timesteps;
+
+        if mat.y_length != range || batch_size.is_some_and(|batch_size| mat.x_length != batch_size) {
+            return Err(NnError::ShapeMismatch {
+                operation,
+                left: (range, batch_size.unwrap_or(1)),
+                right: (mat.y_length, mat.x_length)
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Layer for MultiHeadAttention {
+    fn tag(&self) -> String {
+        format!("MultiHeadAttention {} {}", self.nb_heads, self.key_size)
+    }
+
+    fn build(&mut self, input: &DenseShape, rng: &mut NnRng) -> NnResult<DenseShape> {
+        if input.z != 1 || input.range == 0 || self.nb_heads == 0 || self.key_size == 0 {
+            return Err(NnError::ShapeMismatch {
+                operation: "MultiHeadAttention::build",
+                left: (input.y * input.z, input.x),
+                right: (self.nb_heads, self.key_size)
+            });
+        }
+
+        self.features = input.x;
+        self.timesteps = input.y;
+
+        let nb_rows: usize = self.nb_heads * self.key_size;
+
+        for weights in [&mut self.query_weights, &mut self.key_weights, &mut self.value_weights] {
+            *weights = Matrix::new(self.features, nb_rows);
+            self.initializer.initialize(weights, self.features, nb_rows, rng);
+        }
+
+        self.output_weights = Matrix::new(self.features, nb_rows);
+        self.initializer.initialize(&mut self.output_weights, nb_rows, self.features, rng);
+
+        self.output_biases = Matrix::new(1, self.features);
+        Ok(*input)
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        self.check(input, None, "MultiHeadAttention::forward")?;
+
+        let batch_size: usize = input.x_length;
+        let mut output = Matrix::new(batch_size, input.y_length);
+
+        self.inputs = Vec::with_capacity(batch_size);
+        self.heads = Vec::with_capacity(batch_size);
+
+        for s in 0..batch_size {
+            let mut steps = Matrix::new(self.features, self.timesteps);
+            steps.values = input.column(s).values;
+
+            let mut sample_output = Matrix::new(self.features, self.timesteps);
+            let mut heads: Vec<Head> = Vec::with_capacity(self.nb_heads);
+
+            for h in 0..self.nb_heads {
+                let start: usize = h * self.key_size;
+
+                let query = Matrix::try_dot(&steps, &self.query_weights.try_rows(start, self.key_size)?.transpose())?;
+                let key = Matrix::try_dot(&steps, &self.key_weights.try_rows(start, self.key_size)?.transpose())?;
+                let value = Matrix::try_dot(&steps, &self.value_weights.try_rows(start, self.key_size)?.transpose())?;
+
+                let (head_output, weights) = scaled_dot_product_attention(&query, &key, &value)?;
+                sample_output = Matrix::try_add(&sample_output,
+                    &Matrix::try_dot(&head_output, &self.output_weights.try_rows(start, self.key_size)?)?)?;
+
+                heads.push(Head {query, key, value, weights, output: head_output});
+            }
+
+            for (r, x) in sample_output.values.iter().enumerate() {
+                output.values[r * batch_size + s] = x + self.output_biases.values[r % self.features];
+            }
+
+            self.inputs.push(steps);
+            self.heads.push(heads);
+        }
+        Ok(output)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        let batch_size: usize = self.inputs.len();
+        self.check(gradient, Some(batch_size), "MultiHeadAttention::backward")?;
+
+        let nb_rows: usize = self.nb_heads * self.key_size;
+
+        let mut d_query_weights = Matrix::new(self.features, nb_rows);
+        let mut d_key_weights = Matrix::new(self.features, nb_rows);
+        let mut d_value_weights = Matrix::new(self.features, nb_rows);
+        let mut d_output_weights = Matrix::new(self.features, nb_rows);
+        let mut d_output_biases = Matrix::new(1, self.features);
+        let mut input_gradient = Matrix::new(batch_size, gradient.y_length);
+
+        for (s, (steps, heads)) in self.inputs.iter().zip(self.heads.iter()).enumerate() {
+            let mut d_output = Matrix::new(self.features, self.timesteps);
+            d_output.values = gradient.column(s).values;
+
+            for (r, d) in d_output.values.iter().enumerate() {
+                d_output_biases.values[r % self.features] += d;
+            }
+
+            let mut d_steps = Matrix::new(self.features, self.timesteps);
+
+            for (h, head) in heads.iter().enumerate() {
+                let start: usize = h * self.key_size;
+
+                let d_head_output = Matrix::try_dot(&d_output, &self.output_weights.try_rows(start, self.key_size)?.transpose())?;
+                add_rows(&mut d_output_weights, start, &Matrix::try_dot(&head.output.transpose(), &d_output)?);
+
+                let (d_query, d_key, d_value) = attention_backward(&head.query, &head.key, &head.value,
+                    &head.weights, &d_head_output)?;
+
+                for (d_projection, weights, d_weights) in [(d_query, &self.query_weights, &mut d_query_weights),
+                    (d_key, &self.key_weights, &mut d_key_weights), (d_value, &self.value_weights, &mut d_value_weights)] {
+
+                    add_rows(d_weights, start, &Matrix::try_dot(&d_projection.transpose(), steps)?);
+                    d_steps = Matrix::try_add(&d_steps, &Matrix::try_dot(&d_projection, &weights.try_rows(start, self.key_size)?)?)?;
+                }
+            }
+
+            for (r, d) in d_steps.values.iter().enumerate() {
+                input_gradient.values[r * batch_size + s] = *d;
+            }
+        }
+
+        let scale: f64 = 1.0 / batch_size.max(1) as f64;
+
+        for d_parameter in [&mut d_query_weights, &mut d_key_weights, &mut d_value_weights, &mut d_output_weights, &mut d_output_biases] {
+            d_parameter.scale(scale);
+        }
+
+        self.d_query_weights = d_query_weights;
+        self.d_key_weights = d_key_weights;
+        self.d_value_weights = d_value_weights;
+        self.d_output_weights = d_output_weights;
+        self.d_output_biases = d_output_biases;
+        Ok(input_gradient)
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        vec![&self.query_weights, &self.key_weights, &self.value_weights, &self.output_weights, &self.output_biases]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        vec![&mut self.query_weights, &mut self.key_weights, &mut self.value_weights, &mut self.output_weights, &mut self.output_biases]
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        vec![self.d_query_weights.copy(), self.d_key_weights.copy(), self.d_value_weights.copy(),
+            self.d_output_weights.copy(), self.d_output_biases.copy()]
+    }
+}
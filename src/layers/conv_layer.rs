@@ -0,0 +1,258 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::initializers::dense_initializer::DenseInitializer;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::regularizers::dense_regularizer::DenseRegularizer;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+// 2D convolution over shaped samples (x * y values for each of the z input channels),
+// producing one output channel per filter. The kernel, stride, padding (zeros added on
+// each side) and dilation are given as (x, y).
+pub struct Conv2D {
+    nb_filters: usize,
+    kernel: (usize, usize),
+    stride: (usize, usize),
+    padding: (usize, usize),
+    dilation: (usize, usize),
+
+    weight_initializer: DenseInitializer,
+    bias_initializer: DenseInitializer,
+    regularizer: DenseRegularizer,
+
+    input_shape: DenseShape,
+    output_shape: DenseShape,
+
+    // one row per filter, holding its kernel for each input channel (channel after channel, row after row)
+    weights: Matrix,
+    biases: Matrix,
+
+    d_weights: Matrix,
+    d_biases: Matrix,
+
+    // input values seen by each output position (one column per position), for each sample of the last forward
+    columns: Vec<Matrix>
+}
+
+impl Conv2D {
+    // stride and dilation of 1, no padding, weights drawn with XavierUniform and biases starting at zero
+    pub fn new(nb_filters: usize, kernel: (usize, usize)) -> Conv2D {
+        Conv2D {
+            nb_filters,
+            kernel,
+            stride: (1, 1),
+            padding: (0, 0),
+            dilation: (1, 1),
+            weight_initializer: DenseInitializer::XavierUniform,
+            bias_initializer: DenseInitializer::Zeros,
+            regularizer: DenseRegularizer::NoRegularizer,
+            input_shape: DenseShape::new(0, 0, 0),
+            output_shape: DenseShape::new(0, 0, 0),
+            weights: Matrix::new(0, 0),
+            biases: Matrix::new(0, 0),
+            d_weights: Matrix::new(0, 0),
+            d_biases: Matrix::new(0, 0),
+            columns: Vec::new()
+        }
+    }
+
+    pub fn strided(mut self, stride: (usize, usize)) -> Conv2D {
+        self.stride = stride;
+        self
+    }
+
+    pub fn padded(mut self, padding: (usize, usize)) -> Conv2D {
+        self.padding = padding;
+        self
+    }
+
+    pub fn dilated(mut self, dilation: (usize, usize)) -> Conv2D {
+        self.dilation = dilation;
+        self
+    }
+
+    pub fn initialized(mut self, weight_initializer: DenseInitializer, bias_initializer: DenseInitializer) -> Conv2D {
+        self.weight_initializer = weight_initializer;
+        self.bias_initializer = bias_initializer;
+        self
+    }
+
+    pub fn regularized(mut self, regularizer: DenseRegularizer) -> Conv2D {
+        self.regularizer = regularizer;
+        self
+    }
+
+    fn kernel_size(&self) -> usize {
+        self.input_shape.z * self.kernel.0 * self.kernel.1
+    }
+
+    // input index read by the row r of the columns at the output position (out_x, out_y),
+    // None when it falls in the padding
+    fn input_index(&self, r: usize, out_x: usize, out_y: usize) -> Option<usize> {
+        let (kernel_x, kernel_y) = self.kernel;
+        let (channel, k_y, k_x) = (r / (kernel_x * kernel_y), (r / kernel_x) % kernel_y, r % kernel_x);
+
+        let x: usize = (out_x * self.stride.0 + k_x * self.dilation.0).checked_sub(self.padding.0)?;
+        let y: usize = (out_y * self.stride.1 + k_y * self.dilation.1).checked_sub(self.padding.1)?;
+
+        if x >= self.input_shape.x || y >= self.input_shape.y {
+            return None;
+        }
+        Some((channel * self.input_shape.y + y) * self.input_shape.x + x)
+    }
+
+    // calls f(row of the columns, output position, input index) for every input value seen by the kernel
+    fn for_each_tap(&self, mut f: impl FnMut(usize, usize, usize)) {
+        let (output_x, output_y) = (self.output_shape.x, self.output_shape.y);
+
+        for r in 0..self.kernel_size() {
+            for out_y in 0..output_y {
+                for out_x in 0..output_x {
+                    if let Some(i) = self.input_index(r, out_x, out_y) {
+                        f(r, out_y * output_x + out_x, i);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// number of positions of a (dilated) kernel along one dimension, None when it does not fit
+fn output_length(input: usize, kernel: usize, stride: usize, padding: usize, dilation: usize) -> Option<usize> {
+    let span: usize = dilation * (kernel.checked_sub(1)?) + 1;
+    let padded: usize = input + 2 * padding;
+
+    if stride == 0 || padded < span {
+        return None;
+    }
+    Some((padded - span) / stride + 1)
+}
+
+impl Layer for Conv2D {
+    fn tag(&self) -> String {
+        format!("Conv2D {} {} {} {} {} {} {} {} {} {}", self.nb_filters, self.kernel.0, self.kernel.1,
+            self.stride.0, self.stride.1, self.padding.0, self.padding.1, self.dilation.0, self.dilation.1, self.regularizer)
+    }
+
+    fn build(&mut self, input: &DenseShape, rng: &mut NnRng) -> NnResult<DenseShape> {
+        let output_x = output_length(input.x, self.kernel.0, self.stride.0, self.padding.0, self.dilation.0);
+        let output_y = output_length(input.y, self.kernel.1, self.stride.1, self.padding.1, self.dilation.1);
+
+        let (output_x, output_y) = match (output_x, output_y) {
+            (Some(x), Some(y)) => (x, y),
+            _ => return Err(NnError::ShapeMismatch {
+                operation: "Conv2D::build",
+                left: (input.y, input.x),
+                right: (self.kernel.1, self.kernel.0)
+            })
+        };
+
+        self.input_shape = *input;
+        self.output_shape = DenseShape::new(output_x, output_y, self.nb_filters);
+
+        let kernel_area: usize = self.kernel.0 * self.kernel.1;
+        let (fan_in, fan_out) = (input.z * kernel_area, self.nb_filters * kernel_area);
+
+        self.weights = Matrix::new(self.kernel_size(), self.nb_filters);
+        self.weight_initializer.initialize(&mut self.weights, fan_in, fan_out, rng);
+
+        self.biases = Matrix::new(1, self.nb_filters);
+        self.bias_initializer.initialize(&mut self.biases, fan_in, fan_out, rng);
+
+        Ok(self.output_shape)
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        if input.y_length != self.input_shape.range {
+            return Err(NnError::ShapeMismatch {
+                operation: "Conv2D::forward",
+                left: (self.input_shape.range, 1),
+                right: (input.y_length, input.x_length)
+            });
+        }
+
+        let batch_size: usize = input.x_length;
+        let positions: usize = self.output_shape.x * self.output_shape.y;
+
+        let mut output = Matrix::new(batch_size, self.output_shape.range);
+        self.columns = Vec::with_capacity(batch_size);
+
+        for s in 0..batch_size {
+            let mut columns = Matrix::new(positions, self.kernel_size());
+            self.for_each_tap(|r, p, i| columns.values[r * positions + p] = input.values[i * batch_size + s]);
+
+            let result = Matrix::try_add_column(&Matrix::try_dot(&self.weights, &columns)?, &self.biases)?;
+
+            for (o, value) in result.values.iter().enumerate() {
+                output.values[o * batch_size + s] = *value;
+            }
+            self.columns.push(columns);
+        }
+        Ok(output)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        let batch_size: usize = self.columns.len();
+
+        if gradient.x_length != batch_size || gradient.y_length != self.output_shape.range {
+            return Err(NnError::ShapeMismatch {
+                operation: "Conv2D::backward",
+                left: (self.output_shape.range, batch_size),
+                right: (gradient.y_length, gradient.x_length)
+            });
+        }
+
+        let positions: usize = self.output_shape.x * self.output_shape.y;
+
+        let mut d_weights = Matrix::new(self.kernel_size(), self.nb_filters);
+        let mut d_biases = Matrix::new(1, self.nb_filters);
+        let mut input_gradient = Matrix::new(batch_size, self.input_shape.range);
+
+        let transposed_weights = self.weights.transpose();
+
+        for (s, columns) in self.columns.iter().enumerate() {
+            let mut sample_gradient = Matrix::new(positions, self.nb_filters);
+
+            for (o, value) in sample_gradient.values.iter_mut().enumerate() {
+                *value = gradient.values[o * batch_size + s];
+            }
+
+            d_weights = Matrix::try_add(&d_weights, &Matrix::try_dot(&sample_gradient, &columns.transpose())?)?;
+
+            for (f, d_bias) in d_biases.values.iter_mut().enumerate() {
+                *d_bias += sample_gradient.values[f * positions..(f + 1) * positions].iter().sum::<f64>();
+            }
+
+            let d_columns = Matrix::try_dot(&transposed_weights, &sample_gradient)?;
+            self.for_each_tap(|r, p, i| input_gradient.values[i * batch_size + s] += d_columns.values[r * positions + p]);
+        }
+
+        d_weights.scale(1.0 / batch_size as f64);
+        d_biases.scale(1.0 / batch_size as f64);
+
+        if self.regularizer != DenseRegularizer::NoRegularizer {
+            d_weights = Matrix::try_add(&d_weights, &self.regularizer.gradient(&self.weights))?;
+        }
+
+        self.d_weights = d_weights;
+        self.d_biases = d_biases;
+        Ok(input_gradient)
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        vec![&self.weights, &self.biases]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        vec![&mut self.weights, &mut self.biases]
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        vec![self.d_weights.copy(), self.d_biases.copy()]
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        self.regularizer.penalty(&self.weights)
+    }
+}
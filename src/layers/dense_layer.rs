@@ -0,0 +1,130 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::initializers::dense_initializer::DenseInitializer;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::regularizers::dense_constraint::DenseConstraint;
+use crate::regularizers::dense_regularizer::DenseRegularizer;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+// Fully connected layer: weights . input + biases, without activation.
+// Shaped inputs are taken as flat vectors, the output being a vector of nb_outputs values.
+pub struct Dense {
+    nb_outputs: usize,
+
+    weight_initializer: DenseInitializer,
+    bias_initializer: DenseInitializer,
+    regularizer: DenseRegularizer,
+    constraint: DenseConstraint,
+
+    weights: Matrix,
+    biases: Matrix,
+
+    d_weights: Matrix,
+    d_biases: Matrix,
+
+    // input of the last forward, needed by backward
+    input: Matrix
+}
+
+impl Dense {
+    // weights drawn with XavierUniform, biases starting at zero
+    pub fn new(nb_outputs: usize) -> Dense {
+        Dense {
+            nb_outputs,
+            weight_initializer: DenseInitializer::XavierUniform,
+            bias_initializer: DenseInitializer::Zeros,
+            regularizer: DenseRegularizer::NoRegularizer,
+            constraint: DenseConstraint::NoConstraint,
+            weights: Matrix::new(0, 0),
+            biases: Matrix::new(0, 0),
+            d_weights: Matrix::new(0, 0),
+            d_biases: Matrix::new(0, 0),
+            input: Matrix::new(0, 0)
+        }
+    }
+
+    pub fn initialized(mut self, weight_initializer: DenseInitializer, bias_initializer: DenseInitializer) -> Dense {
+        self.weight_initializer = weight_initializer;
+        self.bias_initializer = bias_initializer;
+        self
+    }
+
+    pub fn regularized(mut self, regularizer: DenseRegularizer) -> Dense {
+        self.regularizer = regularizer;
+        self
+    }
+
+    pub fn constrained(mut self, constraint: DenseConstraint) -> Dense {
+        self.constraint = constraint;
+        self
+    }
+}
+
+impl Layer for Dense {
+    fn tag(&self) -> String {
+        format!("Dense {} {} {}", self.nb_outputs, self.regularizer, self.constraint)
+    }
+
+    fn build(&mut self, input: &DenseShape, rng: &mut NnRng) -> NnResult<DenseShape> {
+        self.regularizer.check()?;
+        self.constraint.check()?;
+
+        let input_size: usize = input.range;
+
+        self.weights = Matrix::new(input_size, self.nb_outputs);
+        self.weight_initializer.initialize(&mut self.weights, input_size, self.nb_outputs, rng);
+
+        self.biases = Matrix::new(1, self.nb_outputs);
+        self.bias_initializer.initialize(&mut self.biases, input_size, self.nb_outputs, rng);
+
+        self.constraint.apply(&mut self.weights);
+        Ok(DenseShape::new(self.nb_outputs, 1, 1))
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        self.input = input.copy();
+        Matrix::try_add_column(&Matrix::try_dot(&self.weights, input)?, &self.biases)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        if gradient.x_length != self.input.x_length || gradient.y_length != self.nb_outputs {
+            return Err(NnError::ShapeMismatch {
+                operation: "Dense::backward",
+                left: (self.nb_outputs, self.input.x_length),
+                right: (gradient.y_length, gradient.x_length)
+            });
+        }
+
+        self.d_weights = Matrix::try_dot(gradient, &self.input.transpose())?;
+        self.d_weights.scale(1.0 / gradient.x_length as f64);
+
+        if self.regularizer != DenseRegularizer::NoRegularizer {
+            self.d_weights = Matrix::try_add(&self.d_weights, &self.regularizer.gradient(&self.weights))?;
+        }
+        self.d_biases = gradient.mean_columns();
+
+        Matrix::try_dot(&self.weights.transpose(), gradient)
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        vec![&self.weights, &self.biases]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        vec![&mut self.weights, &mut self.biases]
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        vec![self.d_weights.copy(), self.d_biases.copy()]
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        self.regularizer.penalty(&self.weights)
+    }
+
+    fn apply_constraints(&mut self) {
+        self.constraint.apply(&mut self.weights);
+    }
+}
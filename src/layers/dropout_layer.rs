@@ -0,0 +1,50 @@
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::regularizers::dense_dropout::DenseDropout;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+// Drops its input while training (see DenseDropout), and lets it through at inference.
+pub struct Dropout {
+    dropout: DenseDropout,
+
+    // mask of the last forward, empty when nothing was dropped
+    mask: Matrix
+}
+
+impl Dropout {
+    pub fn new(dropout: DenseDropout) -> Dropout {
+        Dropout {dropout, mask: Matrix::new(0, 0)}
+    }
+}
+
+impl Layer for Dropout {
+    fn tag(&self) -> String {
+        format!("Dropout {}", self.dropout)
+    }
+
+    fn build(&mut self, input: &DenseShape, _rng: &mut NnRng) -> NnResult<DenseShape> {
+        self.dropout.check()?;
+        Ok(*input)
+    }
+
+    fn forward(&mut self, input: &Matrix, training: bool, rng: &mut NnRng) -> NnResult<Matrix> {
+        if !training || self.dropout == DenseDropout::NoDropout {
+            self.mask = Matrix::new(0, 0);
+            return Ok(input.copy());
+        }
+
+        let (dropped, mask) = self.dropout.apply(input, rng);
+        self.mask = mask;
+        Ok(dropped)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        if self.mask.values.is_empty() {
+            return Ok(gradient.copy());
+        }
+        Matrix::try_hadamard(gradient, &self.mask)
+    }
+}
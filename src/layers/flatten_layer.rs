@@ -0,0 +1,35 @@
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+// Turns shaped samples into vectors. Samples are always stored as flat columns (see DenseShape),
+// so only the shape changes, the values are left as they are.
+#[derive(Default)]
+pub struct Flatten;
+
+impl Flatten {
+    pub fn new() -> Flatten {
+        Flatten
+    }
+}
+
+impl Layer for Flatten {
+    fn tag(&self) -> String {
+        "Flatten".to_string()
+    }
+
+    fn build(&mut self, input: &DenseShape, _rng: &mut NnRng) -> NnResult<DenseShape> {
+        Ok(DenseShape::new(input.range, 1, 1))
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        Ok(input.copy())
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        Ok(gradient.copy())
+    }
+}
@@ -0,0 +1,61 @@
+use crate::activations::activation::Activation;
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::shapes::dense_shape::DenseShape;
+
+// A building block of a Sequential model. Every matrix holds one sample per column.
+// Implement it to add a new kind of layer, and register its tag in a Registry so that
+// models using it can be loaded back.
+pub trait Layer {
+    // line written in the .arch file: a name without whitespace, optionally followed by
+    // whitespace-separated arguments (e.g. "Dense 4 L2(0.01) NoConstraint")
+    fn tag(&self) -> String;
+
+    // called once by the Sequential model, with the shape of each input sample, to allocate and
+    // initialize the parameters; returns the shape of each output sample
+    fn build(&mut self, input: &DenseShape, rng: &mut NnRng) -> NnResult<DenseShape>;
+
+    // training is false at inference (see Sequential::set_training)
+    fn forward(&mut self, input: &Matrix, training: bool, rng: &mut NnRng) -> NnResult<Matrix>;
+
+    // given the gradient w.r.t. the output of the last forward (one column per sample), stores the
+    // gradients of the parameters (averaged over the samples) and returns the gradient w.r.t. the input
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix>;
+
+    // learnable parameters, always in the same order, and the gradients computed by the last backward
+    fn parameters(&self) -> Vec<&Matrix> {
+        Vec::new()
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        Vec::new()
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        Vec::new()
+    }
+
+    // values updated by the forward passes while training rather than by the optimizer (the running mean
+    // and variance of BatchNorm), saved along with the parameters
+    fn running_statistics(&self) -> Vec<&Matrix> {
+        Vec::new()
+    }
+
+    fn running_statistics_mut(&mut self) -> Vec<&mut Matrix> {
+        Vec::new()
+    }
+
+    // penalty added to the loss, its gradient being included in the gradients
+    fn regularization_loss(&self) -> f64 {
+        0.0
+    }
+
+    // called after each update of the parameters
+    fn apply_constraints(&mut self) {}
+
+    // the activation of an activation layer (used for the fused softmax / cross-entropy gradient)
+    fn activation(&self) -> Option<&dyn Activation> {
+        None
+    }
+}
@@ -0,0 +1,12 @@
+pub mod activation_layer;
+pub mod attention_layer;
+pub mod conv_layer;
+pub mod dense_layer;
+pub mod dropout_layer;
+pub mod flatten_layer;
+pub mod layer;
+pub mod normalization_layer;
+pub mod pooling_layer;
+pub mod positional_layer;
+pub mod recurrent_layer;
+pub mod transformer_layer;
@@ -0,0 +1,89 @@
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::normalizations::dense_normalization::{DenseNormalization, NormalizationCache};
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+// Normalizes its input (see DenseNormalization), then scales and shifts it by the learnable gamma and beta
+// of each value. Placed between a Dense layer and its activation, it normalizes the layer as DenseModel does.
+pub struct Normalization {
+    normalization: DenseNormalization,
+
+    // gamma (first row) and beta (second row), empty without normalization
+    parameters: Matrix,
+    d_parameters: Matrix,
+
+    // running mean (first row) and variance (second row) of BatchNorm, empty otherwise
+    running_statistics: Matrix,
+
+    // kept by the last forward for backward
+    cache: Option<NormalizationCache>
+}
+
+impl Normalization {
+    pub fn new(normalization: DenseNormalization) -> Normalization {
+        Normalization {
+            normalization,
+            parameters: Matrix::new(0, 0),
+            d_parameters: Matrix::new(0, 0),
+            running_statistics: Matrix::new(0, 0),
+            cache: None
+        }
+    }
+}
+
+impl Layer for Normalization {
+    fn tag(&self) -> String {
+        format!("Normalization {}", self.normalization)
+    }
+
+    fn build(&mut self, input: &DenseShape, _rng: &mut NnRng) -> NnResult<DenseShape> {
+        self.parameters = self.normalization.initial_parameters(input.range);
+        self.running_statistics = self.normalization.initial_statistics(input.range);
+        Ok(*input)
+    }
+
+    fn forward(&mut self, input: &Matrix, training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        if self.normalization == DenseNormalization::NoNormalization {
+            self.cache = None;
+            return Ok(input.copy());
+        }
+
+        let mut values = input.copy();
+        self.cache = Some(self.normalization.forward(&mut values, &self.parameters, &mut self.running_statistics, training)?);
+        Ok(values)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        match &self.cache {
+            Some(cache) => {
+                let (delta, d_parameters) = self.normalization.backward(cache, &self.parameters, gradient)?;
+                self.d_parameters = d_parameters;
+                Ok(delta)
+            },
+            None => Ok(gradient.copy())
+        }
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        if self.parameters.values.is_empty() {Vec::new()} else {vec![&self.parameters]}
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        if self.parameters.values.is_empty() {Vec::new()} else {vec![&mut self.parameters]}
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        if self.parameters.values.is_empty() {Vec::new()} else {vec![self.d_parameters.copy()]}
+    }
+
+    fn running_statistics(&self) -> Vec<&Matrix> {
+        if self.running_statistics.values.is_empty() {Vec::new()} else {vec![&self.running_statistics]}
+    }
+
+    fn running_statistics_mut(&mut self) -> Vec<&mut Matrix> {
+        if self.running_statistics.values.is_empty() {Vec::new()} else {vec![&mut self.running_statistics]}
+    }
+}
@@ -0,0 +1,236 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+// Geometry shared by the pooling layers: windows of pool values (x, y) moved by stride over
+// each channel, without padding.
+#[derive(Clone, Copy)]
+struct Window {
+    pool: (usize, usize),
+    stride: (usize, usize),
+    input: DenseShape,
+    output: DenseShape
+}
+
+impl Window {
+    fn new(pool: (usize, usize), stride: (usize, usize)) -> Window {
+        Window {pool, stride, input: DenseShape::new(0, 0, 0), output: DenseShape::new(0, 0, 0)}
+    }
+
+    fn build(&mut self, input: &DenseShape, operation: &'static str) -> NnResult<DenseShape> {
+        let (pool_x, pool_y) = self.pool;
+        let (stride_x, stride_y) = self.stride;
+
+        if pool_x == 0 || pool_y == 0 || stride_x == 0 || stride_y == 0 || pool_x > input.x || pool_y > input.y {
+            return Err(NnError::ShapeMismatch {
+                operation,
+                left: (input.y, input.x),
+                right: (pool_y, pool_x)
+            });
+        }
+
+        self.input = *input;
+        self.output = DenseShape::new((input.x - pool_x) / stride_x + 1, (input.y - pool_y) / stride_y + 1, input.z);
+        Ok(self.output)
+    }
+
+    // input indices of the window pooled into the output index o
+    fn indices(&self, o: usize) -> impl Iterator<Item = usize> + '_ {
+        let (out_x, out_y, channel) = (o % self.output.x, (o / self.output.x) % self.output.y, o / (self.output.x * self.output.y));
+        let (start_x, start_y) = (out_x * self.stride.0, out_y * self.stride.1);
+
+        (0..self.pool.1).flat_map(move |j| (0..self.pool.0)
+            .map(move |i| (channel * self.input.y + start_y + j) * self.input.x + start_x + i))
+    }
+
+    fn check(&self, mat: &Matrix, shape: &DenseShape, operation: &'static str) -> NnResult<()> {
+        if mat.y_length != shape.range {
+            return Err(NnError::ShapeMismatch {
+                operation,
+                left: (shape.range, 1),
+                right: (mat.y_length, mat.x_length)
+            });
+        }
+        Ok(())
+    }
+}
+
+// Keeps the maximum of each window (the stride defaults to the pool size).
+pub struct MaxPool2D {
+    window: Window,
+
+    // input index of the maximum of each output value of the last forward (same layout as the output)
+    maxima: Vec<usize>
+}
+
+impl MaxPool2D {
+    pub fn new(pool: (usize, usize)) -> MaxPool2D {
+        MaxPool2D {window: Window::new(pool, pool), maxima: Vec::new()}
+    }
+
+    pub fn strided(mut self, stride: (usize, usize)) -> MaxPool2D {
+        self.window.stride = stride;
+        self
+    }
+}
+
+impl Layer for MaxPool2D {
+    fn tag(&self) -> String {
+        let Window {pool, stride, ..} = self.window;
+        format!("MaxPool2D {} {} {} {}", pool.0, pool.1, stride.0, stride.1)
+    }
+
+    fn build(&mut self, input: &DenseShape, _rng: &mut NnRng) -> NnResult<DenseShape> {
+        self.window.build(input, "MaxPool2D::build")
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        self.window.check(input, &self.window.input, "MaxPool2D::forward")?;
+
+        let batch_size: usize = input.x_length;
+        let mut output = Matrix::new(batch_size, self.window.output.range);
+        self.maxima = vec![0; output.values.len()];
+
+        for o in 0..self.window.output.range {
+            for s in 0..batch_size {
+                let maximum: usize = self.window.indices(o)
+                    .max_by(|a, b| input.values[a * batch_size + s].total_cmp(&input.values[b * batch_size + s]))
+                    .unwrap_or(0);
+
+                self.maxima[o * batch_size + s] = maximum;
+                output.values[o * batch_size + s] = input.values[maximum * batch_size + s];
+            }
+        }
+        Ok(output)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        // the maxima were kept for the batch of the last forward
+        if gradient.y_length != self.window.output.range || gradient.values.len() != self.maxima.len() {
+            return Err(NnError::ShapeMismatch {
+                operation: "MaxPool2D::backward",
+                left: (self.window.output.range, self.maxima.len() / self.window.output.range.max(1)),
+                right: (gradient.y_length, gradient.x_length)
+            });
+        }
+
+        let batch_size: usize = gradient.x_length;
+        let mut input_gradient = Matrix::new(batch_size, self.window.input.range);
+
+        // only the maximum of each window reached the output
+        for (j, g) in gradient.values.iter().enumerate() {
+            input_gradient.values[self.maxima[j] * batch_size + j % batch_size] += g;
+        }
+        Ok(input_gradient)
+    }
+}
+
+// Keeps the mean of each window (the stride defaults to the pool size).
+pub struct AveragePool2D {
+    window: Window
+}
+
+impl AveragePool2D {
+    pub fn new(pool: (usize, usize)) -> AveragePool2D {
+        AveragePool2D {window: Window::new(pool, pool)}
+    }
+
+    pub fn strided(mut self, stride: (usize, usize)) -> AveragePool2D {
+        self.window.stride = stride;
+        self
+    }
+}
+
+impl Layer for AveragePool2D {
+    fn tag(&self) -> String {
+        let Window {pool, stride, ..} = self.window;
+        format!("AveragePool2D {} {} {} {}", pool.0, pool.1, stride.0, stride.1)
+    }
+
+    fn build(&mut self, input: &DenseShape, _rng: &mut NnRng) -> NnResult<DenseShape> {
+        self.window.build(input, "AveragePool2D::build")
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        average_forward(&self.window, input, "AveragePool2D::forward")
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        average_backward(&self.window, gradient, "AveragePool2D::backward")
+    }
+}
+
+// Averages each channel over all its values, giving a vector of one value per channel (as a 1 * 1 * z shape).
+pub struct GlobalAveragePooling {
+    // covers the whole input once built
+    window: Window
+}
+
+impl GlobalAveragePooling {
+    pub fn new() -> GlobalAveragePooling {
+        GlobalAveragePooling {window: Window::new((0, 0), (0, 0))}
+    }
+}
+
+impl Default for GlobalAveragePooling {
+    fn default() -> GlobalAveragePooling {
+        GlobalAveragePooling::new()
+    }
+}
+
+impl Layer for GlobalAveragePooling {
+    fn tag(&self) -> String {
+        "GlobalAveragePooling".to_string()
+    }
+
+    fn build(&mut self, input: &DenseShape, _rng: &mut NnRng) -> NnResult<DenseShape> {
+        self.window = Window::new((input.x, input.y), (input.x, input.y));
+        self.window.build(input, "GlobalAveragePooling::build")
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        average_forward(&self.window, input, "GlobalAveragePooling::forward")
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        average_backward(&self.window, gradient, "GlobalAveragePooling::backward")
+    }
+}
+
+fn average_forward(window: &Window, input: &Matrix, operation: &'static str) -> NnResult<Matrix> {
+    window.check(input, &window.input, operation)?;
+
+    let batch_size: usize = input.x_length;
+    let area: f64 = (window.pool.0 * window.pool.1) as f64;
+    let mut output = Matrix::new(batch_size, window.output.range);
+
+    for o in 0..window.output.range {
+        for s in 0..batch_size {
+            output.values[o * batch_size + s] = window.indices(o).map(|i| input.values[i * batch_size + s]).sum::<f64>() / area;
+        }
+    }
+    Ok(output)
+}
+
+// each value of a window gets an equal share of the gradient of its mean
+fn average_backward(window: &Window, gradient: &Matrix, operation: &'static str) -> NnResult<Matrix> {
+    window.check(gradient, &window.output, operation)?;
+
+    let batch_size: usize = gradient.x_length;
+    let area: f64 = (window.pool.0 * window.pool.1) as f64;
+    let mut input_gradient = Matrix::new(batch_size, window.input.range);
+
+    for o in 0..window.output.range {
+        for s in 0..batch_size {
+            let share: f64 = gradient.values[o * batch_size + s] / area;
+
+            for i in window.indices(o) {
+                input_gradient.values[i * batch_size + s] += share;
+            }
+        }
+    }
+    Ok(input_gradient)
+}
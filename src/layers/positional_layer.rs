@@ -0,0 +1,78 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+// Adds to each step of a sequence shaped (features, timesteps, 1) a vector encoding its position,
+// so that attention, which sees the steps as an unordered set, can tell them apart.
+pub struct PositionalEncoding {
+    // learned encodings start from the sinusoidal ones and are parameters of the layer
+    learned: bool,
+
+    // one value per feature of each step, added to every sample
+    encoding: Matrix,
+    d_encoding: Matrix
+}
+
+impl PositionalEncoding {
+    // sin(t / 10000^(i / features)) for the even features i of the step t,
+    // and cos(t / 10000^((i - 1) / features)) for the odd ones (Vaswani et al., 2017)
+    pub fn sinusoidal() -> PositionalEncoding {
+        PositionalEncoding {learned: false, encoding: Matrix::new(0, 0), d_encoding: Matrix::new(0, 0)}
+    }
+
+    pub fn learned() -> PositionalEncoding {
+        PositionalEncoding {learned: true, ..PositionalEncoding::sinusoidal()}
+    }
+}
+
+impl Layer for PositionalEncoding {
+    fn tag(&self) -> String {
+        format!("PositionalEncoding {}", if self.learned {"Learned"} else {"Sinusoidal"})
+    }
+
+    fn build(&mut self, input: &DenseShape, _rng: &mut NnRng) -> NnResult<DenseShape> {
+        if input.z != 1 {
+            return Err(NnError::ShapeMismatch {
+                operation: "PositionalEncoding::build",
+                left: (input.y * input.z, input.x),
+                right: (input.y, input.x)
+            });
+        }
+
+        self.encoding = Matrix::new(1, input.range);
+
+        for (k, x) in self.encoding.values.iter_mut().enumerate() {
+            let (t, i) = (k / input.x, k % input.x);
+            let angle: f64 = t as f64 / 10000f64.powf((i - i % 2) as f64 / input.x as f64);
+
+            *x = if i % 2 == 0 {angle.sin()} else {angle.cos()};
+        }
+        Ok(*input)
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        Matrix::try_add_column(input, &self.encoding)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        if self.learned {
+            self.d_encoding = gradient.mean_columns();
+        }
+        Ok(gradient.copy())
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        if self.learned {vec![&self.encoding]} else {Vec::new()}
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        if self.learned {vec![&mut self.encoding]} else {Vec::new()}
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        if self.learned {vec![self.d_encoding.copy()]} else {Vec::new()}
+    }
+}
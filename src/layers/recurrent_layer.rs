@@ -0,0 +1,525 @@
+use crate::activations::dense_activation::{__sigmoid, __tanh};
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::initializers::dense_initializer::DenseInitializer;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::regularizers::dense_regularizer::DenseRegularizer;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::layer::Layer;
+
+use std::fmt;
+use std::str::FromStr;
+
+// The parameters of every cell are stacked gate after gate (one row per unit and gate).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RecurrentCell {
+    // h = tanh(W x + U h + b)
+    SimpleRnn,
+
+    // input, forget, candidate and output gates (Hochreiter & Schmidhuber, 1997):
+    // c = f * c + i * g and h = o * tanh(c), the forget gate biases starting at 1
+    Lstm,
+
+    // update and reset gates (Cho et al., 2014): h = (1 - z) * h + z * tanh(W x + U (r * h) + b)
+    Gru
+}
+
+impl RecurrentCell {
+    fn nb_gates(&self) -> usize {
+        match self {
+            RecurrentCell::SimpleRnn => 1,
+            RecurrentCell::Lstm => 4,
+            RecurrentCell::Gru => 3
+        }
+    }
+}
+
+impl fmt::Display for RecurrentCell {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecurrentCell::SimpleRnn => write!(f, "SimpleRNN"),
+            RecurrentCell::Lstm => write!(f, "LSTM"),
+            RecurrentCell::Gru => write!(f, "GRU")
+        }
+    }
+}
+
+impl FromStr for RecurrentCell {
+    type Err = NnError;
+
+    fn from_str(input: &str) -> Result<RecurrentCell, Self::Err> {
+        match input {
+            "SimpleRNN" => Ok(RecurrentCell::SimpleRnn),
+            "LSTM" => Ok(RecurrentCell::Lstm),
+            "GRU" => Ok(RecurrentCell::Gru),
+            _ => Err(NnError::UnsupportedLayer(input.to_string()))
+        }
+    }
+}
+
+// kept by the forward pass for each step
+struct Step {
+    input: Matrix,
+
+    // state (and LSTM memory) before the step
+    state: Matrix,
+    memory: Matrix,
+
+    // activated gates, and tanh of the LSTM memory after the step
+    gates: Matrix,
+    memory_tanh: Matrix,
+
+    // samples for which the step was skipped
+    masked: Vec<bool>
+}
+
+// Recurrent layer over sequences shaped (features, timesteps, 1): the step t of a sample is made of
+// the values t * features to (t + 1) * features - 1 of its column. The state starts at zero for every sequence.
+pub struct Recurrent {
+    cell: RecurrentCell,
+    units: usize,
+
+    // outputs the state after every step, shaped (units, timesteps, 1), instead of the last state only
+    return_sequences: bool,
+
+    // truncated backpropagation through time: the steps are split into windows of that many steps ending
+    // at the last one, and the gradient is not carried from a window to the previous one (0 for full BPTT)
+    truncation: usize,
+
+    // steps whose features all equal the mask value (e.g. the padding added by Sample::sequence) are skipped,
+    // the state being carried over; when returning sequences, their output is the mask value so that
+    // a following recurrent layer masked with the same value skips them too
+    mask: Option<f64>,
+
+    input_initializer: DenseInitializer,
+    recurrent_initializer: DenseInitializer,
+    regularizer: DenseRegularizer,
+
+    features: usize,
+    timesteps: usize,
+
+    kernel: Matrix,
+    recurrent_kernel: Matrix,
+    biases: Matrix,
+
+    d_kernel: Matrix,
+    d_recurrent_kernel: Matrix,
+    d_biases: Matrix,
+
+    steps: Vec<Step>
+}
+
+impl Recurrent {
+    // returns the last state, with full BPTT, input weights drawn with XavierUniform and
+    // recurrent ones with Orthogonal
+    pub fn new(cell: RecurrentCell, units: usize) -> Recurrent {
+        Recurrent {
+            cell,
+            units,
+            return_sequences: false,
+            truncation: 0,
+            mask: None,
+            input_initializer: DenseInitializer::XavierUniform,
+            recurrent_initializer: DenseInitializer::Orthogonal,
+            regularizer: DenseRegularizer::NoRegularizer,
+            features: 0,
+            timesteps: 0,
+            kernel: Matrix::new(0, 0),
+            recurrent_kernel: Matrix::new(0, 0),
+            biases: Matrix::new(0, 0),
+            d_kernel: Matrix::new(0, 0),
+            d_recurrent_kernel: Matrix::new(0, 0),
+            d_biases: Matrix::new(0, 0),
+            steps: Vec::new()
+        }
+    }
+
+    pub fn returning_sequences(mut self) -> Recurrent {
+        self.return_sequences = true;
+        self
+    }
+
+    pub fn truncated(mut self, truncation: usize) -> Recurrent {
+        self.truncation = truncation;
+        self
+    }
+
+    pub fn masked(mut self, mask: f64) -> Recurrent {
+        self.mask = Some(mask);
+        self
+    }
+
+    pub fn initialized(mut self, input_initializer: DenseInitializer, recurrent_initializer: DenseInitializer) -> Recurrent {
+        self.input_initializer = input_initializer;
+        self.recurrent_initializer = recurrent_initializer;
+        self
+    }
+
+    // penalizes the input and the recurrent weights
+    pub fn regularized(mut self, regularizer: DenseRegularizer) -> Recurrent {
+        self.regularizer = regularizer;
+        self
+    }
+
+    fn output_size(&self) -> usize {
+        if self.return_sequences {self.units * self.timesteps} else {self.units}
+    }
+
+    // new state, new LSTM memory, activated gates and tanh of the new memory,
+    // for a step of every sample (one per column)
+    fn cell_forward(&self, input: &Matrix, state: &Matrix, memory: &Matrix) -> NnResult<(Matrix, Matrix, Matrix, Matrix)> {
+        let batch_size: usize = input.x_length;
+        let size: usize = self.units * batch_size;
+
+        let mut gates = Matrix::try_add_column(&Matrix::try_dot(&self.kernel, input)?, &self.biases)?;
+        let mut new_state = Matrix::new(batch_size, self.units);
+
+        match self.cell {
+            RecurrentCell::SimpleRnn => {
+                gates = Matrix::try_add(&gates, &Matrix::try_dot(&self.recurrent_kernel, state)?)?;
+                gates.values.iter_mut().for_each(|x| *x = __tanh(*x));
+                new_state.values.copy_from_slice(&gates.values);
+
+                Ok((new_state, memory.copy(), gates, Matrix::new(0, 0)))
+            },
+            RecurrentCell::Lstm => {
+                gates = Matrix::try_add(&gates, &Matrix::try_dot(&self.recurrent_kernel, state)?)?;
+
+                for (k, x) in gates.values.iter_mut().enumerate() {
+                    *x = if k / size == 2 {__tanh(*x)} else {__sigmoid(*x)};
+                }
+
+                let mut new_memory = Matrix::new(batch_size, self.units);
+                let mut memory_tanh = Matrix::new(batch_size, self.units);
+
+                for k in 0..size {
+                    let (i, f, g, o) = (gates.values[k], gates.values[size + k], gates.values[2 * size + k], gates.values[3 * size + k]);
+
+                    new_memory.values[k] = f * memory.values[k] + i * g;
+                    memory_tanh.values[k] = __tanh(new_memory.values[k]);
+                    new_state.values[k] = o * memory_tanh.values[k];
+                }
+                Ok((new_state, new_memory, gates, memory_tanh))
+            },
+            RecurrentCell::Gru => {
+                let recurrent = Matrix::try_dot(&self.recurrent_kernel.try_rows(0, 2 * self.units)?, state)?;
+
+                for k in 0..2 * size {
+                    gates.values[k] = __sigmoid(gates.values[k] + recurrent.values[k]);
+                }
+
+                let reset_state = reset_state(&gates, state);
+                let candidate = Matrix::try_dot(&self.recurrent_kernel.try_rows(2 * self.units, self.units)?, &reset_state)?;
+
+                for k in 0..size {
+                    let z: f64 = gates.values[k];
+                    let h: f64 = __tanh(gates.values[2 * size + k] + candidate.values[k]);
+
+                    gates.values[2 * size + k] = h;
+                    new_state.values[k] = (1.0 - z) * state.values[k] + z * h;
+                }
+                Ok((new_state, memory.copy(), gates, Matrix::new(0, 0)))
+            }
+        }
+    }
+
+    // given the gradients w.r.t. the state and the LSTM memory after the step, returns the gradients
+    // w.r.t. the gates before their activation and w.r.t. the state and the memory before the step,
+    // adding the gradient of the recurrent weights to d_recurrent_kernel
+    fn cell_backward(&self, step: &Step, d_state: &Matrix, d_memory: &Matrix,
+        d_recurrent_kernel: &mut Matrix) -> NnResult<(Matrix, Matrix, Matrix)> {
+
+        let batch_size: usize = d_state.x_length;
+        let size: usize = self.units * batch_size;
+        let gates: &[f64] = &step.gates.values;
+
+        let mut d_gates = Matrix::new(batch_size, self.units * self.cell.nb_gates());
+
+        let (d_previous_state, d_previous_memory) = match self.cell {
+            RecurrentCell::SimpleRnn => {
+                for ((d, g), h) in d_gates.values.iter_mut().zip(d_state.values.iter()).zip(gates.iter()) {
+                    *d = g * (1.0 - h * h);
+                }
+                *d_recurrent_kernel = Matrix::try_add(d_recurrent_kernel, &Matrix::try_dot(&d_gates, &step.state.transpose())?)?;
+
+                (Matrix::try_dot(&self.recurrent_kernel.transpose(), &d_gates)?, d_memory.copy())
+            },
+            RecurrentCell::Lstm => {
+                let mut d_previous_memory = Matrix::new(batch_size, self.units);
+
+                for k in 0..size {
+                    let (i, f, g, o) = (gates[k], gates[size + k], gates[2 * size + k], gates[3 * size + k]);
+                    let memory_tanh: f64 = step.memory_tanh.values[k];
+                    let d_new_memory: f64 = d_memory.values[k] + d_state.values[k] * o * (1.0 - memory_tanh * memory_tanh);
+
+                    d_gates.values[k] = d_new_memory * g * i * (1.0 - i);
+                    d_gates.values[size + k] = d_new_memory * step.memory.values[k] * f * (1.0 - f);
+                    d_gates.values[2 * size + k] = d_new_memory * i * (1.0 - g * g);
+                    d_gates.values[3 * size + k] = d_state.values[k] * memory_tanh * o * (1.0 - o);
+                    d_previous_memory.values[k] = d_new_memory * f;
+                }
+                *d_recurrent_kernel = Matrix::try_add(d_recurrent_kernel, &Matrix::try_dot(&d_gates, &step.state.transpose())?)?;
+
+                (Matrix::try_dot(&self.recurrent_kernel.transpose(), &d_gates)?, d_previous_memory)
+            },
+            RecurrentCell::Gru => {
+                let mut d_previous_state = Matrix::new(batch_size, self.units);
+
+                for k in 0..size {
+                    let (z, h) = (gates[k], gates[2 * size + k]);
+
+                    d_gates.values[k] = d_state.values[k] * (h - step.state.values[k]) * z * (1.0 - z);
+                    d_gates.values[2 * size + k] = d_state.values[k] * z * (1.0 - h * h);
+                    d_previous_state.values[k] = d_state.values[k] * (1.0 - z);
+                }
+
+                // the candidate sees the state through the reset gate
+                let d_candidate = d_gates.try_rows(2 * self.units, self.units)?;
+                let candidate_kernel = self.recurrent_kernel.try_rows(2 * self.units, self.units)?;
+                let d_reset_state = Matrix::try_dot(&candidate_kernel.transpose(), &d_candidate)?;
+
+                for k in 0..size {
+                    let r: f64 = gates[size + k];
+
+                    d_gates.values[size + k] = d_reset_state.values[k] * step.state.values[k] * r * (1.0 - r);
+                    d_previous_state.values[k] += d_reset_state.values[k] * r;
+                }
+
+                let d_update_reset = d_gates.try_rows(0, 2 * self.units)?;
+                let update_reset_kernel = self.recurrent_kernel.try_rows(0, 2 * self.units)?;
+                d_previous_state = Matrix::try_add(&d_previous_state, &Matrix::try_dot(&update_reset_kernel.transpose(), &d_update_reset)?)?;
+
+                let d_kernel: Matrix = stack(&Matrix::try_dot(&d_update_reset, &step.state.transpose())?,
+                    &Matrix::try_dot(&d_candidate, &reset_state(&step.gates, &step.state).transpose())?);
+                *d_recurrent_kernel = Matrix::try_add(d_recurrent_kernel, &d_kernel)?;
+
+                (d_previous_state, d_memory.copy())
+            }
+        };
+        Ok((d_gates, d_previous_state, d_previous_memory))
+    }
+}
+
+// the rows of top followed by the rows of bottom
+fn stack(top: &Matrix, bottom: &Matrix) -> Matrix {
+    let mut result = Matrix::new(top.x_length, top.y_length + bottom.y_length);
+    result.values = top.values.iter().chain(bottom.values.iter()).copied().collect();
+    result
+}
+
+// r * h, the state seen by the candidate of a GRU
+fn reset_state(gates: &Matrix, state: &Matrix) -> Matrix {
+    let size: usize = state.values.len();
+    let mut result = Matrix::new(state.x_length, state.y_length);
+
+    for (k, x) in result.values.iter_mut().enumerate() {
+        *x = gates.values[size + k] * state.values[k];
+    }
+    result
+}
+
+// copies the columns of the masked samples from source into mat
+fn keep_masked(mat: &mut Matrix, source: &Matrix, masked: &[bool]) {
+    for (k, x) in mat.values.iter_mut().enumerate() {
+        if masked[k % masked.len()] {
+            *x = source.values[k];
+        }
+    }
+}
+
+fn clear_masked(mat: &mut Matrix, masked: &[bool]) {
+    for (k, x) in mat.values.iter_mut().enumerate() {
+        if masked[k % masked.len()] {
+            *x = 0.0;
+        }
+    }
+}
+
+impl Layer for Recurrent {
+    fn tag(&self) -> String {
+        let mask: String = self.mask.map_or("NoMask".to_string(), |mask| mask.to_string());
+
+        format!("{} {} {} {} {} {}", self.cell, self.units, if self.return_sequences {"Sequences"} else {"Last"},
+            self.truncation, mask, self.regularizer)
+    }
+
+    fn build(&mut self, input: &DenseShape, rng: &mut NnRng) -> NnResult<DenseShape> {
+        if input.z != 1 || input.range == 0 || self.units == 0 {
+            return Err(NnError::ShapeMismatch {
+                operation: "Recurrent::build",
+                left: (input.y * input.z, input.x),
+                right: (input.y, input.x)
+            });
+        }
+
+        self.features = input.x;
+        self.timesteps = input.y;
+
+        let nb_rows: usize = self.units * self.cell.nb_gates();
+
+        self.kernel = Matrix::new(self.features, nb_rows);
+        self.input_initializer.initialize(&mut self.kernel, self.features, self.units, rng);
+
+        self.recurrent_kernel = Matrix::new(self.units, nb_rows);
+        self.recurrent_initializer.initialize(&mut self.recurrent_kernel, self.units, self.units, rng);
+
+        self.biases = Matrix::new(1, nb_rows);
+
+        // remembers everything at first
+        if self.cell == RecurrentCell::Lstm {
+            self.biases.values[self.units..2 * self.units].iter_mut().for_each(|b| *b = 1.0);
+        }
+
+        if self.return_sequences {
+            Ok(DenseShape::new(self.units, self.timesteps, 1))
+        } else {
+            Ok(DenseShape::new(self.units, 1, 1))
+        }
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        if input.y_length != self.features * self.timesteps {
+            return Err(NnError::ShapeMismatch {
+                operation: "Recurrent::forward",
+                left: (self.features * self.timesteps, 1),
+                right: (input.y_length, input.x_length)
+            });
+        }
+
+        let batch_size: usize = input.x_length;
+        let size: usize = self.units * batch_size;
+
+        let mut state = Matrix::new(batch_size, self.units);
+        let mut memory = Matrix::new(batch_size, self.units);
+        let mut output = Matrix::new(batch_size, self.output_size());
+
+        self.steps = Vec::with_capacity(self.timesteps);
+
+        for t in 0..self.timesteps {
+            let step_input = input.try_rows(t * self.features, self.features)?;
+
+            let masked: Vec<bool> = (0..batch_size).map(|s| self.mask.is_some_and(|mask|
+                (0..self.features).all(|i| step_input.values[i * batch_size + s] == mask))).collect();
+
+            let (mut new_state, mut new_memory, gates, memory_tanh) = self.cell_forward(&step_input, &state, &memory)?;
+            keep_masked(&mut new_state, &state, &masked);
+            keep_masked(&mut new_memory, &memory, &masked);
+
+            if self.return_sequences {
+                let step_output: &mut [f64] = &mut output.values[t * size..(t + 1) * size];
+
+                for (k, x) in step_output.iter_mut().enumerate() {
+                    *x = if masked[k % batch_size] {self.mask.unwrap_or(0.0)} else {new_state.values[k]};
+                }
+            }
+
+            let previous_state: Matrix = std::mem::replace(&mut state, new_state);
+            let previous_memory: Matrix = std::mem::replace(&mut memory, new_memory);
+
+            self.steps.push(Step {input: step_input, state: previous_state, memory: previous_memory, gates, memory_tanh, masked});
+        }
+
+        if !self.return_sequences {
+            output = state;
+        }
+        Ok(output)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        let batch_size: usize = self.steps.first().map_or(0, |step| step.input.x_length);
+
+        if gradient.x_length != batch_size || gradient.y_length != self.output_size() {
+            return Err(NnError::ShapeMismatch {
+                operation: "Recurrent::backward",
+                left: (self.output_size(), batch_size),
+                right: (gradient.y_length, gradient.x_length)
+            });
+        }
+
+        let size: usize = self.units * batch_size;
+        let nb_rows: usize = self.units * self.cell.nb_gates();
+
+        let mut d_kernel = Matrix::new(self.features, nb_rows);
+        let mut d_recurrent_kernel = Matrix::new(self.units, nb_rows);
+        let mut d_biases = Matrix::new(1, nb_rows);
+        let mut input_gradient = Matrix::new(batch_size, self.features * self.timesteps);
+
+        // gradients w.r.t. the state and the memory after the current step
+        let mut d_state = Matrix::new(batch_size, self.units);
+        let mut d_memory = Matrix::new(batch_size, self.units);
+
+        for (t, step) in self.steps.iter().enumerate().rev() {
+            if self.return_sequences {
+                for k in 0..size {
+                    if !step.masked[k % batch_size] {
+                        d_state.values[k] += gradient.values[t * size + k];
+                    }
+                }
+            }
+            else if t + 1 == self.timesteps {
+                d_state = Matrix::try_add(&d_state, gradient)?;
+            }
+
+            // masked samples skipped the step, their gradients go through it unchanged
+            let (mut active_state, mut active_memory) = (d_state.copy(), d_memory.copy());
+            clear_masked(&mut active_state, &step.masked);
+            clear_masked(&mut active_memory, &step.masked);
+
+            let (d_gates, mut d_previous_state, mut d_previous_memory) = self.cell_backward(step, &active_state,
+                &active_memory, &mut d_recurrent_kernel)?;
+
+            keep_masked(&mut d_previous_state, &d_state, &step.masked);
+            keep_masked(&mut d_previous_memory, &d_memory, &step.masked);
+
+            d_kernel = Matrix::try_add(&d_kernel, &Matrix::try_dot(&d_gates, &step.input.transpose())?)?;
+
+            for (r, d_bias) in d_biases.values.iter_mut().enumerate() {
+                *d_bias += d_gates.values[r * batch_size..(r + 1) * batch_size].iter().sum::<f64>();
+            }
+
+            let d_input = Matrix::try_dot(&self.kernel.transpose(), &d_gates)?;
+            let step_size: usize = self.features * batch_size;
+            input_gradient.values[t * step_size..(t + 1) * step_size].copy_from_slice(&d_input.values);
+
+            if self.truncation > 0 && (self.timesteps - t).is_multiple_of(self.truncation) {
+                d_previous_state = Matrix::new(batch_size, self.units);
+                d_previous_memory = Matrix::new(batch_size, self.units);
+            }
+
+            d_state = d_previous_state;
+            d_memory = d_previous_memory;
+        }
+
+        let scale: f64 = 1.0 / batch_size.max(1) as f64;
+        d_kernel.scale(scale);
+        d_recurrent_kernel.scale(scale);
+        d_biases.scale(scale);
+
+        if self.regularizer != DenseRegularizer::NoRegularizer {
+            d_kernel = Matrix::try_add(&d_kernel, &self.regularizer.gradient(&self.kernel))?;
+            d_recurrent_kernel = Matrix::try_add(&d_recurrent_kernel, &self.regularizer.gradient(&self.recurrent_kernel))?;
+        }
+
+        self.d_kernel = d_kernel;
+        self.d_recurrent_kernel = d_recurrent_kernel;
+        self.d_biases = d_biases;
+        Ok(input_gradient)
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        vec![&self.kernel, &self.recurrent_kernel, &self.biases]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        vec![&mut self.kernel, &mut self.recurrent_kernel, &mut self.biases]
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        vec![self.d_kernel.copy(), self.d_recurrent_kernel.copy(), self.d_biases.copy()]
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        self.regularizer.penalty(&self.kernel) + self.regularizer.penalty(&self.recurrent_kernel)
+    }
+}
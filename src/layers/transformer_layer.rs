@@ -0,0 +1,227 @@
+use crate::activations::dense_activation::DenseActivation;
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+use crate::maths::random::NnRng;
+use crate::normalizations::dense_normalization::{DenseNormalization, NormalizationCache};
+use crate::regularizers::dense_dropout::DenseDropout;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::activation_layer::ActivationLayer;
+use super::attention_layer::MultiHeadAttention;
+use super::dense_layer::Dense;
+use super::dropout_layer::Dropout;
+use super::layer::Layer;
+
+// Transformer encoder block (Vaswani et al., 2017) over sequences shaped (features, timesteps, 1):
+// multi-head self-attention, then a feed-forward network of hidden neurons applied to each step on its own,
+// each one followed by dropout, a residual connection and a LayerNorm of each step.
+pub struct TransformerEncoder {
+    nb_heads: usize,
+    key_size: usize,
+    hidden: usize,
+    activation: DenseActivation,
+    dropout: DenseDropout,
+
+    features: usize,
+    timesteps: usize,
+
+    attention: MultiHeadAttention,
+    attention_dropout: Dropout,
+
+    // the feed-forward network sees one step per column
+    expand: Dense,
+    activation_layer: ActivationLayer,
+    contract: Dense,
+    feed_forward_dropout: Dropout,
+
+    // gamma and beta of the normalization after the attention and after the feed-forward network
+    first_norm: Matrix,
+    second_norm: Matrix,
+
+    d_first_norm: Matrix,
+    d_second_norm: Matrix,
+
+    first_cache: Option<NormalizationCache>,
+    second_cache: Option<NormalizationCache>
+}
+
+impl TransformerEncoder {
+    // Relu in the feed-forward network, without dropout
+    pub fn new(nb_heads: usize, key_size: usize, hidden: usize) -> TransformerEncoder {
+        TransformerEncoder {
+            nb_heads,
+            key_size,
+            hidden,
+            activation: DenseActivation::Relu,
+            dropout: DenseDropout::NoDropout,
+            features: 0,
+            timesteps: 0,
+            attention: MultiHeadAttention::new(nb_heads, key_size),
+            attention_dropout: Dropout::new(DenseDropout::NoDropout),
+            expand: Dense::new(hidden),
+            activation_layer: ActivationLayer::new(Box::new(DenseActivation::Relu)),
+            contract: Dense::new(0),
+            feed_forward_dropout: Dropout::new(DenseDropout::NoDropout),
+            first_norm: Matrix::new(0, 0),
+            second_norm: Matrix::new(0, 0),
+            d_first_norm: Matrix::new(0, 0),
+            d_second_norm: Matrix::new(0, 0),
+            first_cache: None,
+            second_cache: None
+        }
+    }
+
+    pub fn activated(mut self, activation: DenseActivation) -> TransformerEncoder {
+        self.activation = activation;
+        self.activation_layer = ActivationLayer::new(Box::new(activation));
+        self
+    }
+
+    // applied to the output of the attention and of the feed-forward network, before the residual connections
+    pub fn with_dropout(mut self, dropout: DenseDropout) -> TransformerEncoder {
+        self.dropout = dropout;
+        self.attention_dropout = Dropout::new(dropout);
+        self.feed_forward_dropout = Dropout::new(dropout);
+        self
+    }
+
+    // layers applied to each step on their own
+    fn feed_forward_layers(&self) -> [&dyn Layer; 3] {
+        [&self.expand, &self.activation_layer, &self.contract]
+    }
+}
+
+// (timesteps * features) x batch sequences to features x (timesteps * batch) steps
+fn to_steps(mat: &Matrix, features: usize) -> Matrix {
+    let batch_size: usize = mat.x_length;
+    let mut steps = Matrix::new(mat.values.len() / features.max(1), features);
+
+    for (k, x) in mat.values.iter().enumerate() {
+        let (r, s) = (k / batch_size, k % batch_size);
+        steps.values[(r % features) * steps.x_length + (r / features) * batch_size + s] = *x;
+    }
+    steps
+}
+
+// reverse of to_steps
+fn from_steps(steps: &Matrix, features: usize, batch_size: usize) -> Matrix {
+    let mut mat = Matrix::new(batch_size, steps.values.len() / batch_size.max(1));
+
+    for (k, x) in mat.values.iter_mut().enumerate() {
+        let (r, s) = (k / batch_size, k % batch_size);
+        *x = steps.values[(r % features) * steps.x_length + (r / features) * batch_size + s];
+    }
+    mat
+}
+
+impl Layer for TransformerEncoder {
+    fn tag(&self) -> String {
+        format!("TransformerEncoder {} {} {} {} {}", self.nb_heads, self.key_size, self.hidden, self.activation, self.dropout)
+    }
+
+    fn build(&mut self, input: &DenseShape, rng: &mut NnRng) -> NnResult<DenseShape> {
+        self.attention.build(input, rng)?;
+
+        self.features = input.x;
+        self.timesteps = input.y;
+
+        let step = DenseShape::new(self.features, 1, 1);
+        let hidden: DenseShape = self.expand.build(&step, rng)?;
+        self.activation_layer.build(&hidden, rng)?;
+
+        self.contract = Dense::new(self.features);
+        self.contract.build(&hidden, rng)?;
+
+        self.first_norm = DenseNormalization::LayerNorm.initial_parameters(self.features);
+        self.second_norm = DenseNormalization::LayerNorm.initial_parameters(self.features);
+        Ok(*input)
+    }
+
+    fn forward(&mut self, input: &Matrix, training: bool, rng: &mut NnRng) -> NnResult<Matrix> {
+        let attended = self.attention.forward(input, training, rng)?;
+        let attended = self.attention_dropout.forward(&attended, training, rng)?;
+
+        let mut first = to_steps(&Matrix::try_add(input, &attended)?, self.features);
+        self.first_cache = Some(DenseNormalization::LayerNorm.forward(&mut first, &self.first_norm, &mut Matrix::new(0, 0), training)?);
+
+        let hidden = self.expand.forward(&first, training, rng)?;
+        let hidden = self.activation_layer.forward(&hidden, training, rng)?;
+        let fed = self.contract.forward(&hidden, training, rng)?;
+        let fed = self.feed_forward_dropout.forward(&fed, training, rng)?;
+
+        let mut second = Matrix::try_add(&first, &fed)?;
+        self.second_cache = Some(DenseNormalization::LayerNorm.forward(&mut second, &self.second_norm, &mut Matrix::new(0, 0), training)?);
+
+        Ok(from_steps(&second, self.features, input.x_length))
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        let (Some(first_cache), Some(second_cache)) = (&self.first_cache, &self.second_cache) else {
+            return Err(NnError::ShapeMismatch {
+                operation: "TransformerEncoder::backward",
+                left: (self.features * self.timesteps, 0),
+                right: (gradient.y_length, gradient.x_length)
+            });
+        };
+
+        let batch_size: usize = gradient.x_length;
+        let steps_gradient = to_steps(gradient, self.features);
+
+        let (d_second, mut d_second_norm) = DenseNormalization::LayerNorm.backward(second_cache, &self.second_norm, &steps_gradient)?;
+
+        let d_fed = self.feed_forward_dropout.backward(&d_second)?;
+        let d_hidden = self.contract.backward(&d_fed)?;
+        let d_hidden = self.activation_layer.backward(&d_hidden)?;
+        let d_first = Matrix::try_add(&d_second, &self.expand.backward(&d_hidden)?)?;
+
+        let (d_residual, mut d_first_norm) = DenseNormalization::LayerNorm.backward(first_cache, &self.first_norm, &d_first)?;
+        let d_residual = from_steps(&d_residual, self.features, batch_size);
+
+        let d_attended = self.attention_dropout.backward(&d_residual)?;
+        let input_gradient = Matrix::try_add(&d_residual, &self.attention.backward(&d_attended)?)?;
+
+        // the gradients of the step-wise parameters were averaged over every step instead of every sample
+        d_first_norm.scale(self.timesteps as f64);
+        d_second_norm.scale(self.timesteps as f64);
+
+        self.d_first_norm = d_first_norm;
+        self.d_second_norm = d_second_norm;
+        Ok(input_gradient)
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        let mut parameters: Vec<&Matrix> = self.attention.parameters();
+        parameters.push(&self.first_norm);
+        parameters.extend(self.expand.parameters());
+        parameters.extend(self.activation_layer.parameters());
+        parameters.extend(self.contract.parameters());
+        parameters.push(&self.second_norm);
+        parameters
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        let mut parameters: Vec<&mut Matrix> = self.attention.parameters_mut();
+        parameters.push(&mut self.first_norm);
+        parameters.extend(self.expand.parameters_mut());
+        parameters.extend(self.activation_layer.parameters_mut());
+        parameters.extend(self.contract.parameters_mut());
+        parameters.push(&mut self.second_norm);
+        parameters
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        let mut gradients: Vec<Matrix> = self.attention.gradients();
+        gradients.push(self.d_first_norm.copy());
+
+        // same as the normalizations
+        for layer in self.feed_forward_layers() {
+            gradients.extend(layer.gradients().into_iter().map(|mut gradient| {
+                gradient.scale(self.timesteps as f64);
+                gradient
+            }));
+        }
+
+        gradients.push(self.d_second_norm.copy());
+        gradients
+    }
+}
@@ -0,0 +1,18 @@
+pub mod activations;
+pub mod data;
+pub mod derivations;
+pub mod errors;
+pub mod initializers;
+pub mod layers;
+pub mod losses;
+pub mod maths;
+pub mod metrics;
+pub mod models;
+pub mod normalizations;
+pub mod optimizers;
+pub mod prelude;
+pub mod regularizers;
+pub mod schedules;
+pub mod sessions;
+pub mod shapes;
+pub(crate) mod utils;
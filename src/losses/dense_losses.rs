@@ -1,21 +1,74 @@
+use crate::activations::dense_activation::DenseActivation;
+use crate::errors::nn_error::{NnError, NnResult};
 use crate::maths::matrices::Matrix;
-use crate::maths::INFINITY;
+use crate::utils::parsing::{parse_argument, parse_arguments};
 
-use std::process;
+use super::loss::Loss;
+
+use std::f64::consts::LN_2;
+use std::fmt;
 use std::str::FromStr;
 
+// loss of a single sample (column vector)
+type SampleLoss = Box<dyn Fn(&Matrix, &Matrix) -> f64>;
+
+// keeps the logarithm of the Poisson loss finite when the predicted rate is zero
+const POISSON_EPSILON: f64 = 1e-8;
 
-#[derive(strum_macros::Display)]
+// probabilities are clamped to [PROBABILITY_EPSILON, 1 - PROBABILITY_EPSILON] before taking their logarithm
+const PROBABILITY_EPSILON: f64 = 1e-12;
+
+fn clamp_probability(p: f64) -> f64 {
+    p.clamp(PROBABILITY_EPSILON, 1.0 - PROBABILITY_EPSILON)
+}
+
+// custom losses implement the Loss trait instead
+// the regression losses are averaged over the outputs of each sample, like MeanSquaredError
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum DenseLosses {
     NoLoss,
     CategoricalCrossEntropy,
     BinaryCrossEntropy,
     MeanSquaredError,
-    CustomLoss
+    MeanAbsoluteError,
+    Huber(f64), // quadratic below delta, linear above
+    LogCosh,
+    Quantile(f64), // pinball loss of the given quantile, in (0, 1)
+    Poisson, // negative log-likelihood, the outputs being the (positive) predicted rates
+    MeanSquaredLogError,
+    Hinge, // desired outputs are 0 / 1 (or -1 / 1) labels
+    SquaredHinge,
+    Focal(f64, f64), // binary focal loss (gamma, alpha), alpha weighting the positive class
+    KlDivergence,
+    SparseCategoricalCrossEntropy, // desired outputs hold the index of the class of each sample
+    LabelSmoothing(f64) // categorical cross-entropy with targets y * (1 - epsilon) + epsilon / classes
+}
+
+impl fmt::Display for DenseLosses {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DenseLosses::NoLoss => write!(f, "NoLoss"),
+            DenseLosses::CategoricalCrossEntropy => write!(f, "CategoricalCrossEntropy"),
+            DenseLosses::BinaryCrossEntropy => write!(f, "BinaryCrossEntropy"),
+            DenseLosses::MeanSquaredError => write!(f, "MeanSquaredError"),
+            DenseLosses::MeanAbsoluteError => write!(f, "MeanAbsoluteError"),
+            DenseLosses::Huber(delta) => write!(f, "Huber({delta})"),
+            DenseLosses::LogCosh => write!(f, "LogCosh"),
+            DenseLosses::Quantile(quantile) => write!(f, "Quantile({quantile})"),
+            DenseLosses::Poisson => write!(f, "Poisson"),
+            DenseLosses::MeanSquaredLogError => write!(f, "MeanSquaredLogError"),
+            DenseLosses::Hinge => write!(f, "Hinge"),
+            DenseLosses::SquaredHinge => write!(f, "SquaredHinge"),
+            DenseLosses::Focal(gamma, alpha) => write!(f, "Focal({gamma},{alpha})"),
+            DenseLosses::KlDivergence => write!(f, "KlDivergence"),
+            DenseLosses::SparseCategoricalCrossEntropy => write!(f, "SparseCategoricalCrossEntropy"),
+            DenseLosses::LabelSmoothing(epsilon) => write!(f, "LabelSmoothing({epsilon})")
+        }
+    }
 }
 
 impl FromStr for DenseLosses {
-    type Err = ();
+    type Err = NnError;
 
     fn from_str(input: &str) -> Result<DenseLosses, Self::Err> {
         match input {
@@ -23,46 +76,209 @@ impl FromStr for DenseLosses {
             "CategoricalCrossEntropy"   => Ok(DenseLosses::CategoricalCrossEntropy),
             "BinaryCrossEntropy"        => Ok(DenseLosses::BinaryCrossEntropy),
             "MeanSquaredError"          => Ok(DenseLosses::MeanSquaredError),
-            "CustomLoss"                => Ok(DenseLosses::CustomLoss),
-            _ => Err(())
+            "MeanAbsoluteError"         => Ok(DenseLosses::MeanAbsoluteError),
+            "LogCosh"                   => Ok(DenseLosses::LogCosh),
+            "Poisson"                   => Ok(DenseLosses::Poisson),
+            "MeanSquaredLogError"       => Ok(DenseLosses::MeanSquaredLogError),
+            "Hinge"                     => Ok(DenseLosses::Hinge),
+            "SquaredHinge"              => Ok(DenseLosses::SquaredHinge),
+            "KlDivergence"              => Ok(DenseLosses::KlDivergence),
+            "SparseCategoricalCrossEntropy" => Ok(DenseLosses::SparseCategoricalCrossEntropy),
+            _ => {
+                if let Some(delta) = parse_argument(input, "Huber").filter(|delta| *delta > 0.0) {
+                    Ok(DenseLosses::Huber(delta))
+                }
+                else if let Some(quantile) = parse_argument(input, "Quantile").filter(|q| *q > 0.0 && *q < 1.0) {
+                    Ok(DenseLosses::Quantile(quantile))
+                }
+                else if let Some(epsilon) = parse_argument(input, "LabelSmoothing").filter(|e| *e >= 0.0 && *e < 1.0) {
+                    Ok(DenseLosses::LabelSmoothing(epsilon))
+                }
+                else if let Some(values) = parse_arguments(input, "Focal")
+                    .filter(|v| v.len() == 2 && v[0] >= 0.0 && (0.0..=1.0).contains(&v[1])) {
+                    Ok(DenseLosses::Focal(values[0], values[1]))
+                }
+                else {
+                    Err(NnError::UnsupportedLoss(input.to_string()))
+                }
+            }
         }
     }
 }
 
-pub fn calculate_error(loss: &DenseLosses, values: &Matrix, desired_output: &Matrix) -> f64 {
+// targets the outputs are compared with: the one-hot encoded class indices for
+// SparseCategoricalCrossEntropy, the smoothed desired outputs for LabelSmoothing
+// and the desired outputs themselves otherwise
+pub fn dense_targets(loss: &DenseLosses, values: &Matrix, desired_output: &Matrix) -> NnResult<Matrix> {
+
+    let targets: Matrix = match *loss {
+        DenseLosses::SparseCategoricalCrossEntropy => one_hot(desired_output, values.y_length)?,
+        DenseLosses::LabelSmoothing(epsilon) => {
+            let mut targets = desired_output.copy();
+            targets.scale(1.0 - epsilon);
+            targets.add_real(epsilon / desired_output.y_length as f64);
+            targets
+        },
+        _ => desired_output.copy()
+    };
+
+    if values.x_length != targets.x_length || values.y_length != targets.y_length {
+        return Err(NnError::ShapeMismatch {
+            operation: "dense_targets",
+            left: (values.y_length, values.x_length),
+            right: (desired_output.y_length, desired_output.x_length)
+        });
+    }
+    Ok(targets)
+}
 
-    match loss {
-        DenseLosses::NoLoss | DenseLosses::CustomLoss => {
-            println!("No Activation function, exiting..."); 
-            process::exit(1);
+// labels holds one class index per column
+fn one_hot(labels: &Matrix, nb_classes: usize) -> NnResult<Matrix> {
+    if labels.y_length != 1 {
+        return Err(NnError::ShapeMismatch {
+            operation: "one_hot",
+            left: (1, labels.x_length),
+            right: (labels.y_length, labels.x_length)
+        });
+    }
+
+    let mut encoded = Matrix::new(labels.x_length, nb_classes);
+
+    for (x, label) in labels.values.iter().enumerate() {
+        if label.fract() != 0.0 || *label < 0.0 || *label >= nb_classes as f64 {
+            return Err(NnError::InvalidDataset(
+                format!("{label} is not the index of one of the {nb_classes} classes")));
+        }
+        encoded.set(*label as usize, x, 1.0);
+    }
+    Ok(encoded)
+}
+
+pub fn calculate_error(loss: &DenseLosses, values: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+
+    let desired_output: &Matrix = &dense_targets(loss, values, desired_output)?;
+
+    let loss_function: SampleLoss = match *loss {
+        DenseLosses::NoLoss => {
+            return Err(NnError::UnsupportedLoss(loss.to_string()));
         }
-        DenseLosses::CategoricalCrossEntropy => categorical_cross_entropy(values, desired_output),
-        DenseLosses::BinaryCrossEntropy => binary_cross_entropy(values, desired_output),
-        DenseLosses::MeanSquaredError => mean_squared_error(values, desired_output)
+        DenseLosses::CategoricalCrossEntropy => Box::new(categorical_cross_entropy),
+        DenseLosses::BinaryCrossEntropy => Box::new(binary_cross_entropy),
+        DenseLosses::MeanSquaredError => Box::new(mean_squared_error),
+        DenseLosses::MeanAbsoluteError => Box::new(|v, d| mean_element_wise(v, d, absolute_error)),
+        DenseLosses::Huber(delta) => Box::new(move |v, d| mean_element_wise(v, d, |g, y| huber(g, y, delta))),
+        DenseLosses::LogCosh => Box::new(|v, d| mean_element_wise(v, d, log_cosh)),
+        DenseLosses::Quantile(quantile) => Box::new(move |v, d| mean_element_wise(v, d, |g, y| pinball(g, y, quantile))),
+        DenseLosses::Poisson => Box::new(|v, d| mean_element_wise(v, d, poisson)),
+        DenseLosses::MeanSquaredLogError => Box::new(|v, d| mean_element_wise(v, d, squared_log_error)),
+        DenseLosses::Hinge => Box::new(|v, d| mean_element_wise(v, d, hinge)),
+        DenseLosses::SquaredHinge => Box::new(|v, d| mean_element_wise(v, d, squared_hinge)),
+        DenseLosses::Focal(gamma, alpha) => Box::new(move |v, d| sum_element_wise(v, d, |g, y| focal(g, y, gamma, alpha))),
+        DenseLosses::KlDivergence => Box::new(|v, d| sum_element_wise(v, d, kl_divergence)),
+        DenseLosses::SparseCategoricalCrossEntropy | DenseLosses::LabelSmoothing(_) => Box::new(categorical_cross_entropy)
+    };
+
+    // each column is a sample of the batch, the error is averaged over the batch
+    if values.x_length == 1 {
+        return Ok(loss_function(values, desired_output));
     }
+
+    let mut sum: f64 = 0.0;
+
+    for x in 0..values.x_length {
+        sum += loss_function(&values.column(x), &desired_output.column(x));
+    }
+
+    Ok(sum / values.x_length as f64)
 }
 
-pub fn derivative_error(loss: &DenseLosses, nb_values: usize, single_guess: f64, single_desired: f64) -> f64 {
+// single_desired is the target given by dense_targets
+pub fn derivative_error(loss: &DenseLosses, nb_values: usize, single_guess: f64, single_desired: f64) -> NnResult<f64> {
 
     match loss {
-        DenseLosses::NoLoss | DenseLosses::CustomLoss => {
-            println!("No Activation function, exiting..."); 
-            process::exit(1);
+        DenseLosses::NoLoss => {
+            Err(NnError::UnsupportedLoss(loss.to_string()))
         }
-        DenseLosses::CategoricalCrossEntropy =>
-             d_categorical_cross_entropy(single_guess, single_desired),
+        DenseLosses::CategoricalCrossEntropy | DenseLosses::SparseCategoricalCrossEntropy
+        | DenseLosses::LabelSmoothing(_) =>
+             Ok(d_categorical_cross_entropy(single_guess, single_desired)),
         DenseLosses::BinaryCrossEntropy =>
-             d_binary_cross_entropy(single_guess, single_desired),
+             Ok(d_binary_cross_entropy(single_guess, single_desired)),
         DenseLosses::MeanSquaredError =>
-             d_mean_squared_error(nb_values, single_guess, single_desired)
+             Ok(d_mean_squared_error(nb_values, single_guess, single_desired)),
+        DenseLosses::MeanAbsoluteError =>
+             Ok(d_absolute_error(single_guess, single_desired) / nb_values as f64),
+        DenseLosses::Huber(delta) =>
+             Ok(d_huber(single_guess, single_desired, *delta) / nb_values as f64),
+        DenseLosses::LogCosh =>
+             Ok(d_log_cosh(single_guess, single_desired) / nb_values as f64),
+        DenseLosses::Quantile(quantile) =>
+             Ok(d_pinball(single_guess, single_desired, *quantile) / nb_values as f64),
+        DenseLosses::Poisson =>
+             Ok(d_poisson(single_guess, single_desired) / nb_values as f64),
+        DenseLosses::MeanSquaredLogError =>
+             Ok(d_squared_log_error(single_guess, single_desired) / nb_values as f64),
+        DenseLosses::Hinge =>
+             Ok(d_hinge(single_guess, single_desired) / nb_values as f64),
+        DenseLosses::SquaredHinge =>
+             Ok(d_squared_hinge(single_guess, single_desired) / nb_values as f64),
+        DenseLosses::Focal(gamma, alpha) =>
+             Ok(d_focal(single_guess, single_desired, *gamma, *alpha)),
+        DenseLosses::KlDivergence =>
+             Ok(d_kl_divergence(single_guess, single_desired))
+    }
+}
+
+
+
+impl Loss for DenseLosses {
+    fn name(&self) -> String {
+        self.to_string()
+    }
+
+    fn error(&self, values: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+        calculate_error(self, values, desired_output)
+    }
+
+    fn gradient(&self, values: &Matrix, desired_output: &Matrix) -> NnResult<Matrix> {
+        let desired_output: &Matrix = &dense_targets(self, values, desired_output)?;
+
+        let mut gradient = Matrix::new(values.x_length, values.y_length);
+
+        for i in 0..gradient.values.len() {
+            gradient.values[i] = derivative_error(self, values.y_length, values.values[i], desired_output.values[i])?;
+        }
+        Ok(gradient)
     }
+
+    fn as_dense(&self) -> Option<DenseLosses> {
+        Some(*self)
+    }
+}
+
+pub fn is_softmax_cross_entropy(activation: &DenseActivation, loss: &DenseLosses) -> bool {
+    matches!((activation, loss), (DenseActivation::Softmax,
+        DenseLosses::CategoricalCrossEntropy | DenseLosses::SparseCategoricalCrossEntropy | DenseLosses::LabelSmoothing(_)))
 }
 
+// gradient of the categorical cross-entropy w.r.t. the raw values of a softmax output layer:
+// p * sum(y) - y, that is p - y for desired outputs summing to 1
+pub fn d_softmax_cross_entropy(values: &Matrix, desired_output: &Matrix) -> Matrix {
+    let mut delta = Matrix::new(values.x_length, values.y_length);
 
+    for x in 0..values.x_length {
+        let desired_sum: f64 = (0..values.y_length).map(|y| desired_output.get(y, x)).sum();
+
+        for y in 0..values.y_length {
+            delta.set(y, x, values.get(y, x) * desired_sum - desired_output.get(y, x));
+        }
+    }
+    delta
+}
 
 fn d_categorical_cross_entropy(single_guess: f64, single_desired: f64) -> f64 {
 
-    - (single_desired / single_guess)
+    - (single_desired / clamp_probability(single_guess))
 
 }
 
@@ -71,24 +287,16 @@ fn categorical_cross_entropy(values: &Matrix, desired_output: &Matrix) -> f64 {
     let mut sum: f64 = 0.0;
 
     for i in 0..values.y_length {
-        if values.get(i,0) == 0.0 {
-            
-            if desired_output.get(i,0) == 0.0 {
-                continue;
-            }
-            else {
-                sum += -INFINITY;
-                return -sum;
-            }
-        }
-        sum += desired_output.get(i,0) * values.get(i,0).ln();
+        sum += desired_output.get(i,0) * clamp_probability(values.get(i,0)).ln();
     }
     -sum
 }
 
 fn d_binary_cross_entropy(single_guess: f64, single_desired: f64) -> f64 {
 
-    - (single_desired / single_guess) + (1.0 - single_desired) / (1.0 - single_guess)
+    let guess: f64 = clamp_probability(single_guess);
+
+    - (single_desired / guess) + (1.0 - single_desired) / (1.0 - guess)
 
 }
 
@@ -97,8 +305,10 @@ fn binary_cross_entropy(values: &Matrix, desired_output: &Matrix) -> f64 {
     let mut sum: f64 = 0.0;
 
     for i in 0..values.y_length {
-        sum += desired_output.get(i,0) * values.get(i,0).ln() + 
-             (1.0 - desired_output.get(i,0)) * (1.0 - values.get(i,0)).ln();
+        let guess: f64 = clamp_probability(values.get(i,0));
+
+        sum += desired_output.get(i,0) * guess.ln() +
+             (1.0 - desired_output.get(i,0)) * (1.0 - guess).ln();
     }
 
     -sum
@@ -120,4 +330,127 @@ fn mean_squared_error(values: &Matrix, desired_output: &Matrix) -> f64 {
 
     (1.0 / values.y_length as f64) * sum 
 
-}
\ No newline at end of file
+}
+
+// mean over the outputs of a sample of an element-wise loss of (guess, desired)
+fn mean_element_wise(values: &Matrix, desired_output: &Matrix, loss: impl Fn(f64, f64) -> f64) -> f64 {
+
+    let mut sum: f64 = 0.0;
+
+    for i in 0..values.y_length {
+        sum += loss(values.get(i,0), desired_output.get(i,0));
+    }
+
+    sum / values.y_length as f64
+}
+
+fn absolute_error(guess: f64, desired: f64) -> f64 {
+    (guess - desired).abs()
+}
+
+fn d_absolute_error(guess: f64, desired: f64) -> f64 {
+    if guess > desired {1.0} else if guess < desired {-1.0} else {0.0}
+}
+
+fn huber(guess: f64, desired: f64, delta: f64) -> f64 {
+    let residual: f64 = (guess - desired).abs();
+
+    if residual <= delta {0.5 * residual * residual} else {delta * (residual - 0.5 * delta)}
+}
+
+fn d_huber(guess: f64, desired: f64, delta: f64) -> f64 {
+    (guess - desired).clamp(-delta, delta)
+}
+
+// ln(cosh(x)) = |x| + ln(1 + e^(-2|x|)) - ln(2), which cannot overflow
+fn log_cosh(guess: f64, desired: f64) -> f64 {
+    let residual: f64 = (guess - desired).abs();
+
+    residual + (-2.0 * residual).exp().ln_1p() - LN_2
+}
+
+fn d_log_cosh(guess: f64, desired: f64) -> f64 {
+    (guess - desired).tanh()
+}
+
+// under-predictions cost quantile per unit, over-predictions 1 - quantile
+fn pinball(guess: f64, desired: f64, quantile: f64) -> f64 {
+    let residual: f64 = desired - guess;
+
+    if residual > 0.0 {quantile * residual} else {(quantile - 1.0) * residual}
+}
+
+fn d_pinball(guess: f64, desired: f64, quantile: f64) -> f64 {
+    if desired > guess {-quantile} else {1.0 - quantile}
+}
+
+// the constant ln(desired!) term is left out
+fn poisson(guess: f64, desired: f64) -> f64 {
+    guess - desired * (guess + POISSON_EPSILON).ln()
+}
+
+fn d_poisson(guess: f64, desired: f64) -> f64 {
+    1.0 - desired / (guess + POISSON_EPSILON)
+}
+
+fn squared_log_error(guess: f64, desired: f64) -> f64 {
+    (guess.ln_1p() - desired.ln_1p()).powi(2)
+}
+
+fn d_squared_log_error(guess: f64, desired: f64) -> f64 {
+    2.0 * (guess.ln_1p() - desired.ln_1p()) / (1.0 + guess)
+}
+
+// sum over the outputs of a sample of an element-wise loss of (guess, desired)
+fn sum_element_wise(values: &Matrix, desired_output: &Matrix, loss: impl Fn(f64, f64) -> f64) -> f64 {
+    (0..values.y_length).map(|i| loss(values.get(i,0), desired_output.get(i,0))).sum()
+}
+
+// labels in {0, 1} or {-1, 1} are both read as {-1, 1}
+fn signed_label(desired: f64) -> f64 {
+    if desired > 0.0 {1.0} else {-1.0}
+}
+
+fn hinge(guess: f64, desired: f64) -> f64 {
+    (1.0 - signed_label(desired) * guess).max(0.0)
+}
+
+fn d_hinge(guess: f64, desired: f64) -> f64 {
+    let label: f64 = signed_label(desired);
+
+    if label * guess < 1.0 {-label} else {0.0}
+}
+
+fn squared_hinge(guess: f64, desired: f64) -> f64 {
+    hinge(guess, desired).powi(2)
+}
+
+fn d_squared_hinge(guess: f64, desired: f64) -> f64 {
+    -2.0 * signed_label(desired) * hinge(guess, desired)
+}
+
+// -alpha y (1 - p)^gamma ln(p) - (1 - alpha) (1 - y) p^gamma ln(1 - p)
+fn focal(guess: f64, desired: f64, gamma: f64, alpha: f64) -> f64 {
+    let p: f64 = clamp_probability(guess);
+
+    - alpha * desired * (1.0 - p).powf(gamma) * p.ln()
+        - (1.0 - alpha) * (1.0 - desired) * p.powf(gamma) * (1.0 - p).ln()
+}
+
+fn d_focal(guess: f64, desired: f64, gamma: f64, alpha: f64) -> f64 {
+    let p: f64 = clamp_probability(guess);
+
+    let positive: f64 = gamma * (1.0 - p).powf(gamma - 1.0) * p.ln() - (1.0 - p).powf(gamma) / p;
+    let negative: f64 = p.powf(gamma) / (1.0 - p) - gamma * p.powf(gamma - 1.0) * (1.0 - p).ln();
+
+    alpha * desired * positive + (1.0 - alpha) * (1.0 - desired) * negative
+}
+
+// y ln(y / p), zero where y is
+fn kl_divergence(guess: f64, desired: f64) -> f64 {
+    if desired <= 0.0 {0.0} else {desired * (desired / clamp_probability(guess)).ln()}
+}
+
+fn d_kl_divergence(guess: f64, desired: f64) -> f64 {
+    if desired <= 0.0 {0.0} else {-desired / clamp_probability(guess)}
+}
@@ -0,0 +1,44 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+
+use super::dense_losses::DenseLosses;
+
+// A loss comparing the outputs of a model with the desired ones, one column per sample.
+// Implement it to plug a custom loss into a DenseModel, and register its name
+// in a Registry so that models using it can be loaded back.
+pub trait Loss {
+    // name written in the .arch file, it must not contain whitespace
+    fn name(&self) -> String;
+
+    // loss averaged over the samples
+    fn error(&self, values: &Matrix, desired_output: &Matrix) -> NnResult<f64>;
+
+    // gradient of the loss of each sample w.r.t. its values (not averaged over the samples)
+    fn gradient(&self, values: &Matrix, desired_output: &Matrix) -> NnResult<Matrix>;
+
+    // sum of the losses of each sample multiplied by its weight (a row vector), divided by the number
+    // of samples, so that uniform weights of 1 give back error
+    fn weighted_error(&self, values: &Matrix, desired_output: &Matrix, weights: &Matrix) -> NnResult<f64> {
+        if weights.y_length != 1 || weights.x_length != values.x_length || desired_output.x_length != values.x_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "Loss::weighted_error",
+                left: (1, values.x_length),
+                right: (weights.y_length, weights.x_length)
+            });
+        }
+
+        let mut sum: f64 = 0.0;
+
+        for x in 0..values.x_length {
+            if weights.values[x] != 0.0 {
+                sum += weights.values[x] * self.error(&values.column(x), &desired_output.column(x))?;
+            }
+        }
+        Ok(sum / values.x_length.max(1) as f64)
+    }
+
+    // the built-in loss, if this is one
+    fn as_dense(&self) -> Option<DenseLosses> {
+        None
+    }
+}
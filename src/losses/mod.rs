@@ -1 +1,2 @@
-pub mod dense_losses;
\ No newline at end of file
+pub mod dense_losses;
+pub mod loss;
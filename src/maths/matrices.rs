@@ -1,5 +1,6 @@
 use rand::Rng;
-use std::process;
+
+use crate::errors::nn_error::{NnError, NnResult};
 
 pub struct Matrix {
     pub x_length: usize,
@@ -12,16 +13,10 @@ impl Matrix {
 
         let size: usize = x_length * y_length;
 
-        let mut values = Vec::with_capacity(size);
-
-        for _ in 0..size{
-            values.push(0.0);
-        } 
-
         Matrix {
-            x_length: x_length,
-            y_length: y_length,
-            values: values
+            x_length,
+            y_length,
+            values: vec![0.0; size]
         }
     }
 
@@ -32,13 +27,11 @@ impl Matrix {
 
                 print!("{x}, ");
             }
-            println!("");
+            println!();
         }
     }
 
-    pub fn shuffle(mut self) -> Matrix {
-        
-        let mut rng = rand::thread_rng();
+    pub fn shuffle<R: Rng>(mut self, rng: &mut R) -> Matrix {
 
         for i in 0.. self.values.len() {
             self.values[i] = rng.gen::<f64>();
@@ -58,79 +51,81 @@ impl Matrix {
     }
 
     pub fn get(&self, y: usize, x: usize) -> f64 {
-        if y >= self.y_length || x >= self.x_length {
-            let ymax = self.y_length;
-            let xmax = self.x_length;
+        self.try_get(y, x).unwrap_or_else(|e| panic!("{e}"))
+    }
 
-            println!("Error: GET method for matrix has encountered an exception");
-            println!("Expected to have a y or a x lower then y_length or x_length");
-            println!("--------DEBUG------------");
-            println!("y: {y}, x: {x}, |");
-            println!("y_length: {ymax}, x_length: {xmax}");
-            process::exit(1);
+    pub fn try_get(&self, y: usize, x: usize) -> NnResult<f64> {
+        if y >= self.y_length || x >= self.x_length {
+            return Err(NnError::IndexOutOfRange {
+                operation: "Matrix::get",
+                y,
+                x,
+                y_length: self.y_length,
+                x_length: self.x_length
+            });
         }
-        self.values[y * self.x_length + x]
+        Ok(self.values[y * self.x_length + x])
     }
 
     pub fn set(&mut self, y: usize, x: usize, value: f64) {
-        if y >= self.y_length || x >= self.x_length {
-            let ymax = self.y_length;
-            let xmax = self.x_length;
+        self.try_set(y, x, value).unwrap_or_else(|e| panic!("{e}"))
+    }
 
-            println!("Error: SET method for matrix has encountered an exception");
-            println!("Expected to have a y or a x lower then y_length or x_length");
-            println!("--------DEBUG------------");
-            println!("y: {y}, x: {x}");
-            println!("y_length: {ymax}, x_length: {xmax}");
-            process::exit(1);
+    pub fn try_set(&mut self, y: usize, x: usize, value: f64) -> NnResult<()> {
+        if y >= self.y_length || x >= self.x_length {
+            return Err(NnError::IndexOutOfRange {
+                operation: "Matrix::set",
+                y,
+                x,
+                y_length: self.y_length,
+                x_length: self.x_length
+            });
         }
 
         self.values[y * self.x_length + x] = value;
+        Ok(())
     }
 
     pub fn dot(mat1: &Matrix, mat2: &Matrix) -> Matrix {
-        if mat1.x_length != mat2.y_length {
-            let mat1x = mat1.x_length;
-            let mat2y = mat2.y_length;
+        Matrix::try_dot(mat1, mat2).unwrap_or_else(|e| panic!("{e}"))
+    }
 
-            println!("Error: DOT function for matrix has encountered an exception");
-            println!("Expected to have a two matrices of y size = x size");
-            println!("--------DEBUG------------");
-            println!("mat1x: {mat1x}, mat2y: {mat2y}");
-            process::exit(1);
+    pub fn try_dot(mat1: &Matrix, mat2: &Matrix) -> NnResult<Matrix> {
+        if mat1.x_length != mat2.y_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "Matrix::dot",
+                left: (mat1.y_length, mat1.x_length),
+                right: (mat2.y_length, mat2.x_length)
+            });
         }
         let mut mat = Matrix::new(mat2.x_length, mat1.y_length);
         
+        // y-n-x ordering keeps the inner loop on contiguous rows, which matters for batches
         for y in 0..mat.y_length {
-            for x in 0..mat.x_length {
-                let mut value: f64= 0.0;
-                for n in 0..mat1.x_length { // or mat2.y_length 
-                    
-                    value += mat1.get(y,n) * mat2.get(n,x);
+            for n in 0..mat1.x_length { // or mat2.y_length 
+                let left: f64 = mat1.values[y * mat1.x_length + n];
+
+                for x in 0..mat.x_length {
+                    mat.values[y * mat.x_length + x] += left * mat2.values[n * mat2.x_length + x];
                 }
-                mat.set(y, x, value);
             }
         }
 
-        mat
+        Ok(mat)
 
     }
 
     pub fn add(mat1: &Matrix, mat2: &Matrix) -> Matrix {
-        if mat1.x_length != mat2.x_length || mat1.y_length != mat2.y_length {
-            let mat1x = mat1.x_length;
-            let mat1y = mat1.y_length;
-
-            let mat2x = mat2.x_length;
-            let mat2y = mat2.y_length;
-
-            println!("Error: ADD function for matrix has encountered an exception");
-            println!("Expected to have a two matrices of same size");
-            println!("--------DEBUG------------");
-            println!("mat1x: {mat1x}, mat1y: {mat1y}");
-            println!("mat2x: {mat2x}, mat2y: {mat2y}");
+        Matrix::try_add(mat1, mat2).unwrap_or_else(|e| panic!("{e}"))
+    }
 
-            process::exit(1);
+    pub fn try_add(mat1: &Matrix, mat2: &Matrix) -> NnResult<Matrix> {
+        if mat1.x_length != mat2.x_length || mat1.y_length != mat2.y_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "Matrix::add",
+                left: (mat1.y_length, mat1.x_length),
+                right: (mat2.y_length, mat2.x_length)
+            });
         }
 
         let mut mat = Matrix::new(mat1.x_length, mat1.y_length);
@@ -141,15 +136,13 @@ impl Matrix {
             }
         }
 
-        mat
+        Ok(mat)
     }
 
-    pub fn vec_to_col_mat(vec: &Vec<f64>) -> Matrix{
+    pub fn vec_to_col_mat(vec: &[f64]) -> Matrix{
         let mut result = Matrix::new(1, vec.len());
 
-        for i in 0..vec.len() {
-            result.values[i] = vec[i];
-        }
+        result.values.copy_from_slice(vec);
 
         result
     }
@@ -159,5 +152,158 @@ impl Matrix {
             self.values[i] += b;
         }
     }
+
+    pub fn scale(&mut self, factor: f64) {
+        for value in self.values.iter_mut() {
+            *value *= factor;
+        }
+    }
+
+    pub fn transpose(&self) -> Matrix {
+        let mut mat = Matrix::new(self.y_length, self.x_length);
+
+        for y in 0..self.y_length {
+            for x in 0..self.x_length {
+                mat.values[x * mat.x_length + y] = self.values[y * self.x_length + x];
+            }
+        }
+
+        mat
+    }
+
+    pub fn try_hadamard(mat1: &Matrix, mat2: &Matrix) -> NnResult<Matrix> {
+        if mat1.x_length != mat2.x_length || mat1.y_length != mat2.y_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "Matrix::hadamard",
+                left: (mat1.y_length, mat1.x_length),
+                right: (mat2.y_length, mat2.x_length)
+            });
+        }
+
+        let mut mat = mat1.copy();
+
+        for i in 0..mat.values.len() {
+            mat.values[i] *= mat2.values[i];
+        }
+
+        Ok(mat)
+    }
+
+    // multiplies each column x by factors[x], factors being a row vector
+    pub fn try_scale_columns(&mut self, factors: &Matrix) -> NnResult<()> {
+        if factors.y_length != 1 || factors.x_length != self.x_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "Matrix::scale_columns",
+                left: (self.y_length, self.x_length),
+                right: (factors.y_length, factors.x_length)
+            });
+        }
+
+        for y in 0..self.y_length {
+            for x in 0..self.x_length {
+                self.values[y * self.x_length + x] *= factors.values[x];
+            }
+        }
+        Ok(())
+    }
+
+    // adds the column vector col to every column of mat (e.g. a bias to every sample of a batch)
+    pub fn try_add_column(mat: &Matrix, col: &Matrix) -> NnResult<Matrix> {
+        if col.x_length != 1 || col.y_length != mat.y_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "Matrix::add_column",
+                left: (mat.y_length, mat.x_length),
+                right: (col.y_length, col.x_length)
+            });
+        }
+
+        let mut result = mat.copy();
+
+        for y in 0..result.y_length {
+            for x in 0..result.x_length {
+                result.values[y * result.x_length + x] += col.values[y];
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn column(&self, x: usize) -> Matrix {
+        let mut col = Matrix::new(1, self.y_length);
+
+        for y in 0..self.y_length {
+            col.values[y] = self.get(y, x);
+        }
+
+        col
+    }
+
+    // the count rows starting at the row start
+    pub fn try_rows(&self, start: usize, count: usize) -> NnResult<Matrix> {
+        if start + count > self.y_length {
+            return Err(NnError::IndexOutOfRange {
+                operation: "Matrix::try_rows",
+                y: (start + count).saturating_sub(1),
+                x: 0,
+                y_length: self.y_length,
+                x_length: self.x_length
+            });
+        }
+
+        let mut mat = Matrix::new(self.x_length, count);
+        mat.values.copy_from_slice(&self.values[start * self.x_length..(start + count) * self.x_length]);
+
+        Ok(mat)
+    }
+
+    // builds a batch matrix where each column is one of the given column vectors
+    pub fn from_columns(columns: &[&Matrix]) -> NnResult<Matrix> {
+        let y_length = columns.first().map_or(0, |col| col.y_length);
+        let mut mat = Matrix::new(columns.len(), y_length);
+
+        for (x, col) in columns.iter().enumerate() {
+            if col.x_length != 1 || col.y_length != y_length {
+                return Err(NnError::ShapeMismatch {
+                    operation: "Matrix::from_columns",
+                    left: (y_length, 1),
+                    right: (col.y_length, col.x_length)
+                });
+            }
+
+            for y in 0..y_length {
+                mat.values[y * mat.x_length + x] = col.values[y];
+            }
+        }
+
+        Ok(mat)
+    }
+
+    // index of the greatest value of the column x
+    pub fn argmax(&self, x: usize) -> usize {
+        let mut best: usize = 0;
+
+        for y in 1..self.y_length {
+            if self.get(y, x) > self.get(best, x) {
+                best = y;
+            }
+        }
+        best
+    }
+
+    // averages the columns into a single column vector
+    pub fn mean_columns(&self) -> Matrix {
+        let mut col = Matrix::new(1, self.y_length);
+
+        if self.x_length == 0 {
+            return col;
+        }
+
+        for y in 0..self.y_length {
+            let row = &self.values[y * self.x_length..(y + 1) * self.x_length];
+            col.values[y] = row.iter().sum::<f64>() / self.x_length as f64;
+        }
+
+        col
+    }
     
 }
\ No newline at end of file
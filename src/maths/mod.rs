@@ -1,3 +1,3 @@
 pub mod matrices;
-
-pub const INFINITY: f64 = 100000000.0;
+pub mod random;
+pub mod special_functions;
@@ -0,0 +1,14 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+// Every random draw of the crate (initialization, shuffling, splits, dropout masks) goes through
+// this generator, so that a seed fully determines a training run.
+pub type NnRng = StdRng;
+
+// a seeded generator, or one seeded from the system entropy when no seed is given
+pub fn new_rng(seed: Option<u64>) -> NnRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy()
+    }
+}
@@ -0,0 +1,32 @@
+use std::f64::consts::PI;
+
+// error function, accurate to double precision
+// uses erf(x) = 2/sqrt(pi) * exp(-x^2) * sum_n 2^n x^(2n+1) / (1 * 3 * ... * (2n+1)),
+// whose terms are all positive (no cancellation); |erf(x)| rounds to 1 beyond 6
+pub fn erf(x: f64) -> f64 {
+    if x.abs() >= 6.0 {
+        return x.signum();
+    }
+
+    let mut term: f64 = x;
+    let mut sum: f64 = x;
+    let mut n: f64 = 0.0;
+
+    while term.abs() > 1e-17 * sum.abs() {
+        n += 1.0;
+        term *= 2.0 * x * x / (2.0 * n + 1.0);
+        sum += term;
+    }
+
+    2.0 / PI.sqrt() * (-x * x).exp() * sum
+}
+
+// cumulative distribution function of the standard normal distribution
+pub fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+// probability density function of the standard normal distribution
+pub fn normal_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * PI).sqrt()
+}
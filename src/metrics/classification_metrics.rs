@@ -0,0 +1,238 @@
+use crate::data::split_data::class_of;
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+
+// Every metric takes the predictions of a model and the desired outputs with one column per sample.
+// Classes are read as in data::split_data::class_of: the greatest output for several outputs,
+// or a 0.5 threshold for a single (binary) output.
+
+#[derive(Clone, Copy, strum_macros::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum Average {
+    // computed from the total true/false positives over all classes
+    Micro,
+    // unweighted mean of the per-class scores
+    Macro,
+    // mean of the per-class scores weighted by the number of samples of each class
+    Weighted
+}
+
+pub fn check_shapes(operation: &'static str, predictions: &Matrix, desired_output: &Matrix) -> NnResult<()> {
+    if predictions.x_length != desired_output.x_length || predictions.y_length != desired_output.y_length
+        || predictions.x_length == 0 {
+        return Err(NnError::ShapeMismatch {
+            operation,
+            left: (predictions.y_length, predictions.x_length),
+            right: (desired_output.y_length, desired_output.x_length)
+        });
+    }
+    Ok(())
+}
+
+fn nb_classes(predictions: &Matrix) -> usize {
+    if predictions.y_length == 1 {2} else {predictions.y_length}
+}
+
+pub fn accuracy(predictions: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+    check_shapes("accuracy", predictions, desired_output)?;
+
+    let mut nb_correct: usize = 0;
+
+    for x in 0..predictions.x_length {
+        if class_of(&predictions.column(x)) == class_of(&desired_output.column(x)) {
+            nb_correct += 1;
+        }
+    }
+
+    Ok(nb_correct as f64 / predictions.x_length as f64)
+}
+
+// a sample is correct when its class is among the k greatest outputs
+pub fn top_k_accuracy(predictions: &Matrix, desired_output: &Matrix, k: usize) -> NnResult<f64> {
+    check_shapes("top_k_accuracy", predictions, desired_output)?;
+
+    if predictions.y_length == 1 {
+        return accuracy(predictions, desired_output);
+    }
+
+    let mut nb_correct: usize = 0;
+
+    for x in 0..predictions.x_length {
+        let class: usize = desired_output.argmax(x);
+        let score: f64 = predictions.get(class, x);
+
+        // number of outputs ranked strictly before the desired class
+        let rank: usize = (0..predictions.y_length).filter(|y| predictions.get(*y, x) > score).count();
+
+        if rank < k {
+            nb_correct += 1;
+        }
+    }
+
+    Ok(nb_correct as f64 / predictions.x_length as f64)
+}
+
+// confusion.get(desired class, predicted class) is the number of samples of that pair
+pub fn confusion_matrix(predictions: &Matrix, desired_output: &Matrix) -> NnResult<Matrix> {
+    check_shapes("confusion_matrix", predictions, desired_output)?;
+
+    let nb_classes: usize = nb_classes(predictions);
+    let mut confusion = Matrix::new(nb_classes, nb_classes);
+
+    for x in 0..predictions.x_length {
+        let desired: usize = class_of(&desired_output.column(x));
+        let predicted: usize = class_of(&predictions.column(x));
+
+        confusion.set(desired, predicted, confusion.get(desired, predicted) + 1.0);
+    }
+
+    Ok(confusion)
+}
+
+// per class: (true positives, false positives, false negatives)
+fn class_counts(confusion: &Matrix) -> Vec<(f64, f64, f64)> {
+    let nb_classes: usize = confusion.y_length;
+
+    (0..nb_classes).map(|c| {
+        let true_positives: f64 = confusion.get(c, c);
+        let predicted: f64 = (0..nb_classes).map(|y| confusion.get(y, c)).sum();
+        let actual: f64 = (0..nb_classes).map(|x| confusion.get(c, x)).sum();
+
+        (true_positives, predicted - true_positives, actual - true_positives)
+    }).collect()
+}
+
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {0.0} else {numerator / denominator}
+}
+
+fn f1(precision: f64, recall: f64) -> f64 {
+    ratio(2.0 * precision * recall, precision + recall)
+}
+
+fn averaged(predictions: &Matrix, desired_output: &Matrix, average: Average,
+    score: fn(f64, f64, f64) -> f64) -> NnResult<f64> {
+
+    let counts = class_counts(&confusion_matrix(predictions, desired_output)?);
+
+    match average {
+        Average::Micro => {
+            let (tp, fp, fn_) = counts.iter().fold((0.0, 0.0, 0.0),
+                |acc, c| (acc.0 + c.0, acc.1 + c.1, acc.2 + c.2));
+            Ok(score(tp, fp, fn_))
+        },
+        Average::Macro => {
+            let sum: f64 = counts.iter().map(|c| score(c.0, c.1, c.2)).sum();
+            Ok(sum / counts.len() as f64)
+        },
+        Average::Weighted => {
+            // support of a class = true positives + false negatives
+            let total: f64 = counts.iter().map(|c| c.0 + c.2).sum();
+            let sum: f64 = counts.iter().map(|c| (c.0 + c.2) * score(c.0, c.1, c.2)).sum();
+            Ok(ratio(sum, total))
+        }
+    }
+}
+
+pub fn precision(predictions: &Matrix, desired_output: &Matrix, average: Average) -> NnResult<f64> {
+    averaged(predictions, desired_output, average, |tp, fp, _| ratio(tp, tp + fp))
+}
+
+pub fn recall(predictions: &Matrix, desired_output: &Matrix, average: Average) -> NnResult<f64> {
+    averaged(predictions, desired_output, average, |tp, _, fn_| ratio(tp, tp + fn_))
+}
+
+pub fn f1_score(predictions: &Matrix, desired_output: &Matrix, average: Average) -> NnResult<f64> {
+    averaged(predictions, desired_output, average,
+        |tp, fp, fn_| f1(ratio(tp, tp + fp), ratio(tp, tp + fn_)))
+}
+
+// (score, is positive) pairs of a class, one-vs-rest
+fn scores_of(predictions: &Matrix, desired_output: &Matrix, class: usize) -> Vec<(f64, bool)> {
+    (0..predictions.x_length).map(|x| {
+        if predictions.y_length == 1 {
+            (predictions.get(0, x), class_of(&desired_output.column(x)) == 1)
+        } else {
+            (predictions.get(class, x), class_of(&desired_output.column(x)) == class)
+        }
+    }).collect()
+}
+
+// probability that a random positive is scored above a random negative (Mann-Whitney U),
+// ties counting for one half
+fn binary_roc_auc(scores: &mut [(f64, bool)]) -> Option<f64> {
+    scores.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let nb_positives: f64 = scores.iter().filter(|s| s.1).count() as f64;
+    let nb_negatives: f64 = scores.len() as f64 - nb_positives;
+
+    if nb_positives == 0.0 || nb_negatives == 0.0 {
+        return None;
+    }
+
+    let mut positive_ranks: f64 = 0.0;
+    let mut i: usize = 0;
+
+    while i < scores.len() {
+        let mut j: usize = i;
+        while j < scores.len() && scores[j].0 == scores[i].0 {
+            j += 1;
+        }
+
+        // ranks are 1-based, tied scores share the average of their ranks
+        let rank: f64 = (i + 1 + j) as f64 / 2.0;
+        positive_ranks += rank * scores[i..j].iter().filter(|s| s.1).count() as f64;
+        i = j;
+    }
+
+    Some((positive_ranks - nb_positives * (nb_positives + 1.0) / 2.0) / (nb_positives * nb_negatives))
+}
+
+// average precision: sum over the positives of the precision at their rank, divided by the number of positives
+fn binary_pr_auc(scores: &mut [(f64, bool)]) -> Option<f64> {
+    scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let nb_positives: usize = scores.iter().filter(|s| s.1).count();
+
+    if nb_positives == 0 {
+        return None;
+    }
+
+    let mut true_positives: usize = 0;
+    let mut sum: f64 = 0.0;
+
+    for (i, score) in scores.iter().enumerate() {
+        if score.1 {
+            true_positives += 1;
+            sum += true_positives as f64 / (i + 1) as f64;
+        }
+    }
+
+    Some(sum / nb_positives as f64)
+}
+
+// macro average of the one-vs-rest curves, ignoring classes absent from (or filling) the desired outputs
+fn one_vs_rest(predictions: &Matrix, desired_output: &Matrix,
+    curve: fn(&mut [(f64, bool)]) -> Option<f64>) -> NnResult<f64> {
+
+    let nb_curves: usize = if predictions.y_length == 1 {1} else {predictions.y_length};
+    let areas: Vec<f64> = (0..nb_curves)
+        .filter_map(|class| curve(&mut scores_of(predictions, desired_output, class)))
+        .collect();
+
+    if areas.is_empty() {
+        return Err(NnError::InvalidDataset("the area under the curve needs both positive and negative samples".to_string()));
+    }
+
+    Ok(areas.iter().sum::<f64>() / areas.len() as f64)
+}
+
+pub fn roc_auc(predictions: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+    check_shapes("roc_auc", predictions, desired_output)?;
+    one_vs_rest(predictions, desired_output, binary_roc_auc)
+}
+
+pub fn pr_auc(predictions: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+    check_shapes("pr_auc", predictions, desired_output)?;
+    one_vs_rest(predictions, desired_output, binary_pr_auc)
+}
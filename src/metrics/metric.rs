@@ -0,0 +1,68 @@
+use crate::data::split_data::Labels;
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+
+use super::classification_metrics::{
+    accuracy, f1_score, pr_auc, precision, recall, roc_auc, top_k_accuracy, Average
+};
+use super::regression_metrics::{
+    mean_absolute_error, mean_absolute_percentage_error, r2_score, root_mean_squared_error
+};
+
+use std::fmt;
+
+// metrics that a Session reports at the end of each epoch
+#[derive(Clone, Copy)]
+pub enum Metric {
+    Accuracy,
+    TopKAccuracy(usize),
+    Precision(Average),
+    Recall(Average),
+    F1Score(Average),
+    RocAuc,
+    PrAuc,
+    MeanAbsoluteError,
+    RootMeanSquaredError,
+    R2Score,
+    MeanAbsolutePercentageError
+}
+
+impl Metric {
+    pub fn compute(&self, predictions: &Matrix, desired_output: &Matrix, labels: Labels) -> NnResult<f64> {
+        // the classification metrics read sparse labels as one-hot desired outputs,
+        // unless the model has a single (binary) output, which they already match
+        let classes = || if predictions.y_length == 1 {Ok(desired_output.copy())} else {labels.one_hot(desired_output)};
+
+        match self {
+            Metric::Accuracy => accuracy(predictions, &classes()?),
+            Metric::TopKAccuracy(k) => top_k_accuracy(predictions, &classes()?, *k),
+            Metric::Precision(average) => precision(predictions, &classes()?, *average),
+            Metric::Recall(average) => recall(predictions, &classes()?, *average),
+            Metric::F1Score(average) => f1_score(predictions, &classes()?, *average),
+            Metric::RocAuc => roc_auc(predictions, &classes()?),
+            Metric::PrAuc => pr_auc(predictions, &classes()?),
+            Metric::MeanAbsoluteError => mean_absolute_error(predictions, desired_output),
+            Metric::RootMeanSquaredError => root_mean_squared_error(predictions, desired_output),
+            Metric::R2Score => r2_score(predictions, desired_output),
+            Metric::MeanAbsolutePercentageError => mean_absolute_percentage_error(predictions, desired_output)
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Metric::Accuracy => write!(f, "accuracy"),
+            Metric::TopKAccuracy(k) => write!(f, "top {k} accuracy"),
+            Metric::Precision(average) => write!(f, "{average} precision"),
+            Metric::Recall(average) => write!(f, "{average} recall"),
+            Metric::F1Score(average) => write!(f, "{average} f1"),
+            Metric::RocAuc => write!(f, "roc auc"),
+            Metric::PrAuc => write!(f, "pr auc"),
+            Metric::MeanAbsoluteError => write!(f, "mae"),
+            Metric::RootMeanSquaredError => write!(f, "rmse"),
+            Metric::R2Score => write!(f, "r2"),
+            Metric::MeanAbsolutePercentageError => write!(f, "mape")
+        }
+    }
+}
@@ -0,0 +1,3 @@
+pub mod classification_metrics;
+pub mod metric;
+pub mod regression_metrics;
\ No newline at end of file
@@ -0,0 +1,55 @@
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+
+use super::classification_metrics::check_shapes;
+
+// Regression metrics are averaged over every output of every sample.
+
+pub fn mean_absolute_error(predictions: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+    check_shapes("mean_absolute_error", predictions, desired_output)?;
+
+    let sum: f64 = predictions.values.iter().zip(desired_output.values.iter())
+        .map(|(p, d)| (d - p).abs())
+        .sum();
+
+    Ok(sum / predictions.values.len() as f64)
+}
+
+pub fn root_mean_squared_error(predictions: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+    check_shapes("root_mean_squared_error", predictions, desired_output)?;
+
+    let sum: f64 = predictions.values.iter().zip(desired_output.values.iter())
+        .map(|(p, d)| (d - p).powi(2))
+        .sum();
+
+    Ok((sum / predictions.values.len() as f64).sqrt())
+}
+
+// coefficient of determination: 1 - SS_res / SS_tot
+pub fn r2_score(predictions: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+    check_shapes("r2_score", predictions, desired_output)?;
+
+    let mean: f64 = desired_output.values.iter().sum::<f64>() / desired_output.values.len() as f64;
+
+    let residual_sum: f64 = predictions.values.iter().zip(desired_output.values.iter())
+        .map(|(p, d)| (d - p).powi(2))
+        .sum();
+    let total_sum: f64 = desired_output.values.iter().map(|d| (d - mean).powi(2)).sum();
+
+    if total_sum == 0.0 {
+        return Ok(if residual_sum == 0.0 {1.0} else {0.0});
+    }
+
+    Ok(1.0 - residual_sum / total_sum)
+}
+
+// returned as a fraction (0.05 = 5%), desired values of 0 are guarded by f64::EPSILON
+pub fn mean_absolute_percentage_error(predictions: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+    check_shapes("mean_absolute_percentage_error", predictions, desired_output)?;
+
+    let sum: f64 = predictions.values.iter().zip(desired_output.values.iter())
+        .map(|(p, d)| (d - p).abs() / d.abs().max(f64::EPSILON))
+        .sum();
+
+    Ok(sum / predictions.values.len() as f64)
+}
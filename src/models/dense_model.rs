@@ -1,296 +1,648 @@
-use crate::activations::dense_activation::{apply_activation, DenseActivation};
-use crate::derivations::dense_derivation::{apply_derivation};
-use crate::losses::dense_losses::{DenseLosses, derivative_error};
+use crate::activations::activation::Activation;
+use crate::activations::dense_activation::DenseActivation;
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::initializers::dense_initializer::DenseInitializer;
+use crate::layers::activation_layer::ActivationLayer;
+use crate::layers::dense_layer::Dense;
+use crate::layers::dropout_layer::Dropout;
+use crate::layers::layer::Layer;
+use crate::layers::normalization_layer::Normalization;
+use crate::losses::dense_losses::DenseLosses;
+use crate::losses::loss::Loss;
 use crate::shapes::dense_shape::DenseShape;
 use crate::maths::matrices::Matrix;
+use crate::normalizations::dense_normalization::DenseNormalization;
+use crate::optimizers::optimizer::Optimizer;
+use crate::regularizers::dense_constraint::DenseConstraint;
+use crate::regularizers::dense_dropout::DenseDropout;
+use crate::regularizers::dense_regularizer::DenseRegularizer;
+
+use super::registry::Registry;
+use super::sequential::Sequential;
 
 use std::fs::File;
+use std::io;
 use std::io::Write;
 use std::io::{BufReader, BufRead};
 use std::str::FromStr;
 
+// A stack of fully connected layers, each one being a Dense layer followed by a Normalization, an activation
+// and, for the hidden layers, a Dropout, held by a Sequential model that trains them.
+// Parameters and gradients are given per kind rather than layer after layer (see gradients),
+// and the model is saved in its own .arch / .wab format.
 pub struct DenseModel {
-    nb_layers: usize,
-    pub loss: DenseLosses,
-    activations: Vec<DenseActivation>,
-
-    // three-dimensional vector:
-    // z selects the group of weights between two layers
-    weights: Vec<Matrix>,
-
-    // two-dimensional vector (Matrix is in L(1,n))
-    // each perceptron of layer has a certain bias attributed to it.
-    biases: Vec<Matrix>,
-
-    // two-dimensional vector (Matrix is in L(1,n))
-    raw_values: Vec<Matrix>,
+    model: Sequential,
+
+    // the settings the layers were built with, recorded in the saved architecture
+    weight_initializers: Vec<DenseInitializer>,
+    bias_initializers: Vec<DenseInitializer>,
+    regularizers: Vec<DenseRegularizer>,
+    constraints: Vec<DenseConstraint>,
+    dropouts: Vec<DenseDropout>,
+    normalizations: Vec<DenseNormalization>
+}
 
-    // two-dimensional vector (Matrix is in L(1,n))
-    values: Vec<Matrix> 
+// index of the Dense layer of the given layer in the Sequential model,
+// its Normalization, activation and Dropout following it
+fn slot(layer: usize) -> usize {
+    4 * layer
 }
 
 impl DenseModel {
+    // weights are initialized according to each activation (see DenseInitializer::default_for),
+    // biases start at zero
+    // panics when there is not one more shape than activations, see new_seeded to get the error instead
     pub fn new(activations_arr: Vec<DenseActivation>, loss: DenseLosses,
     shapes: Vec<DenseShape>) -> DenseModel {
-        
-        let mut values = Vec::with_capacity(shapes.len());
-        let mut raw_values = Vec::with_capacity(shapes.len());
 
-        let mut weights = Vec::with_capacity(activations_arr.len()); //shapes.len() - 1
-        let mut biases = Vec::with_capacity(activations_arr.len()); //shapes.len() - 1
+        DenseModel::new_seeded(activations_arr, loss, shapes, None).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    // same as new, but the weights are drawn from the given seed (or from entropy when None)
+    pub fn new_seeded(activations_arr: Vec<DenseActivation>, loss: DenseLosses,
+    shapes: Vec<DenseShape>, seed: Option<u64>) -> NnResult<DenseModel> {
 
-        let length = activations_arr.len();
+        let weight_initializers = activations_arr.iter().map(DenseInitializer::default_for).collect();
+        let bias_initializers = vec![DenseInitializer::Zeros; activations_arr.len()];
+
+        DenseModel::with_initializers(activations_arr, loss, shapes, weight_initializers, bias_initializers, seed)
+    }
+
+    pub fn with_initializers(activations_arr: Vec<DenseActivation>, loss: DenseLosses,
+    shapes: Vec<DenseShape>, weight_initializers: Vec<DenseInitializer>,
+    bias_initializers: Vec<DenseInitializer>, seed: Option<u64>) -> NnResult<DenseModel> {
+
+        let activations: Vec<Box<dyn Activation>> = activations_arr.into_iter()
+            .map(|activation| Box::new(activation) as Box<dyn Activation>)
+            .collect();
+
+        DenseModel::with_custom(activations, Box::new(loss), shapes, weight_initializers, bias_initializers, seed)
+    }
+
+    // same as new_seeded, with user-defined activations and loss
+    pub fn new_custom(activations: Vec<Box<dyn Activation>>, loss: Box<dyn Loss>,
+    shapes: Vec<DenseShape>, seed: Option<u64>) -> NnResult<DenseModel> {
+
+        let weight_initializers = activations.iter().map(|activation| match activation.as_dense() {
+            Some(dense) => DenseInitializer::default_for(&dense),
+            None => DenseInitializer::XavierUniform
+        }).collect();
+        let bias_initializers = vec![DenseInitializer::Zeros; activations.len()];
+
+        DenseModel::with_custom(activations, loss, shapes, weight_initializers, bias_initializers, seed)
+    }
 
-        for i in 0..length {
-            values.push(Matrix::new(1,shapes[i].range));
-            raw_values.push(Matrix::new(1,shapes[i].range).shuffle());
+    pub fn with_custom(activations: Vec<Box<dyn Activation>>, loss: Box<dyn Loss>,
+    shapes: Vec<DenseShape>, weight_initializers: Vec<DenseInitializer>,
+    bias_initializers: Vec<DenseInitializer>, seed: Option<u64>) -> NnResult<DenseModel> {
 
-            weights.push(Matrix::new(shapes[i].range, shapes[i + 1].range).shuffle());
-            biases.push(Matrix::new(1, shapes[i + 1].range).shuffle());
+        let length = activations.len();
+
+        if shapes.len() != length + 1 || weight_initializers.len() != length || bias_initializers.len() != length {
+            return Err(NnError::ShapeMismatch {
+                operation: "DenseModel::with_custom",
+                left: (shapes.len(), length),
+                right: (weight_initializers.len(), bias_initializers.len())
+            });
         }
-        values.push(Matrix::new(1,shapes[length].range));
-        raw_values.push(Matrix::new(1,shapes[length].range));
-
-        DenseModel {
-            nb_layers: shapes.len(),
-            loss: loss,
-            activations: activations_arr,
-            weights: weights,
-            biases: biases,
-            raw_values: raw_values,
-            values: values
+
+        let mut layers: Vec<Box<dyn Layer>> = Vec::with_capacity(4 * length);
+
+        for (i, activation) in activations.into_iter().enumerate() {
+            layers.push(Box::new(Dense::new(shapes[i + 1].range).initialized(weight_initializers[i], bias_initializers[i])));
+            layers.push(Box::new(Normalization::new(DenseNormalization::NoNormalization)));
+            layers.push(Box::new(ActivationLayer::new(activation)));
+
+            if i + 1 < length {
+                layers.push(Box::new(Dropout::new(DenseDropout::NoDropout)));
+            }
         }
+
+        Ok(DenseModel {
+            model: Sequential::new(shapes[0].range, layers, loss, seed)?,
+            weight_initializers,
+            bias_initializers,
+            regularizers: vec![DenseRegularizer::NoRegularizer; length],
+            constraints: vec![DenseConstraint::NoConstraint; length],
+            dropouts: vec![DenseDropout::NoDropout; length.saturating_sub(1)],
+            normalizations: vec![DenseNormalization::NoNormalization; length]
+        })
     }
 
-    pub fn result(&self) -> Matrix{
-        self.values[self.nb_layers - 1].copy()
+    pub fn loss(&self) -> &dyn Loss {
+        self.model.loss.as_ref()
     }
 
-    pub fn feed_forward(&mut self, input: &Matrix) {
-        if input.y_length != self.values[0].y_length {
-            return;
+    fn check_layer(&self, layer: usize, operation: &'static str) -> NnResult<()> {
+        if layer >= self.regularizers.len() {
+            return Err(NnError::IndexOutOfRange {
+                operation,
+                y: layer,
+                x: 0,
+                y_length: self.regularizers.len(),
+                x_length: 1
+            });
         }
-        self.values[0] = input.copy();
-        self.raw_values[0] = self.values[0].copy();
+        Ok(())
+    }
+
+    // rebuilds the Dense layer of the given layer with its current settings, keeping its weights and biases
+    fn rebuild_dense(&mut self, layer: usize) -> NnResult<()> {
+        let parameters: Vec<Matrix> = self.model.layers()[slot(layer)].parameters().into_iter().map(Matrix::copy).collect();
 
-        for i in 0..(self.nb_layers - 1) {
+        let dense = Dense::new(parameters[1].y_length)
+            .initialized(self.weight_initializers[layer], self.bias_initializers[layer])
+            .regularized(self.regularizers[layer])
+            .constrained(self.constraints[layer]);
+        self.model.replace_layer(slot(layer), Box::new(dense))?;
 
-            let mut mat = Matrix::dot(&self.weights[i], &self.values[i]);
+        let dense = &mut self.model.layers_mut()[slot(layer)];
 
-            mat = Matrix::add(&mat, &self.biases[i]);
-            self.raw_values[i + 1] = mat.copy();
-            
-            apply_activation(&self.activations[i], &mut mat);
-            self.values[i + 1] = mat;
+        for (parameter, values) in dense.parameters_mut().into_iter().zip(parameters) {
+            *parameter = values;
         }
+        dense.apply_constraints();
+        Ok(())
     }
 
-    pub fn back_propagate(&mut self, output: &Matrix) -> Vec<Vec<f64>> {
-        
-        let mut deltas: Vec<Vec<f64>> = Vec::with_capacity(self.nb_layers);
-        for _ in 0..self.nb_layers {
-            deltas.push(Vec::new());
-        }
+    // layer 0 holds the weights between the inputs and the first hidden layer
+    pub fn set_regularizer(&mut self, layer: usize, regularizer: DenseRegularizer) -> NnResult<()> {
+        self.check_layer(layer, "DenseModel::set_regularizer")?;
+        regularizer.check()?;
 
-        for l in (1..self.nb_layers).rev() {
-            for i in 0..self.weights[l - 1].y_length {
-                let d_activation = apply_derivation(&self.activations[l - 1], self.raw_values[l].get(i,0));
-                let result: f64;
-                
-                if l == self.nb_layers - 1 {
-                    let d_cost = derivative_error(&self.loss, self.values[l].y_length, self.values[l].get(i,0), output.get(i,0));
-                    result = d_activation * d_cost;
-                }
-                else {
+        self.regularizers[layer] = regularizer;
+        self.rebuild_dense(layer)
+    }
 
-                    let mut sum: f64 = 0.0;
+    pub fn set_constraint(&mut self, layer: usize, constraint: DenseConstraint) -> NnResult<()> {
+        self.check_layer(layer, "DenseModel::set_constraint")?;
+        constraint.check()?;
 
-                    for j in 0..self.weights[l].y_length {
-                        // why weights[l] and not l + 1 ?
-                        // Well, it is for the unique reason that self.weights.len() = self.nb_layers - 1
-                        sum += deltas[l + 1][j] * self.weights[l].get(j,i);
-                    }
-                    result = sum * d_activation;
-                }
-            
-                deltas[l].push(result);
+        self.constraints[layer] = constraint;
+        self.rebuild_dense(layer)
+    }
+
+    // normalizes the outputs of the affine transformation of the given layer before its activation,
+    // resetting its scale, shift and running statistics
+    pub fn set_normalization(&mut self, layer: usize, normalization: DenseNormalization) -> NnResult<()> {
+        self.check_layer(layer, "DenseModel::set_normalization")?;
+        self.model.replace_layer(slot(layer) + 1, Box::new(Normalization::new(normalization)))?;
+        self.normalizations[layer] = normalization;
+        Ok(())
+    }
+
+    pub fn normalizations(&self) -> &[DenseNormalization] {
+        &self.normalizations
+    }
+
+    // running mean (first row) and variance (second row) of each BatchNorm layer, empty for the others
+    pub fn running_statistics(&self) -> Vec<Matrix> {
+        (0..self.normalizations.len()).map(|l| {
+            self.model.layers()[slot(l) + 1].running_statistics().first().map_or(Matrix::new(0, 0), |statistics| statistics.copy())
+        }).collect()
+    }
+
+    pub fn set_running_statistics(&mut self, statistics: &[Matrix]) -> NnResult<()> {
+        let current: Vec<Matrix> = self.running_statistics();
+
+        if statistics.len() != current.len() {
+            return Err(NnError::ShapeMismatch {
+                operation: "DenseModel::set_running_statistics",
+                left: (current.len(), 1),
+                right: (statistics.len(), 1)
+            });
+        }
+
+        for (current, new) in current.iter().zip(statistics.iter()) {
+            if current.x_length != new.x_length || current.y_length != new.y_length {
+                return Err(NnError::ShapeMismatch {
+                    operation: "DenseModel::set_running_statistics",
+                    left: (current.y_length, current.x_length),
+                    right: (new.y_length, new.x_length)
+                });
             }
-            deltas[l].reverse();
         }
-        deltas
+
+        for (l, new) in statistics.iter().enumerate() {
+            for statistic in self.model.layers_mut()[slot(l) + 1].running_statistics_mut() {
+                *statistic = new.copy();
+            }
+        }
+        Ok(())
     }
 
-    pub fn update_weights(&mut self, deltas: &Vec<Vec<f64>>, learning_rate: f64) {
-        for l in (1..self.nb_layers).rev() {
-            for i in 0..self.weights[l - 1].y_length {
-                
-                let derivative_wrt_bias: f64 = deltas[l][i];
+    pub fn regularizers(&self) -> &[DenseRegularizer] {
+        &self.regularizers
+    }
 
-                for j in 0..self.weights[l - 1].x_length {
+    pub fn constraints(&self) -> &[DenseConstraint] {
+        &self.constraints
+    }
 
-                    let derivative_wrt_weight: f64 = self.values[l - 1].get(j,0) * deltas[l][i];
-                    let new_weight_value: f64 = self.weights[l - 1].get(i, j) - derivative_wrt_weight * learning_rate;
-                    self.weights[l - 1].set(i, j, new_weight_value); 
-                }
-                self.biases[l - 1].set(i,0, derivative_wrt_bias);
-            }
+    // drops the outputs of the given hidden layer (0 being the first hidden layer) while training
+    pub fn set_dropout(&mut self, layer: usize, dropout: DenseDropout) -> NnResult<()> {
+        if layer >= self.dropouts.len() {
+            return Err(NnError::IndexOutOfRange {
+                operation: "DenseModel::set_dropout",
+                y: layer,
+                x: 0,
+                y_length: self.dropouts.len(),
+                x_length: 1
+            });
         }
+        dropout.check()?;
+
+        self.model.replace_layer(slot(layer) + 3, Box::new(Dropout::new(dropout)))?;
+        self.dropouts[layer] = dropout;
+        Ok(())
     }
 
-    pub fn save(&self, filename: &String) {
+    pub fn dropouts(&self) -> &[DenseDropout] {
+        &self.dropouts
+    }
 
-        let mut archi_filename = filename.clone();
-        let mut weights_filename = filename.clone();
+    // dropout masks are only drawn in training mode, which Session::train enables while it updates the weights
+    pub fn set_training(&mut self, training: bool) {
+        self.model.set_training(training);
+    }
 
-        archi_filename.push_str(".arch");
-        weights_filename.push_str(".wab");
+    pub fn is_training(&self) -> bool {
+        self.model.is_training()
+    }
 
-        let mut archi_file: File = File::create(archi_filename).expect("Error while creating the file: {archi_filename}");
-        let mut weights_file: File = File::create(weights_filename).expect("Error while creating the file: {weights_filename}");
+    // reseeds the generator drawing the dropout masks, e.g. after loading the model back,
+    // so that resuming a training is reproducible
+    pub fn set_seed(&mut self, seed: u64) {
+        self.model.set_seed(seed);
+    }
 
-        let structures: Vec<usize> = self.values.iter().map(|x| {x.y_length}).collect();
+    // sum of the penalties of every layer, to be added to the loss
+    pub fn regularization_loss(&self) -> f64 {
+        self.model.regularization_loss()
+    }
 
-        let mut archi_content: String = String::new();
+    pub fn result(&self) -> Matrix {
+        self.model.result()
+    }
 
-        archi_content.push_str(&structures.len().to_string());
-        archi_content.push_str("\n");
+    // input is either a single sample (column vector) or a batch where each column is a sample
+    pub fn feed_forward(&mut self, input: &Matrix) -> NnResult<()> {
+        self.model.feed_forward(input)
+    }
 
-        // saving the neurons and layers structure
-        for i in 0..structures.len() {
-            archi_content.push_str(&structures[i].to_string());
+    // returns the deltas of each layer, with respect to the outputs of its affine transformation (deltas[0] is
+    // left empty as the input layer has none), with one column per sample of the last batch given to feed_forward
+    pub fn back_propagate(&mut self, output: &Matrix) -> NnResult<Vec<Matrix>> {
+        let deltas = self.model.back_propagate(output)?;
+        Ok(self.layer_deltas(deltas))
+    }
+
+    // same as back_propagate for the loss given by Loss::weighted_error:
+    // the deltas of each sample are multiplied by its weight (weights being a row vector)
+    pub fn back_propagate_weighted(&mut self, output: &Matrix, weights: &Matrix) -> NnResult<Vec<Matrix>> {
+        let deltas = self.model.back_propagate_weighted(output, weights)?;
+        Ok(self.layer_deltas(deltas))
+    }
+
+    // the gradients w.r.t. the inputs of the Normalization layers, among those of every layer of the model
+    fn layer_deltas(&self, mut deltas: Vec<Matrix>) -> Vec<Matrix> {
+        let mut layer_deltas: Vec<Matrix> = vec![Matrix::new(0, 0)];
+        layer_deltas.extend((0..self.regularizers.len()).map(|l| std::mem::replace(&mut deltas[slot(l) + 1], Matrix::new(0, 0))));
+        layer_deltas
+    }
+
+    // index in copy_parameters of each parameter of the Sequential model, taken in its own order
+    fn parameter_indices(&self) -> Vec<usize> {
+        let length: usize = self.regularizers.len();
+        let layers = self.model.layers();
+        let mut indices: Vec<usize> = Vec::with_capacity(4 * length);
 
-            if i != structures.len() - 1 {
-                archi_content.push_str(" ");
+        for l in 0..length {
+            indices.extend([2 * l, 2 * l + 1]);
+
+            if !layers[slot(l) + 1].parameters().is_empty() {
+                indices.push(3 * length + l);
+            }
+            if !layers[slot(l) + 2].parameters().is_empty() {
+                indices.push(2 * length + l);
             }
         }
-        archi_content.push_str("\n");
+        indices
+    }
 
-        // saving the activations functions
-        for i in 0..self.activations.len() {
-            archi_content.push_str(&self.activations[i].to_string());
+    // the parameters of the Sequential model at their index, empty matrices for the layers without some
+    fn by_kind(&self, parameters: Vec<Matrix>) -> Vec<Matrix> {
+        let mut by_kind: Vec<Matrix> = (0..4 * self.regularizers.len()).map(|_| Matrix::new(0, 0)).collect();
+
+        for (index, parameter) in self.parameter_indices().into_iter().zip(parameters) {
+            by_kind[index] = parameter;
+        }
+        by_kind
+    }
 
-            if i != structures.len() - 1 {
-                archi_content.push_str(" ");
+    // returns the gradient of every parameter, in the order [weights 0, biases 0, weights 1, ...]
+    // followed by the activation parameters of every layer (empty when the activation has none),
+    // then by the normalization parameters of every layer (empty without normalization)
+    // gradients are averaged over the batch, so a single update is applied per batch,
+    // and include the gradient of the regularization penalties
+    pub fn gradients(&self, deltas: &[Matrix]) -> NnResult<Vec<Matrix>> {
+        Ok(self.by_kind(self.model.gradients(deltas)?))
+    }
+
+    // the optimizer keeps the state of each parameter under its index in gradients
+    pub fn update_weights(&mut self, gradients: &[Matrix], optimizer: &mut dyn Optimizer,
+        learning_rate: f64) -> NnResult<()> {
+
+        let indices: Vec<usize> = self.parameter_indices();
+        self.model.update_indexed(gradients, &indices, optimizer, learning_rate)
+    }
+
+    // copies every parameter, in the same order as the gradients
+    pub fn copy_parameters(&self) -> Vec<Matrix> {
+        self.by_kind(self.model.copy_parameters())
+    }
+
+    pub fn set_parameters(&mut self, parameters: &[Matrix]) -> NnResult<()> {
+        let current: Vec<Matrix> = self.copy_parameters();
+
+        if parameters.len() != current.len() {
+            return Err(NnError::ShapeMismatch {
+                operation: "DenseModel::set_parameters",
+                left: (current.len(), 1),
+                right: (parameters.len(), 1)
+            });
+        }
+
+        for (current, new) in current.iter().zip(parameters.iter()) {
+            if current.x_length != new.x_length || current.y_length != new.y_length {
+                return Err(NnError::ShapeMismatch {
+                    operation: "DenseModel::set_parameters",
+                    left: (current.y_length, current.x_length),
+                    right: (new.y_length, new.x_length)
+                });
             }
         }
-        archi_content.push_str("\n");
 
-        // saving the error / cost function
-        archi_content.push_str(&self.loss.to_string());
-        archi_content.push_str("\n");
+        let ordered: Vec<Matrix> = self.parameter_indices().into_iter().map(|index| parameters[index].copy()).collect();
+        self.model.set_parameters(&ordered)
+    }
 
+    pub fn save(&self, filename: &str) -> NnResult<()> {
 
-        archi_file.write_all(archi_content.as_bytes()).expect("Error while saving the architecture of the model.");
+        let archi_filename = format!("{filename}.arch");
+        let weights_filename = format!("{filename}.wab");
 
+        let mut archi_file: File = File::create(&archi_filename).map_err(|e| NnError::io(&archi_filename, e))?;
+        let mut weights_file: File = File::create(&weights_filename).map_err(|e| NnError::io(&weights_filename, e))?;
 
-        let mut weights_content: String = String::new();
+        let length: usize = self.regularizers.len();
+        let layers = self.model.layers();
+        let parameters: Vec<Matrix> = self.copy_parameters();
+        let statistics: Vec<Matrix> = self.running_statistics();
 
-        for l in 0..self.weights.len() {
+        let mut structures: Vec<usize> = vec![self.model.input_shape().range];
+        structures.extend((0..length).map(|l| parameters[2 * l + 1].y_length));
 
-            for i in 0..self.weights[l].y_length {
+        let mut archi_content: String = String::new();
 
-                for j in 0..self.weights[l].x_length {
+        archi_content.push_str(&structures.len().to_string());
+        archi_content.push('\n');
 
-                    weights_content.push_str(&self.weights[l].get(i,j).to_string());
+        // saving the neurons and layers structure
+        let structures: Vec<String> = structures.iter().map(|x| x.to_string()).collect();
+        archi_content.push_str(&structures.join(" "));
+        archi_content.push('\n');
 
-                    if j != self.weights[l].x_length - 1 {
-                        weights_content.push_str(" ");
-                    }
-                }
-                weights_content.push_str("\n");
-                weights_content.push_str(&self.biases[l].get(i,0).to_string());
+        // saving the activations functions
+        let activations: Vec<String> = (0..length)
+            .map(|l| layers[slot(l) + 2].activation().map_or(String::new(), |activation| activation.name()))
+            .collect();
+        archi_content.push_str(&activations.join(" "));
+        archi_content.push('\n');
 
-                if l != self.weights.len() - 1 {
-                    weights_content.push_str("\n");
-                }
+        // saving the error / cost function
+        archi_content.push_str(&self.loss().name());
+        archi_content.push('\n');
+
+        // saving the initializers of the weights and of the biases, then the regularizers, constraints,
+        // dropouts and normalizations
+        let per_layer: [Vec<String>; 6] = [
+            self.weight_initializers.iter().map(|x| x.to_string()).collect(),
+            self.bias_initializers.iter().map(|x| x.to_string()).collect(),
+            self.regularizers.iter().map(|x| x.to_string()).collect(),
+            self.constraints.iter().map(|x| x.to_string()).collect(),
+            self.dropouts.iter().map(|x| x.to_string()).collect(),
+            self.normalizations.iter().map(|x| x.to_string()).collect()
+        ];
+
+        for names in per_layer {
+            archi_content.push_str(&names.join(" "));
+            archi_content.push('\n');
+        }
+
+
+        archi_file.write_all(archi_content.as_bytes()).map_err(|e| NnError::io(&archi_filename, e))?;
+
+
+        let mut weights_content: String = String::new();
+
+        for l in 0..length {
+            let (weights, biases) = (&parameters[2 * l], &parameters[2 * l + 1]);
+
+            // the weights of each neuron, then its bias
+            for i in 0..weights.y_length {
+                let row: Vec<String> = weights.values[i * weights.x_length..(i + 1) * weights.x_length].iter()
+                    .map(|x| x.to_string()).collect();
+
+                weights_content.push_str(&row.join(" "));
+                weights_content.push('\n');
+                weights_content.push_str(&biases.values[i].to_string());
+                weights_content.push('\n');
+            }
+
+            // learnable activations add a line with their parameters after the layer, normalizations
+            // add a line for gamma and one for beta, then BatchNorm one for the running mean and one for
+            // the running variance
+            let mut extra_lines: Vec<&[f64]> = vec![&parameters[2 * length + l].values];
+            extra_lines.extend(parameters[3 * length + l].values.chunks(weights.y_length.max(1)));
+            extra_lines.extend(statistics[l].values.chunks(weights.y_length.max(1)));
+
+            for values in extra_lines.into_iter().filter(|values| !values.is_empty()) {
+                let values: Vec<String> = values.iter().map(|x| x.to_string()).collect();
+                weights_content.push_str(&values.join(" "));
+                weights_content.push('\n');
             }
         }
 
-        weights_file.write_all(weights_content.as_bytes()).expect("Error while saving the weights and biases of the model.");
-    }
+        weights_file.write_all(weights_content.as_bytes()).map_err(|e| NnError::io(&weights_filename, e))?;
 
-    pub fn load_model(filename: &String) -> DenseModel {
+        Ok(())
+    }
 
-        let mut archi_filename = filename.clone();
-        let mut weights_filename = filename.clone();
+    pub fn load_model(filename: &str) -> NnResult<DenseModel> {
+        DenseModel::load_model_with(filename, &Registry::new())
+    }
 
-        archi_filename.push_str(".arch");
-        weights_filename.push_str(".wab");
+    // same as load_model, resolving the names of custom activations and losses through the registry
+    pub fn load_model_with(filename: &str, registry: &Registry) -> NnResult<DenseModel> {
 
-        let  archi_file: File = File::open(archi_filename).expect("Error while creating the file: {archi_filename}");
-        let  weights_file: File = File::open(weights_filename).expect("Error while creating the file: {weights_filename}");
+        let archi_filename = format!("{filename}.arch");
+        let weights_filename = format!("{filename}.wab");
 
-        let mut archi_reader = BufReader::new(archi_file);
-        let mut weights_reader = BufReader::new(weights_file);
+        let archi_file: File = File::open(&archi_filename).map_err(|e| NnError::io(&archi_filename, e))?;
+        let weights_file: File = File::open(&weights_filename).map_err(|e| NnError::io(&weights_filename, e))?;
 
-        let mut buffer: String = String::new();
+        let mut archi_lines = BufReader::new(archi_file).lines();
+        let mut weights_lines = BufReader::new(weights_file).lines();
 
         // getting the number of layers 
-        archi_reader.read_line(&mut buffer).expect("Failed to read the number of layers.");
+        let buffer = read_next_line(&mut archi_lines, &archi_filename, 1)?;
 
-        let nb_layers: usize = buffer.trim().parse::<usize>().expect("Cannot parse the supposed number of layers.");
+        let nb_layers: usize = buffer.trim().parse::<usize>()
+            .map_err(|_| NnError::parse(&archi_filename, 1, "Cannot parse the supposed number of layers."))?;
 
-        let mut weights: Vec<Matrix> = Vec::with_capacity(nb_layers - 1);
-        let mut biases: Vec<Matrix> = Vec::with_capacity(nb_layers - 1);
+        if nb_layers < 2 {
+            return Err(NnError::parse(&archi_filename, 1, "A model needs at least an input and an output layer."));
+        }
 
-        let mut values: Vec<Matrix> = Vec::with_capacity(nb_layers);
-        let mut raw_values: Vec<Matrix> = Vec::with_capacity(nb_layers);
+        // getting the structure of each layer
+        let buffer = read_next_line(&mut archi_lines, &archi_filename, 2)?;
+        let structures: Vec<usize> = buffer.trim().split(' ').map(|x| {
+            x.parse::<usize>().map_err(|_| NnError::parse(&archi_filename, 2, "Cannot parse the structure of each layer."))
+        }).collect::<NnResult<Vec<usize>>>()?;
 
-        let activations: Vec<DenseActivation>;
-        let loss: DenseLosses;
+        if structures.len() != nb_layers {
+            return Err(NnError::parse(&archi_filename, 2, "The structure does not match the number of layers."));
+        }
 
+        let buffer = read_next_line(&mut archi_lines, &archi_filename, 3)?;
+        let activations: Vec<Box<dyn Activation>> = buffer.trim().split(' ')
+            .map(|x| registry.activation(x))
+            .collect::<NnResult<Vec<Box<dyn Activation>>>>()?;
 
-        buffer = String::new();
-        archi_reader.read_line(&mut buffer).expect("Failed to read the structure of each layers");
-        // getting the structure of each layer
-        let structures: Vec<usize> = buffer.trim().split(' ').map(|x| {x.parse::<usize>().expect("Cannot parse the structure of each layer.")}).collect();
+        if activations.len() != nb_layers - 1 {
+            return Err(NnError::parse(&archi_filename, 3, "Expected one activation function per layer."));
+        }
 
-        for i in 0..(structures.len() - 1) {
-            values.push(Matrix::new(1,structures[i]));
-            raw_values.push(Matrix::new(1,structures[i]).shuffle());
+        let buffer = read_next_line(&mut archi_lines, &archi_filename, 4)?;
+        let loss: Box<dyn Loss> = registry.loss(buffer.trim())?;
+
+        // architectures saved before the initializers, regularizers, constraints, dropouts and normalizations
+        // were recorded stop after the loss, otherwise each of these lines is expected
+        let next_line = archi_lines.next();
+        let legacy: bool = next_line.is_none();
+        let mut archi_lines = next_line.into_iter().chain(archi_lines);
+
+        let weight_initializers = read_layer_line(&mut archi_lines, &archi_filename, 5, nb_layers - 1,
+            legacy.then_some(DenseInitializer::Uniform))?;
+        let bias_initializers = read_layer_line(&mut archi_lines, &archi_filename, 6, nb_layers - 1,
+            legacy.then_some(DenseInitializer::Uniform))?;
+        let regularizers = read_layer_line(&mut archi_lines, &archi_filename, 7, nb_layers - 1,
+            legacy.then_some(DenseRegularizer::NoRegularizer))?;
+        let constraints = read_layer_line(&mut archi_lines, &archi_filename, 8, nb_layers - 1,
+            legacy.then_some(DenseConstraint::NoConstraint))?;
+        let dropouts = read_layer_line(&mut archi_lines, &archi_filename, 9, nb_layers - 2,
+            legacy.then_some(DenseDropout::NoDropout))?;
+        let normalizations = read_layer_line(&mut archi_lines, &archi_filename, 10, nb_layers - 1,
+            legacy.then_some(DenseNormalization::NoNormalization))?;
+
+        let shapes: Vec<DenseShape> = structures.iter().map(|range| DenseShape::new(*range, 1, 1)).collect();
+        let mut model = DenseModel::with_custom(activations, loss, shapes, weight_initializers, bias_initializers, None)?;
 
-            weights.push(Matrix::new(structures[i], structures[i + 1]));
-            biases.push(Matrix::new(1, structures[i + 1]));
+        for l in 0..(nb_layers - 1) {
+            model.set_regularizer(l, regularizers[l])?;
+            model.set_constraint(l, constraints[l])?;
+            model.set_normalization(l, normalizations[l])?;
+        }
+        for (l, dropout) in dropouts.into_iter().enumerate() {
+            model.set_dropout(l, dropout)?;
         }
-        values.push(Matrix::new(1,structures[structures.len() - 1]));
-        raw_values.push(Matrix::new(1,structures[structures.len() - 1]));
 
-        buffer = String::new();
-        archi_reader.read_line(&mut buffer).expect("Failed to read the activation function of each layer.");
-        activations = buffer.trim().split(' ').map(|x| {DenseActivation::from_str(x).expect("Cannot parse the activation function")}).collect();
+        let length: usize = nb_layers - 1;
+        let mut parameters: Vec<Matrix> = model.copy_parameters();
+        let mut statistics: Vec<Matrix> = model.running_statistics();
 
-        buffer = String::new();
-        archi_reader.read_line(&mut buffer).expect("Failed to read the cost function of the model.");
-        buffer = buffer.trim().to_string();
-        loss = DenseLosses::from_str(&buffer).expect("Failed to parse the cost function of the model.");
+        let (weights_and_biases, extra) = parameters.split_at_mut(2 * length);
+        let (activation_parameters, normalization_parameters) = extra.split_at_mut(length);
 
+        let mut line_nb: usize = 0;
 
-        for l in 0..(nb_layers - 1) {
+        for l in 0..length {
+            let (weights, biases) = weights_and_biases[2 * l..].split_at_mut(1);
+            let (weights, biases) = (&mut weights[0], &mut biases[0]);
 
-            for i in 0..weights[l].y_length {
+            for i in 0..weights.y_length {
 
-                buffer = String::new();
-                weights_reader.read_line(&mut buffer).expect("Failed to read the weight line.");
-                let weights_values: Vec<f64> = buffer.trim().split(' ').map(|x| {x.parse::<f64>().expect("Cannot parse a weight value into a floating point.")}).collect();
+                line_nb += 1;
+                let buffer = read_next_line(&mut weights_lines, &weights_filename, line_nb)?;
+                let weights_values: Vec<f64> = buffer.trim().split(' ').map(|x| {
+                    x.parse::<f64>().map_err(|_| NnError::parse(&weights_filename, line_nb, "Cannot parse a weight value into a floating point."))
+                }).collect::<NnResult<Vec<f64>>>()?;
+
+                if weights_values.len() != weights.x_length {
+                    return Err(NnError::parse(&weights_filename, line_nb, "Unexpected number of weights on this line."));
+                }
 
-                for j in 0..weights[l].x_length {
-                    weights[l].set(i,j,weights_values[j]);
+                for (j, weight) in weights_values.iter().enumerate() {
+                    weights.set(i,j,*weight);
                 }
 
-                buffer = String::new();
-                weights_reader.read_line(&mut buffer).expect("Failed to read the bias line.");
-                biases[l].set(i,0, buffer.trim().parse::<f64>().expect("Cannot parse a bias value into a floating point."));
+                line_nb += 1;
+                let buffer = read_next_line(&mut weights_lines, &weights_filename, line_nb)?;
+                biases.set(i,0, buffer.trim().parse::<f64>()
+                    .map_err(|_| NnError::parse(&weights_filename, line_nb, "Cannot parse a bias value into a floating point."))?);
+            }
+
+            // the extra lines of the layer, in the order they are saved
+            let nb_neurons: usize = structures[l + 1];
+            let mut extra_lines: Vec<&mut [f64]> = vec![&mut activation_parameters[l].values];
+            extra_lines.extend(normalization_parameters[l].values.chunks_mut(nb_neurons.max(1)));
+            extra_lines.extend(statistics[l].values.chunks_mut(nb_neurons.max(1)));
+
+            for values in extra_lines.into_iter().filter(|values| !values.is_empty()) {
+                line_nb += 1;
+                let buffer = read_next_line(&mut weights_lines, &weights_filename, line_nb)?;
+                let parameters: Vec<f64> = buffer.split_whitespace().map(|x| {
+                    x.parse::<f64>().map_err(|_| NnError::parse(&weights_filename, line_nb, "Cannot parse a layer parameter into a floating point."))
+                }).collect::<NnResult<Vec<f64>>>()?;
+
+                if parameters.len() != values.len() {
+                    return Err(NnError::parse(&weights_filename, line_nb, "Unexpected number of layer parameters on this line."));
+                }
+                values.copy_from_slice(&parameters);
             }
         };
 
-        DenseModel {
-            nb_layers: nb_layers,
-            loss: loss,
-            activations: activations,
-            weights: weights,
-            biases: biases,
-            raw_values: raw_values,
-            values: values
-        }
+        model.set_parameters(&parameters)?;
+        model.set_running_statistics(&statistics)?;
+        Ok(model)
     } 
-}
\ No newline at end of file
+}
+
+// reads a line holding one value per layer, unless a default is given to every layer
+fn read_layer_line<T: FromStr<Err = NnError> + Clone>(lines: &mut impl Iterator<Item = io::Result<String>>,
+    filename: &str, line_nb: usize, nb_layers: usize, default: Option<T>) -> NnResult<Vec<T>> {
+
+    if let Some(default) = default {
+        return Ok(vec![default; nb_layers]);
+    }
+
+    let parsed: Vec<T> = read_next_line(lines, filename, line_nb)?
+        .split_whitespace()
+        .map(|value| T::from_str(value).map_err(|e| NnError::parse(filename, line_nb, &e.to_string())))
+        .collect::<NnResult<Vec<T>>>()?;
+
+    if parsed.len() != nb_layers {
+        return Err(NnError::parse(filename, line_nb, "Expected one value per layer."));
+    }
+    Ok(parsed)
+}
+
+pub(crate) fn read_next_line(lines: &mut impl Iterator<Item = io::Result<String>>, filename: &str, line_nb: usize)
+    -> NnResult<String> {
+
+    match lines.next() {
+        Some(line) => line.map_err(|e| NnError::io(filename, e)),
+        None => Err(NnError::parse(filename, line_nb, "Unexpected end of file."))
+    }
+}
@@ -0,0 +1,142 @@
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+use crate::optimizers::sgd::Sgd;
+use crate::regularizers::dense_dropout::DenseDropout;
+
+use super::dense_model::DenseModel;
+use super::model::Model;
+
+// Maximum relative errors between the analytic and the numerical gradients of one layer.
+pub struct LayerGradientCheck {
+    pub weights_error: f64,
+    pub biases_error: f64,
+
+    // zero when the activation of the layer has no learnable parameters
+    pub activation_error: f64,
+
+    // zero when the layer is not normalized
+    pub normalization_error: f64,
+
+    // difference between the step done by update_weights (plain SGD, learning rate of 1)
+    // and the analytic gradient
+    pub update_error: f64
+}
+
+impl LayerGradientCheck {
+    pub fn max_error(&self) -> f64 {
+        self.weights_error.max(self.biases_error).max(self.activation_error).max(self.normalization_error)
+            .max(self.update_error)
+    }
+}
+
+// relative error, guarded for gradients that are both (close to) zero
+pub fn relative_error(analytic: f64, numerical: f64) -> f64 {
+    (analytic - numerical).abs() / (analytic.abs() + numerical.abs()).max(1e-6)
+}
+
+fn loss_at(model: &mut dyn Model, input: &Matrix, output: &Matrix) -> NnResult<f64> {
+    model.feed_forward(input)?;
+    Ok(model.loss().error(&model.result(), output)? + model.regularization_loss())
+}
+
+// For every parameter matrix of any model, in the order of Model::copy_parameters, the maximum relative
+// error between the analytic gradients and central finite differences of the loss (perturbing each value
+// by epsilon), and between the analytic gradients and the step done by update_weights.
+// The model parameters are left unchanged.
+pub fn parameter_errors(model: &mut dyn Model, input: &Matrix, output: &Matrix, epsilon: f64)
+    -> NnResult<(Vec<f64>, Vec<f64>)> {
+
+    let parameters = model.copy_parameters();
+
+    // uniform weights, so that back_propagate_weighted gives the plain gradients
+    let mut weights = Matrix::new(input.x_length, 1);
+    weights.values.iter_mut().for_each(|w| *w = 1.0);
+
+    model.feed_forward(input)?;
+    let deltas = model.back_propagate_weighted(output, &weights)?;
+    let gradients = model.gradients(&deltas)?;
+
+    // the errors of every parameter, in the order of the gradients
+    let mut errors: Vec<f64> = Vec::with_capacity(parameters.len());
+    let mut perturbed = model.copy_parameters();
+
+    for p in 0..parameters.len() {
+        let mut max_error: f64 = 0.0;
+
+        for k in 0..parameters[p].values.len() {
+            let original: f64 = parameters[p].values[k];
+
+            perturbed[p].values[k] = original + epsilon;
+            model.set_parameters(&perturbed)?;
+            let loss_plus: f64 = loss_at(model, input, output)?;
+
+            perturbed[p].values[k] = original - epsilon;
+            model.set_parameters(&perturbed)?;
+            let loss_minus: f64 = loss_at(model, input, output)?;
+
+            perturbed[p].values[k] = original;
+
+            let numerical: f64 = (loss_plus - loss_minus) / (2.0 * epsilon);
+            max_error = max_error.max(relative_error(gradients[p].values[k], numerical));
+        }
+        errors.push(max_error);
+    }
+
+    // one SGD step with a learning rate of 1 must move every parameter by minus its gradient
+    model.set_parameters(&parameters)?;
+    model.update_weights(&gradients, &mut Sgd::new(), 1.0)?;
+    let updated = model.copy_parameters();
+
+    let mut update_errors: Vec<f64> = Vec::with_capacity(parameters.len());
+
+    for p in 0..parameters.len() {
+        let mut max_error: f64 = 0.0;
+
+        for k in 0..parameters[p].values.len() {
+            let step: f64 = parameters[p].values[k] - updated[p].values[k];
+            max_error = max_error.max(relative_error(gradients[p].values[k], step));
+        }
+        update_errors.push(max_error);
+    }
+
+    model.set_parameters(&parameters)?;
+    Ok((errors, update_errors))
+}
+
+// Compares the gradients given by back_propagate (averaged over the batch of samples held by the
+// columns of input and output) with central finite differences of the loss, perturbing every weight,
+// every bias, every activation and normalization parameter by epsilon (see parameter_errors).
+// The check runs in training mode, so that BatchNorm uses the statistics of the batch, but without dropout,
+// as it would draw a new mask at every forward pass.
+pub fn gradient_check(model: &mut DenseModel, input: &Matrix, output: &Matrix, epsilon: f64)
+    -> NnResult<Vec<LayerGradientCheck>> {
+
+    let training: bool = model.is_training();
+    let dropouts: Vec<DenseDropout> = model.dropouts().to_vec();
+    let statistics: Vec<Matrix> = model.running_statistics();
+
+    model.set_training(true);
+    for l in 0..dropouts.len() {
+        model.set_dropout(l, DenseDropout::NoDropout)?;
+    }
+
+    let (errors, update_errors) = parameter_errors(model, input, output, epsilon)?;
+
+    model.set_running_statistics(&statistics)?;
+    model.set_training(training);
+
+    for (l, dropout) in dropouts.into_iter().enumerate() {
+        model.set_dropout(l, dropout)?;
+    }
+
+    let nb_layers: usize = errors.len() / 4;
+
+    Ok((0..nb_layers).map(|l| LayerGradientCheck {
+        weights_error: errors[2 * l],
+        biases_error: errors[2 * l + 1],
+        activation_error: errors[2 * nb_layers + l],
+        normalization_error: errors[3 * nb_layers + l],
+        update_error: update_errors[2 * l].max(update_errors[2 * l + 1]).max(update_errors[2 * nb_layers + l])
+            .max(update_errors[3 * nb_layers + l])
+    }).collect())
+}
@@ -1 +1,5 @@
-pub mod dense_model;
\ No newline at end of file
+pub mod dense_model;
+pub mod gradient_check;
+pub mod model;
+pub mod registry;
+pub mod sequential;
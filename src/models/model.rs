@@ -0,0 +1,130 @@
+use crate::errors::nn_error::NnResult;
+use crate::losses::loss::Loss;
+use crate::maths::matrices::Matrix;
+use crate::optimizers::optimizer::Optimizer;
+
+use super::dense_model::DenseModel;
+use super::registry::Registry;
+use super::sequential::Sequential;
+
+// What a Session needs to train a model, implemented by DenseModel and Sequential.
+// Parameters and gradients are given in the same order, which the optimizers rely on.
+pub trait Model {
+    fn loss(&self) -> &dyn Loss;
+
+    // input holds one sample per column
+    fn feed_forward(&mut self, input: &Matrix) -> NnResult<()>;
+
+    fn result(&self) -> Matrix;
+
+    // weights is a row vector holding the weight of each sample
+    fn back_propagate_weighted(&mut self, output: &Matrix, weights: &Matrix) -> NnResult<Vec<Matrix>>;
+
+    fn gradients(&self, deltas: &[Matrix]) -> NnResult<Vec<Matrix>>;
+
+    fn update_weights(&mut self, gradients: &[Matrix], optimizer: &mut dyn Optimizer, learning_rate: f64) -> NnResult<()>;
+
+    fn regularization_loss(&self) -> f64;
+
+    fn copy_parameters(&self) -> Vec<Matrix>;
+
+    fn set_parameters(&mut self, parameters: &[Matrix]) -> NnResult<()>;
+
+    // values updated by the forward passes rather than by the optimizer (the running mean and variance
+    // of BatchNorm), which have to be restored along with the parameters
+    fn running_statistics(&self) -> Vec<Matrix> {
+        Vec::new()
+    }
+
+    fn set_running_statistics(&mut self, _statistics: &[Matrix]) -> NnResult<()> {
+        Ok(())
+    }
+
+    fn set_training(&mut self, training: bool);
+
+    // reseeds the random draws of the training (the dropout masks)
+    fn set_seed(&mut self, seed: u64);
+}
+
+// A model written to an .arch and a .wab file, which Session::save_checkpoint and load_checkpoint
+// store along with the optimizer state.
+pub trait SaveableModel: Model + Sized {
+    fn save(&self, filename: &str) -> NnResult<()>;
+
+    // custom activations, losses and layers are resolved through the registry
+    fn load_model_with(filename: &str, registry: &Registry) -> NnResult<Self>;
+}
+
+impl Model for DenseModel {
+    fn loss(&self) -> &dyn Loss {
+        DenseModel::loss(self)
+    }
+
+    fn feed_forward(&mut self, input: &Matrix) -> NnResult<()> {
+        DenseModel::feed_forward(self, input)
+    }
+
+    fn result(&self) -> Matrix {
+        DenseModel::result(self)
+    }
+
+    fn back_propagate_weighted(&mut self, output: &Matrix, weights: &Matrix) -> NnResult<Vec<Matrix>> {
+        DenseModel::back_propagate_weighted(self, output, weights)
+    }
+
+    fn gradients(&self, deltas: &[Matrix]) -> NnResult<Vec<Matrix>> {
+        DenseModel::gradients(self, deltas)
+    }
+
+    fn update_weights(&mut self, gradients: &[Matrix], optimizer: &mut dyn Optimizer, learning_rate: f64) -> NnResult<()> {
+        DenseModel::update_weights(self, gradients, optimizer, learning_rate)
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        DenseModel::regularization_loss(self)
+    }
+
+    fn copy_parameters(&self) -> Vec<Matrix> {
+        DenseModel::copy_parameters(self)
+    }
+
+    fn set_parameters(&mut self, parameters: &[Matrix]) -> NnResult<()> {
+        DenseModel::set_parameters(self, parameters)
+    }
+
+    fn running_statistics(&self) -> Vec<Matrix> {
+        DenseModel::running_statistics(self)
+    }
+
+    fn set_running_statistics(&mut self, statistics: &[Matrix]) -> NnResult<()> {
+        DenseModel::set_running_statistics(self, statistics)
+    }
+
+    fn set_training(&mut self, training: bool) {
+        DenseModel::set_training(self, training)
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        DenseModel::set_seed(self, seed)
+    }
+}
+
+impl SaveableModel for DenseModel {
+    fn save(&self, filename: &str) -> NnResult<()> {
+        DenseModel::save(self, filename)
+    }
+
+    fn load_model_with(filename: &str, registry: &Registry) -> NnResult<DenseModel> {
+        DenseModel::load_model_with(filename, registry)
+    }
+}
+
+impl SaveableModel for Sequential {
+    fn save(&self, filename: &str) -> NnResult<()> {
+        Sequential::save(self, filename)
+    }
+
+    fn load_model_with(filename: &str, registry: &Registry) -> NnResult<Sequential> {
+        Sequential::load_model_with(filename, registry)
+    }
+}
@@ -0,0 +1,202 @@
+use crate::activations::activation::Activation;
+use crate::activations::dense_activation::DenseActivation;
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::layers::activation_layer::ActivationLayer;
+use crate::layers::attention_layer::MultiHeadAttention;
+use crate::layers::conv_layer::Conv2D;
+use crate::layers::dense_layer::Dense;
+use crate::layers::dropout_layer::Dropout;
+use crate::layers::flatten_layer::Flatten;
+use crate::layers::layer::Layer;
+use crate::layers::normalization_layer::Normalization;
+use crate::layers::pooling_layer::{AveragePool2D, GlobalAveragePooling, MaxPool2D};
+use crate::layers::positional_layer::PositionalEncoding;
+use crate::layers::recurrent_layer::{Recurrent, RecurrentCell};
+use crate::layers::transformer_layer::TransformerEncoder;
+use crate::losses::dense_losses::DenseLosses;
+use crate::losses::loss::Loss;
+use crate::normalizations::dense_normalization::DenseNormalization;
+use crate::regularizers::dense_constraint::DenseConstraint;
+use crate::regularizers::dense_dropout::DenseDropout;
+use crate::regularizers::dense_regularizer::DenseRegularizer;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+pub type ActivationFactory = Box<dyn Fn(&str) -> NnResult<Box<dyn Activation>>>;
+pub type LossFactory = Box<dyn Fn(&str) -> NnResult<Box<dyn Loss>>>;
+pub type LayerFactory = Box<dyn Fn(&str) -> NnResult<Box<dyn Layer>>>;
+
+// Resolves the names read from an .arch file into activations and losses.
+// Built-in names always resolve; custom ones are looked up by the part of the name before
+// any '(' (so "Scaled(2)" resolves through the factory registered as "Scaled"), and the
+// factory is given the whole name to read its own parameters from.
+// Layers are looked up by the first word of their tag, their factory being given the rest of it.
+#[derive(Default)]
+pub struct Registry {
+    activations: HashMap<String, ActivationFactory>,
+    losses: HashMap<String, LossFactory>,
+    layers: HashMap<String, LayerFactory>
+}
+
+fn base_name(name: &str) -> &str {
+    name.split('(').next().unwrap_or(name)
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry {
+            activations: HashMap::new(),
+            losses: HashMap::new(),
+            layers: HashMap::new()
+        }
+    }
+
+    pub fn register_activation(&mut self, name: &str,
+        factory: impl Fn(&str) -> NnResult<Box<dyn Activation>> + 'static) {
+
+        self.activations.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn register_loss(&mut self, name: &str, factory: impl Fn(&str) -> NnResult<Box<dyn Loss>> + 'static) {
+        self.losses.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn register_layer(&mut self, name: &str, factory: impl Fn(&str) -> NnResult<Box<dyn Layer>> + 'static) {
+        self.layers.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn activation(&self, name: &str) -> NnResult<Box<dyn Activation>> {
+        if let Ok(activation) = DenseActivation::from_str(name) {
+            return Ok(Box::new(activation));
+        }
+
+        match self.activations.get(base_name(name)) {
+            Some(factory) => factory(name),
+            None => Err(NnError::UnsupportedActivation(name.to_string()))
+        }
+    }
+
+    pub fn loss(&self, name: &str) -> NnResult<Box<dyn Loss>> {
+        if let Ok(loss) = DenseLosses::from_str(name) {
+            return Ok(Box::new(loss));
+        }
+
+        match self.losses.get(base_name(name)) {
+            Some(factory) => factory(name),
+            None => Err(NnError::UnsupportedLoss(name.to_string()))
+        }
+    }
+
+    pub fn layer(&self, tag: &str) -> NnResult<Box<dyn Layer>> {
+        let (name, arguments) = tag.split_once(' ').unwrap_or((tag, ""));
+        let arguments: &str = arguments.trim();
+
+        match name {
+            "Dense" => dense_layer(tag, arguments),
+            "Activation" => Ok(Box::new(ActivationLayer::new(self.activation(arguments)?))),
+            "Dropout" => Ok(Box::new(Dropout::new(DenseDropout::from_str(arguments)?))),
+            "Normalization" => Ok(Box::new(Normalization::new(DenseNormalization::from_str(arguments)?))),
+            "Flatten" => Ok(Box::new(Flatten::new())),
+            "Conv2D" => conv_layer(tag, arguments),
+            "MaxPool2D" => {
+                let [pool_x, pool_y, stride_x, stride_y] = sizes(tag, arguments)?;
+                Ok(Box::new(MaxPool2D::new((pool_x, pool_y)).strided((stride_x, stride_y))))
+            },
+            "AveragePool2D" => {
+                let [pool_x, pool_y, stride_x, stride_y] = sizes(tag, arguments)?;
+                Ok(Box::new(AveragePool2D::new((pool_x, pool_y)).strided((stride_x, stride_y))))
+            },
+            "GlobalAveragePooling" => Ok(Box::new(GlobalAveragePooling::new())),
+            "SimpleRNN" | "LSTM" | "GRU" => recurrent_layer(RecurrentCell::from_str(name)?, tag, arguments),
+            "MultiHeadAttention" => {
+                let [nb_heads, key_size] = sizes(tag, arguments)?;
+                Ok(Box::new(MultiHeadAttention::new(nb_heads, key_size)))
+            },
+            "PositionalEncoding" => match arguments {
+                "Sinusoidal" | "" => Ok(Box::new(PositionalEncoding::sinusoidal())),
+                "Learned" => Ok(Box::new(PositionalEncoding::learned())),
+                _ => Err(NnError::UnsupportedLayer(tag.to_string()))
+            },
+            "TransformerEncoder" => transformer_layer(tag, arguments),
+            _ => match self.layers.get(name) {
+                Some(factory) => factory(arguments),
+                None => Err(NnError::UnsupportedLayer(tag.to_string()))
+            }
+        }
+    }
+}
+
+// "Dense <nb outputs> [regularizer] [constraint]"
+fn dense_layer(tag: &str, arguments: &str) -> NnResult<Box<dyn Layer>> {
+    let mut arguments = arguments.split_whitespace();
+
+    let nb_outputs: usize = arguments.next().and_then(|x| x.parse::<usize>().ok())
+        .ok_or(NnError::UnsupportedLayer(tag.to_string()))?;
+
+    let regularizer = arguments.next().map_or(Ok(DenseRegularizer::NoRegularizer), DenseRegularizer::from_str)?;
+    let constraint = arguments.next().map_or(Ok(DenseConstraint::NoConstraint), DenseConstraint::from_str)?;
+
+    Ok(Box::new(Dense::new(nb_outputs).regularized(regularizer).constrained(constraint)))
+}
+
+// the first N whitespace-separated sizes of the arguments
+fn sizes<const N: usize>(tag: &str, arguments: &str) -> NnResult<[usize; N]> {
+    let mut sizes: [usize; N] = [0; N];
+    let mut arguments = arguments.split_whitespace();
+
+    for size in sizes.iter_mut() {
+        *size = arguments.next().and_then(|x| x.parse::<usize>().ok())
+            .ok_or(NnError::UnsupportedLayer(tag.to_string()))?;
+    }
+    Ok(sizes)
+}
+
+// "Conv2D <filters> <kernel x y> <stride x y> <padding x y> <dilation x y> [regularizer]"
+fn conv_layer(tag: &str, arguments: &str) -> NnResult<Box<dyn Layer>> {
+    let [filters, kernel_x, kernel_y, stride_x, stride_y, padding_x, padding_y, dilation_x, dilation_y] = sizes(tag, arguments)?;
+
+    let regularizer = arguments.split_whitespace().nth(9)
+        .map_or(Ok(DenseRegularizer::NoRegularizer), DenseRegularizer::from_str)?;
+
+    Ok(Box::new(Conv2D::new(filters, (kernel_x, kernel_y)).strided((stride_x, stride_y))
+        .padded((padding_x, padding_y)).dilated((dilation_x, dilation_y)).regularized(regularizer)))
+}
+
+// "<cell> <units> [Sequences|Last] [truncation] [mask value|NoMask] [regularizer]"
+fn recurrent_layer(cell: RecurrentCell, tag: &str, arguments: &str) -> NnResult<Box<dyn Layer>> {
+    let unsupported = || NnError::UnsupportedLayer(tag.to_string());
+    let mut arguments = arguments.split_whitespace();
+
+    let units: usize = arguments.next().and_then(|x| x.parse::<usize>().ok()).ok_or_else(unsupported)?;
+    let mut layer = Recurrent::new(cell, units);
+
+    match arguments.next() {
+        Some("Sequences") => layer = layer.returning_sequences(),
+        Some("Last") | None => {},
+        Some(_) => return Err(unsupported())
+    }
+
+    if let Some(truncation) = arguments.next() {
+        layer = layer.truncated(truncation.parse::<usize>().map_err(|_| unsupported())?);
+    }
+
+    match arguments.next() {
+        Some("NoMask") | None => {},
+        Some(mask) => layer = layer.masked(mask.parse::<f64>().map_err(|_| unsupported())?)
+    }
+
+    let regularizer = arguments.next().map_or(Ok(DenseRegularizer::NoRegularizer), DenseRegularizer::from_str)?;
+    Ok(Box::new(layer.regularized(regularizer)))
+}
+
+// "TransformerEncoder <heads> <key size> <hidden> [activation] [dropout]"
+fn transformer_layer(tag: &str, arguments: &str) -> NnResult<Box<dyn Layer>> {
+    let [nb_heads, key_size, hidden] = sizes(tag, arguments)?;
+    let mut arguments = arguments.split_whitespace().skip(3);
+
+    let activation = arguments.next().map_or(Ok(DenseActivation::Relu), DenseActivation::from_str)?;
+    let dropout = arguments.next().map_or(Ok(DenseDropout::NoDropout), DenseDropout::from_str)?;
+
+    Ok(Box::new(TransformerEncoder::new(nb_heads, key_size, hidden).activated(activation).with_dropout(dropout)))
+}
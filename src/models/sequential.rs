@@ -0,0 +1,460 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::layers::layer::Layer;
+use crate::losses::dense_losses::{d_softmax_cross_entropy, dense_targets, is_softmax_cross_entropy, DenseLosses};
+use crate::losses::loss::Loss;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::{new_rng, NnRng};
+use crate::optimizers::optimizer::Optimizer;
+use crate::shapes::dense_shape::DenseShape;
+
+use super::dense_model::read_next_line;
+use super::model::Model;
+use super::registry::Registry;
+
+use std::fs::File;
+use std::io::Write;
+use std::io::{BufReader, BufRead};
+
+// A stack of layers, each one fed with the output of the previous one.
+// Unlike DenseModel, any Layer can be used, so new kinds of layers are trained by a Session as they are.
+pub struct Sequential {
+    pub loss: Box<dyn Loss>,
+    input_shape: DenseShape,
+    output_shape: DenseShape,
+    layers: Vec<Box<dyn Layer>>,
+
+    // shape of the samples entering each layer
+    layer_shapes: Vec<DenseShape>,
+
+    // dropout layers only drop values in training mode, see set_training
+    training: bool,
+
+    // initializes the layers, then draws the dropout masks
+    rng: NnRng,
+
+    // output of the last layer after the last feed_forward
+    output: Matrix
+}
+
+impl Sequential {
+    // builds every layer for samples of input_size values, the parameters being drawn from the seed
+    // (or from entropy when None)
+    pub fn new(input_size: usize, layers: Vec<Box<dyn Layer>>, loss: Box<dyn Loss>,
+        seed: Option<u64>) -> NnResult<Sequential> {
+
+        Sequential::with_input_shape(DenseShape::new(input_size, 1, 1), layers, loss, seed)
+    }
+
+    // same as new, for shaped samples (e.g. images of x * y pixels with z channels)
+    pub fn with_input_shape(input_shape: DenseShape, mut layers: Vec<Box<dyn Layer>>, loss: Box<dyn Loss>,
+        seed: Option<u64>) -> NnResult<Sequential> {
+
+        let mut rng = new_rng(seed);
+        let mut shape: DenseShape = input_shape;
+        let mut layer_shapes: Vec<DenseShape> = Vec::with_capacity(layers.len());
+
+        for layer in layers.iter_mut() {
+            layer_shapes.push(shape);
+            shape = layer.build(&shape, &mut rng)?;
+        }
+
+        Ok(Sequential {
+            loss,
+            input_shape,
+            output_shape: shape,
+            layers,
+            layer_shapes,
+            training: false,
+            rng,
+            output: Matrix::new(1, shape.range)
+        })
+    }
+
+    pub fn layers(&self) -> &[Box<dyn Layer>] {
+        &self.layers
+    }
+
+    pub(crate) fn layers_mut(&mut self) -> &mut [Box<dyn Layer>] {
+        &mut self.layers
+    }
+
+    // builds the layer in place of the one at the given index, which it must not reshape
+    // (from a copy of the generator, so that the draws of the model, e.g. its dropout masks, do not depend on it)
+    pub(crate) fn replace_layer(&mut self, index: usize, mut layer: Box<dyn Layer>) -> NnResult<()> {
+        if index >= self.layers.len() {
+            return Err(NnError::IndexOutOfRange {
+                operation: "Sequential::replace_layer",
+                y: index,
+                x: 0,
+                y_length: self.layers.len(),
+                x_length: 1
+            });
+        }
+
+        let shape: DenseShape = layer.build(&self.layer_shapes[index], &mut self.rng.clone())?;
+        let expected: DenseShape = self.layer_shapes.get(index + 1).copied().unwrap_or(self.output_shape);
+
+        if shape != expected {
+            return Err(NnError::ShapeMismatch {
+                operation: "Sequential::replace_layer",
+                left: (expected.range, 1),
+                right: (shape.range, 1)
+            });
+        }
+        self.layers[index] = layer;
+        Ok(())
+    }
+
+    pub fn input_shape(&self) -> DenseShape {
+        self.input_shape
+    }
+
+    pub fn output_shape(&self) -> DenseShape {
+        self.output_shape
+    }
+
+    pub fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.training
+    }
+
+    // reseeds the generator drawing the dropout masks, e.g. after loading the model back,
+    // so that resuming a training is reproducible
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = new_rng(Some(seed));
+    }
+
+    pub fn feed_forward(&mut self, input: &Matrix) -> NnResult<()> {
+        if input.y_length != self.input_shape.range {
+            return Err(NnError::ShapeMismatch {
+                operation: "Sequential::feed_forward",
+                left: (self.input_shape.range, 1),
+                right: (input.y_length, input.x_length)
+            });
+        }
+
+        let mut values: Matrix = input.copy();
+
+        for layer in self.layers.iter_mut() {
+            values = layer.forward(&values, self.training, &mut self.rng)?;
+        }
+        self.output = values;
+        Ok(())
+    }
+
+    pub fn result(&self) -> Matrix {
+        self.output.copy()
+    }
+
+    // returns the gradient w.r.t. the input of each layer, followed by the gradient w.r.t. the output
+    // (one column per sample), the gradients of the parameters being kept by the layers
+    pub fn back_propagate(&mut self, output: &Matrix) -> NnResult<Vec<Matrix>> {
+        self.backward(output, None)
+    }
+
+    // same as DenseModel::back_propagate_weighted
+    pub fn back_propagate_weighted(&mut self, output: &Matrix, weights: &Matrix) -> NnResult<Vec<Matrix>> {
+        self.backward(output, Some(weights))
+    }
+
+    fn backward(&mut self, output: &Matrix, weights: Option<&Matrix>) -> NnResult<Vec<Matrix>> {
+        if output.x_length != self.output.x_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "Sequential::back_propagate",
+                left: (self.output.y_length, self.output.x_length),
+                right: (output.y_length, output.x_length)
+            });
+        }
+
+        let fused: Option<DenseLosses> = match (self.layers.last().and_then(|layer| layer.activation())
+            .and_then(|activation| activation.as_dense()), self.loss.as_dense()) {
+            (Some(activation), Some(loss)) if is_softmax_cross_entropy(&activation, &loss) => Some(loss),
+            _ => None
+        };
+
+        let mut deltas: Vec<Matrix> = Vec::with_capacity(self.layers.len() + 1);
+        let mut nb_layers: usize = self.layers.len();
+
+        let mut gradient: Matrix = match fused {
+            // the softmax Jacobian and the cross-entropy derivative cancel out into p - y,
+            // which is the gradient w.r.t. the input of the softmax layer
+            Some(loss) => {
+                let targets = dense_targets(&loss, &self.output, output)?;
+                nb_layers -= 1;
+                deltas.push(Matrix::new(0, 0));
+                d_softmax_cross_entropy(&self.output, &targets)
+            },
+            None => self.loss.gradient(&self.output, output)?
+        };
+
+        if let Some(weights) = weights {
+            gradient.try_scale_columns(weights)?;
+        }
+
+        for layer in self.layers[..nb_layers].iter_mut().rev() {
+            let input_gradient = layer.backward(&gradient)?;
+            deltas.push(gradient);
+            gradient = input_gradient;
+        }
+        deltas.push(gradient);
+
+        deltas.reverse();
+        Ok(deltas)
+    }
+
+    // the gradients of every parameter, layer after layer, averaged over the last batch
+    pub fn gradients(&self, _deltas: &[Matrix]) -> NnResult<Vec<Matrix>> {
+        Ok(self.layers.iter().flat_map(|layer| layer.gradients()).collect())
+    }
+
+    pub fn update_weights(&mut self, gradients: &[Matrix], optimizer: &mut dyn Optimizer,
+        learning_rate: f64) -> NnResult<()> {
+
+        let indices: Vec<usize> = (0..gradients.len()).collect();
+        self.update_indexed(gradients, &indices, optimizer, learning_rate)
+    }
+
+    // same as update_weights, the k-th parameter being updated with gradients[indices[k]],
+    // under that index in the optimizer state
+    pub(crate) fn update_indexed(&mut self, gradients: &[Matrix], indices: &[usize], optimizer: &mut dyn Optimizer,
+        learning_rate: f64) -> NnResult<()> {
+
+        let nb_parameters: usize = self.layers.iter().map(|layer| layer.parameters().len()).sum();
+
+        if indices.len() != nb_parameters || indices.iter().any(|index| *index >= gradients.len()) {
+            return Err(NnError::ShapeMismatch {
+                operation: "Sequential::update_weights",
+                left: (nb_parameters, 1),
+                right: (gradients.len(), 1)
+            });
+        }
+
+        optimizer.begin_step();
+        let mut indices = indices.iter();
+
+        for layer in self.layers.iter_mut() {
+            for (parameter, index) in layer.parameters_mut().into_iter().zip(indices.by_ref()) {
+                optimizer.update(*index, parameter, &gradients[*index], learning_rate)?;
+            }
+            layer.apply_constraints();
+        }
+        Ok(())
+    }
+
+    pub fn regularization_loss(&self) -> f64 {
+        self.layers.iter().map(|layer| layer.regularization_loss()).sum()
+    }
+
+    pub fn copy_parameters(&self) -> Vec<Matrix> {
+        self.layers.iter().flat_map(|layer| layer.parameters().into_iter().map(Matrix::copy)).collect()
+    }
+
+    pub fn set_parameters(&mut self, parameters: &[Matrix]) -> NnResult<()> {
+        let current: Vec<Matrix> = self.copy_parameters();
+
+        if current.len() != parameters.len() {
+            return Err(NnError::ShapeMismatch {
+                operation: "Sequential::set_parameters",
+                left: (current.len(), 1),
+                right: (parameters.len(), 1)
+            });
+        }
+
+        for (current, new) in current.iter().zip(parameters.iter()) {
+            if current.x_length != new.x_length || current.y_length != new.y_length {
+                return Err(NnError::ShapeMismatch {
+                    operation: "Sequential::set_parameters",
+                    left: (current.y_length, current.x_length),
+                    right: (new.y_length, new.x_length)
+                });
+            }
+        }
+
+        for (parameter, values) in self.layers.iter_mut().flat_map(|layer| layer.parameters_mut()).zip(parameters.iter()) {
+            *parameter = values.copy();
+        }
+        Ok(())
+    }
+
+    // running statistics of every layer keeping some (see Layer::running_statistics), layer after layer
+    pub fn running_statistics(&self) -> Vec<Matrix> {
+        self.layers.iter().flat_map(|layer| layer.running_statistics().into_iter().map(Matrix::copy)).collect()
+    }
+
+    pub fn set_running_statistics(&mut self, statistics: &[Matrix]) -> NnResult<()> {
+        let current: Vec<Matrix> = self.running_statistics();
+
+        if current.len() != statistics.len() {
+            return Err(NnError::ShapeMismatch {
+                operation: "Sequential::set_running_statistics",
+                left: (current.len(), 1),
+                right: (statistics.len(), 1)
+            });
+        }
+
+        for (current, new) in current.iter().zip(statistics.iter()) {
+            if current.x_length != new.x_length || current.y_length != new.y_length {
+                return Err(NnError::ShapeMismatch {
+                    operation: "Sequential::set_running_statistics",
+                    left: (current.y_length, current.x_length),
+                    right: (new.y_length, new.x_length)
+                });
+            }
+        }
+
+        for (statistic, values) in self.layers.iter_mut().flat_map(|layer| layer.running_statistics_mut()).zip(statistics.iter()) {
+            *statistic = values.copy();
+        }
+        Ok(())
+    }
+
+    // the .arch file holds the input shape (x y z), the number of layers, the tag of each layer and the loss,
+    // the .wab file one line per parameter matrix (row after row), layer after layer, then one line
+    // per running statistics matrix
+    pub fn save(&self, filename: &str) -> NnResult<()> {
+
+        let archi_filename = format!("{filename}.arch");
+        let weights_filename = format!("{filename}.wab");
+
+        let shape: DenseShape = self.input_shape;
+        let mut archi_content: String = format!("{} {} {}\n{}\n", shape.x, shape.y, shape.z, self.layers.len());
+
+        for layer in self.layers.iter() {
+            archi_content.push_str(&layer.tag());
+            archi_content.push('\n');
+        }
+        archi_content.push_str(&self.loss.name());
+        archi_content.push('\n');
+
+        let mut weights_content: String = String::new();
+
+        for parameter in self.copy_parameters().iter().chain(self.running_statistics().iter()) {
+            let values: Vec<String> = parameter.values.iter().map(|x| x.to_string()).collect();
+            weights_content.push_str(&values.join(" "));
+            weights_content.push('\n');
+        }
+
+        let mut archi_file: File = File::create(&archi_filename).map_err(|e| NnError::io(&archi_filename, e))?;
+        archi_file.write_all(archi_content.as_bytes()).map_err(|e| NnError::io(&archi_filename, e))?;
+
+        let mut weights_file: File = File::create(&weights_filename).map_err(|e| NnError::io(&weights_filename, e))?;
+        weights_file.write_all(weights_content.as_bytes()).map_err(|e| NnError::io(&weights_filename, e))?;
+
+        Ok(())
+    }
+
+    pub fn load_model(filename: &str) -> NnResult<Sequential> {
+        Sequential::load_model_with(filename, &Registry::new())
+    }
+
+    // same as load_model, resolving the tags of custom layers (and the names of custom activations
+    // and losses) through the registry
+    pub fn load_model_with(filename: &str, registry: &Registry) -> NnResult<Sequential> {
+
+        let archi_filename = format!("{filename}.arch");
+        let weights_filename = format!("{filename}.wab");
+
+        let archi_file: File = File::open(&archi_filename).map_err(|e| NnError::io(&archi_filename, e))?;
+        let weights_file: File = File::open(&weights_filename).map_err(|e| NnError::io(&weights_filename, e))?;
+
+        let mut archi_lines = BufReader::new(archi_file).lines();
+        let mut weights_lines = BufReader::new(weights_file).lines();
+
+        // a single size stands for a vector
+        let dimensions: Vec<usize> = read_next_line(&mut archi_lines, &archi_filename, 1)?.split_whitespace()
+            .map(|x| x.parse::<usize>().map_err(|_| NnError::parse(&archi_filename, 1, "Cannot parse the input shape.")))
+            .collect::<NnResult<Vec<usize>>>()?;
+
+        let input_shape: DenseShape = match dimensions[..] {
+            [x] => DenseShape::new(x, 1, 1),
+            [x, y, z] => DenseShape::new(x, y, z),
+            _ => return Err(NnError::parse(&archi_filename, 1, "Expected the input size, or its x, y and z dimensions."))
+        };
+
+        let nb_layers: usize = read_next_line(&mut archi_lines, &archi_filename, 2)?.trim().parse::<usize>()
+            .map_err(|_| NnError::parse(&archi_filename, 2, "Cannot parse the number of layers."))?;
+
+        let layers: Vec<Box<dyn Layer>> = (0..nb_layers)
+            .map(|l| registry.layer(read_next_line(&mut archi_lines, &archi_filename, l + 3)?.trim()))
+            .collect::<NnResult<Vec<Box<dyn Layer>>>>()?;
+
+        let loss: Box<dyn Loss> = registry.loss(read_next_line(&mut archi_lines, &archi_filename, nb_layers + 3)?.trim())?;
+
+        let mut model = Sequential::with_input_shape(input_shape, layers, loss, None)?;
+        let mut parameters: Vec<Matrix> = model.copy_parameters();
+        let mut statistics: Vec<Matrix> = model.running_statistics();
+
+        for (p, parameter) in parameters.iter_mut().chain(statistics.iter_mut()).enumerate() {
+            let line_nb: usize = p + 1;
+            let values: Vec<f64> = read_next_line(&mut weights_lines, &weights_filename, line_nb)?.split_whitespace().map(|x| {
+                x.parse::<f64>().map_err(|_| NnError::parse(&weights_filename, line_nb, "Cannot parse a parameter into a floating point."))
+            }).collect::<NnResult<Vec<f64>>>()?;
+
+            if values.len() != parameter.values.len() {
+                return Err(NnError::parse(&weights_filename, line_nb, "Unexpected number of parameters on this line."));
+            }
+            parameter.values = values;
+        }
+
+        model.set_parameters(&parameters)?;
+        model.set_running_statistics(&statistics)?;
+        Ok(model)
+    }
+}
+
+impl Model for Sequential {
+    fn loss(&self) -> &dyn Loss {
+        self.loss.as_ref()
+    }
+
+    fn feed_forward(&mut self, input: &Matrix) -> NnResult<()> {
+        Sequential::feed_forward(self, input)
+    }
+
+    fn result(&self) -> Matrix {
+        Sequential::result(self)
+    }
+
+    fn back_propagate_weighted(&mut self, output: &Matrix, weights: &Matrix) -> NnResult<Vec<Matrix>> {
+        Sequential::back_propagate_weighted(self, output, weights)
+    }
+
+    fn gradients(&self, deltas: &[Matrix]) -> NnResult<Vec<Matrix>> {
+        Sequential::gradients(self, deltas)
+    }
+
+    fn update_weights(&mut self, gradients: &[Matrix], optimizer: &mut dyn Optimizer, learning_rate: f64) -> NnResult<()> {
+        Sequential::update_weights(self, gradients, optimizer, learning_rate)
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        Sequential::regularization_loss(self)
+    }
+
+    fn copy_parameters(&self) -> Vec<Matrix> {
+        Sequential::copy_parameters(self)
+    }
+
+    fn set_parameters(&mut self, parameters: &[Matrix]) -> NnResult<()> {
+        Sequential::set_parameters(self, parameters)
+    }
+
+    fn running_statistics(&self) -> Vec<Matrix> {
+        Sequential::running_statistics(self)
+    }
+
+    fn set_running_statistics(&mut self, statistics: &[Matrix]) -> NnResult<()> {
+        Sequential::set_running_statistics(self, statistics)
+    }
+
+    fn set_training(&mut self, training: bool) {
+        Sequential::set_training(self, training)
+    }
+
+    fn set_seed(&mut self, seed: u64) {
+        Sequential::set_seed(self, seed)
+    }
+}
@@ -0,0 +1,210 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+use crate::utils::parsing::parse_argument;
+
+use super::{DEFAULT_BATCH_NORM_MOMENTUM, NORMALIZATION_EPSILON};
+
+use std::fmt;
+use std::str::FromStr;
+
+// Normalization applied between the affine transformation of a layer and its activation,
+// followed by a learnable scale (gamma) and shift (beta) for each neuron.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DenseNormalization {
+    NoNormalization,
+
+    // normalizes each neuron over the samples of the batch while training, and with running statistics
+    // at inference (running = momentum * running + (1 - momentum) * batch statistic)
+    BatchNorm(f64),
+
+    // normalizes the neurons of each sample together, the same way while training and at inference
+    LayerNorm
+}
+
+// kept by the forward pass for the backward one
+pub struct NormalizationCache {
+    normalized: Matrix,
+
+    // 1 / sqrt(variance + epsilon), of each neuron for BatchNorm and of each sample for LayerNorm
+    inverse_std: Vec<f64>,
+
+    // false when the statistics did not depend on the values (BatchNorm at inference)
+    batch_statistics: bool
+}
+
+impl DenseNormalization {
+    // gamma (first row) starts at 1 and beta (second row) at 0, empty without normalization
+    pub fn initial_parameters(&self, nb_neurons: usize) -> Matrix {
+        if *self == DenseNormalization::NoNormalization {
+            return Matrix::new(0, 0);
+        }
+        let mut parameters = Matrix::new(nb_neurons, 2);
+        parameters.values[..nb_neurons].iter_mut().for_each(|gamma| *gamma = 1.0);
+        parameters
+    }
+
+    // running mean (first row) at 0 and running variance (second row) at 1, empty unless BatchNorm
+    pub fn initial_statistics(&self, nb_neurons: usize) -> Matrix {
+        match self {
+            DenseNormalization::BatchNorm(_) => {
+                let mut statistics = Matrix::new(nb_neurons, 2);
+                statistics.values[nb_neurons..].iter_mut().for_each(|variance| *variance = 1.0);
+                statistics
+            },
+            _ => Matrix::new(0, 0)
+        }
+    }
+
+    // number of groups normalized together, their size, and the index of the k-th value of a group
+    fn layout(&self, mat: &Matrix) -> (usize, usize, impl Fn(usize, usize) -> usize) {
+        let x_length: usize = mat.x_length;
+        let by_neuron: bool = matches!(self, DenseNormalization::BatchNorm(_));
+
+        let index = move |group: usize, k: usize| if by_neuron {group * x_length + k} else {k * x_length + group};
+
+        if by_neuron {(mat.y_length, mat.x_length, index)} else {(mat.x_length, mat.y_length, index)}
+    }
+
+    // normalizes, scales and shifts mat in place (one sample per column);
+    // BatchNorm updates its running statistics while training, from batches of at least 2 samples
+    pub fn forward(&self, mat: &mut Matrix, parameters: &Matrix, statistics: &mut Matrix,
+        training: bool) -> NnResult<NormalizationCache> {
+
+        if *self == DenseNormalization::NoNormalization {
+            return Ok(NormalizationCache {normalized: mat.copy(), inverse_std: Vec::new(), batch_statistics: false});
+        }
+
+        if parameters.x_length != mat.y_length || parameters.y_length != 2 {
+            return Err(NnError::ShapeMismatch {
+                operation: "DenseNormalization::forward",
+                left: (parameters.y_length, parameters.x_length),
+                right: (mat.y_length, mat.x_length)
+            });
+        }
+
+        // the variance of a single sample is 0, which would leave only beta and no gradient,
+        // so a batch of one sample (e.g. the last one of an epoch) is normalized with the running statistics
+        let batch_statistics: bool = !matches!(self, DenseNormalization::BatchNorm(_)) || (training && mat.x_length > 1);
+
+        let (nb_groups, group_size, index) = self.layout(mat);
+        let nb_neurons: usize = mat.y_length;
+
+        let mut normalized = Matrix::new(mat.x_length, mat.y_length);
+        let mut inverse_std: Vec<f64> = Vec::with_capacity(nb_groups);
+
+        for group in 0..nb_groups {
+            let (mean, variance) = if batch_statistics {
+                let mean: f64 = (0..group_size).map(|k| mat.values[index(group, k)]).sum::<f64>() / group_size as f64;
+                let variance: f64 = (0..group_size).map(|k| (mat.values[index(group, k)] - mean).powi(2))
+                    .sum::<f64>() / group_size as f64;
+
+                if let DenseNormalization::BatchNorm(momentum) = *self {
+                    statistics.values[group] = momentum * statistics.values[group] + (1.0 - momentum) * mean;
+                    statistics.values[nb_neurons + group] = momentum * statistics.values[nb_neurons + group]
+                        + (1.0 - momentum) * variance;
+                }
+                (mean, variance)
+            }
+            else {
+                (statistics.values[group], statistics.values[nb_neurons + group])
+            };
+
+            let inverse: f64 = 1.0 / (variance + NORMALIZATION_EPSILON).sqrt();
+            inverse_std.push(inverse);
+
+            for k in 0..group_size {
+                let i: usize = index(group, k);
+                normalized.values[i] = (mat.values[i] - mean) * inverse;
+            }
+        }
+
+        for (i, value) in mat.values.iter_mut().enumerate() {
+            let neuron: usize = i / normalized.x_length;
+            *value = parameters.values[neuron] * normalized.values[i] + parameters.values[nb_neurons + neuron];
+        }
+
+        Ok(NormalizationCache {normalized, inverse_std, batch_statistics})
+    }
+
+    // returns the gradient with respect to the values before the normalization, and the gradient
+    // of gamma and beta averaged over the batch (same layout as the parameters)
+    pub fn backward(&self, cache: &NormalizationCache, parameters: &Matrix, gradient: &Matrix) -> NnResult<(Matrix, Matrix)> {
+
+        if *self == DenseNormalization::NoNormalization {
+            return Ok((gradient.copy(), Matrix::new(0, 0)));
+        }
+
+        let normalized: &Matrix = &cache.normalized;
+
+        if gradient.x_length != normalized.x_length || gradient.y_length != normalized.y_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "DenseNormalization::backward",
+                left: (normalized.y_length, normalized.x_length),
+                right: (gradient.y_length, gradient.x_length)
+            });
+        }
+
+        let nb_neurons: usize = gradient.y_length;
+        let batch_size: f64 = gradient.x_length as f64;
+
+        let mut d_parameters = Matrix::new(nb_neurons, 2);
+        let mut d_normalized = Matrix::new(gradient.x_length, gradient.y_length);
+
+        for (i, g) in gradient.values.iter().enumerate() {
+            let neuron: usize = i / gradient.x_length;
+
+            d_parameters.values[neuron] += g * normalized.values[i] / batch_size;
+            d_parameters.values[nb_neurons + neuron] += g / batch_size;
+            d_normalized.values[i] = g * parameters.values[neuron];
+        }
+
+        let (nb_groups, group_size, index) = self.layout(gradient);
+        let mut delta = Matrix::new(gradient.x_length, gradient.y_length);
+
+        for group in 0..nb_groups {
+            let inverse: f64 = cache.inverse_std[group];
+
+            // the mean and the variance of the group depend on each of its values
+            let (sum, sum_normalized) = if cache.batch_statistics {
+                (0..group_size).map(|k| index(group, k))
+                    .fold((0.0, 0.0), |(s, sn), i| (s + d_normalized.values[i], sn + d_normalized.values[i] * normalized.values[i]))
+            } else {
+                (0.0, 0.0)
+            };
+
+            for k in 0..group_size {
+                let i: usize = index(group, k);
+                delta.values[i] = inverse * (d_normalized.values[i]
+                    - (sum + normalized.values[i] * sum_normalized) / group_size as f64);
+            }
+        }
+        Ok((delta, d_parameters))
+    }
+}
+
+impl fmt::Display for DenseNormalization {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DenseNormalization::NoNormalization => write!(f, "NoNormalization"),
+            DenseNormalization::BatchNorm(momentum) => write!(f, "BatchNorm({momentum})"),
+            DenseNormalization::LayerNorm => write!(f, "LayerNorm")
+        }
+    }
+}
+
+impl FromStr for DenseNormalization {
+    type Err = NnError;
+
+    fn from_str(input: &str) -> Result<DenseNormalization, Self::Err> {
+        let normalization = match input {
+            "NoNormalization" => Some(DenseNormalization::NoNormalization),
+            "BatchNorm" => Some(DenseNormalization::BatchNorm(DEFAULT_BATCH_NORM_MOMENTUM)),
+            "LayerNorm" => Some(DenseNormalization::LayerNorm),
+            _ => parse_argument(input, "BatchNorm")
+                .filter(|momentum| (0.0..1.0).contains(momentum))
+                .map(DenseNormalization::BatchNorm)
+        };
+
+        normalization.ok_or(NnError::UnsupportedNormalization(input.to_string()))
+    }
+}
@@ -0,0 +1,7 @@
+pub mod dense_normalization;
+
+// added to the variance before taking its square root
+pub const NORMALIZATION_EPSILON: f64 = 1e-5;
+
+// weight of the previous running statistics when BatchNorm is given without a momentum
+pub const DEFAULT_BATCH_NORM_MOMENTUM: f64 = 0.9;
@@ -0,0 +1,59 @@
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+
+use super::optimizer::{check_gradient, Optimizer, OptimizerState};
+
+// s = s + g^2
+// p = p - lr * g / (sqrt(s) + epsilon)
+pub struct Adagrad {
+    pub epsilon: f64,
+    state: OptimizerState
+}
+
+impl Adagrad {
+    pub fn new(epsilon: f64) -> Adagrad {
+        Adagrad {
+            epsilon,
+            state: OptimizerState::new(1)
+        }
+    }
+}
+
+impl Default for Adagrad {
+    fn default() -> Adagrad {
+        Adagrad::new(1e-8)
+    }
+}
+
+impl Optimizer for Adagrad {
+    fn name(&self) -> String {
+        "Adagrad".to_string()
+    }
+
+    fn hyperparameters(&self) -> Vec<f64> {
+        vec![self.epsilon]
+    }
+
+    fn state(&self) -> &OptimizerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut OptimizerState {
+        &mut self.state
+    }
+
+    fn update(&mut self, index: usize, parameter: &mut Matrix, gradient: &Matrix,
+        learning_rate: f64) -> NnResult<()> {
+
+        check_gradient(parameter, gradient)?;
+        let square_sum = self.state.slot(0, index, parameter)?;
+
+        for i in 0..parameter.values.len() {
+            let g: f64 = gradient.values[i];
+
+            square_sum.values[i] += g * g;
+            parameter.values[i] -= learning_rate * g / (square_sum.values[i].sqrt() + self.epsilon);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+
+use super::optimizer::{check_gradient, Optimizer, OptimizerState};
+
+// m = beta1 * m + (1 - beta1) * g
+// v = beta2 * v + (1 - beta2) * g^2
+// p = p - lr * m_hat / (sqrt(v_hat) + epsilon)
+// where m_hat and v_hat are the bias corrected moments
+pub struct Adam {
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+    state: OptimizerState
+}
+
+impl Adam {
+    pub fn new(beta1: f64, beta2: f64, epsilon: f64) -> Adam {
+        Adam {
+            beta1,
+            beta2,
+            epsilon,
+            state: OptimizerState::new(2)
+        }
+    }
+}
+
+impl Default for Adam {
+    fn default() -> Adam {
+        Adam::new(0.9, 0.999, 1e-8)
+    }
+}
+
+impl Optimizer for Adam {
+    fn name(&self) -> String {
+        "Adam".to_string()
+    }
+
+    fn hyperparameters(&self) -> Vec<f64> {
+        vec![self.beta1, self.beta2, self.epsilon]
+    }
+
+    fn state(&self) -> &OptimizerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut OptimizerState {
+        &mut self.state
+    }
+
+    fn update(&mut self, index: usize, parameter: &mut Matrix, gradient: &Matrix,
+        learning_rate: f64) -> NnResult<()> {
+
+        adam_update(&mut self.state, (self.beta1, self.beta2, self.epsilon),
+            index, parameter, gradient, learning_rate)
+    }
+}
+
+// Adam with decoupled weight decay: the parameters are shrunk by lr * weight_decay * p
+// independently of the adaptive gradient step
+pub struct AdamW {
+    pub beta1: f64,
+    pub beta2: f64,
+    pub epsilon: f64,
+    pub weight_decay: f64,
+    state: OptimizerState
+}
+
+impl AdamW {
+    pub fn new(beta1: f64, beta2: f64, epsilon: f64, weight_decay: f64) -> AdamW {
+        AdamW {
+            beta1,
+            beta2,
+            epsilon,
+            weight_decay,
+            state: OptimizerState::new(2)
+        }
+    }
+}
+
+impl Default for AdamW {
+    fn default() -> AdamW {
+        AdamW::new(0.9, 0.999, 1e-8, 0.01)
+    }
+}
+
+impl Optimizer for AdamW {
+    fn name(&self) -> String {
+        "AdamW".to_string()
+    }
+
+    fn hyperparameters(&self) -> Vec<f64> {
+        vec![self.beta1, self.beta2, self.epsilon, self.weight_decay]
+    }
+
+    fn state(&self) -> &OptimizerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut OptimizerState {
+        &mut self.state
+    }
+
+    fn update(&mut self, index: usize, parameter: &mut Matrix, gradient: &Matrix,
+        learning_rate: f64) -> NnResult<()> {
+
+        check_gradient(parameter, gradient)?;
+
+        for value in parameter.values.iter_mut() {
+            *value -= learning_rate * self.weight_decay * *value;
+        }
+
+        adam_update(&mut self.state, (self.beta1, self.beta2, self.epsilon),
+            index, parameter, gradient, learning_rate)
+    }
+}
+
+fn adam_update(state: &mut OptimizerState, (beta1, beta2, epsilon): (f64, f64, f64),
+    index: usize, parameter: &mut Matrix, gradient: &Matrix, learning_rate: f64) -> NnResult<()> {
+
+    check_gradient(parameter, gradient)?;
+
+    // begin_step has been called at least once before any update
+    let step: i32 = state.step.max(1) as i32;
+    let correction1: f64 = 1.0 - beta1.powi(step);
+    let correction2: f64 = 1.0 - beta2.powi(step);
+
+    state.slot(0, index, parameter)?;
+    state.slot(1, index, parameter)?;
+
+    let (first, second) = state.slots.split_at_mut(1);
+    let first_moment = &mut first[0][index];
+    let second_moment = &mut second[0][index];
+
+    for i in 0..parameter.values.len() {
+        let g: f64 = gradient.values[i];
+
+        first_moment.values[i] = beta1 * first_moment.values[i] + (1.0 - beta1) * g;
+        second_moment.values[i] = beta2 * second_moment.values[i] + (1.0 - beta2) * g * g;
+
+        let m_hat: f64 = first_moment.values[i] / correction1;
+        let v_hat: f64 = second_moment.values[i] / correction2;
+
+        parameter.values[i] -= learning_rate * m_hat / (v_hat.sqrt() + epsilon);
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+
+// Applied to the gradients of a model before the optimizer updates its parameters,
+// to keep exploding gradients from making a single huge step.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GradientClipping {
+    // clamps every value of every gradient to [-value, value]
+    Value(f64),
+
+    // rescales all the gradients together when the L2 norm of their concatenation exceeds the value,
+    // which keeps the direction of the step
+    GlobalNorm(f64)
+}
+
+impl GradientClipping {
+    // the limit has to be a positive finite number, as Value would not be able to clamp to a negative
+    // or NaN range and GlobalNorm would zero or flip the gradients (checked by Session::train)
+    pub fn check(&self) -> NnResult<()> {
+        let (GradientClipping::Value(limit) | GradientClipping::GlobalNorm(limit)) = *self;
+
+        if !(limit.is_finite() && limit > 0.0) {
+            return Err(NnError::UnsupportedClipping(format!("{self:?}")));
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, gradients: &mut [Matrix]) {
+        match *self {
+            GradientClipping::Value(value) => {
+                for gradient in gradients.iter_mut() {
+                    gradient.values.iter_mut().for_each(|g| *g = g.clamp(-value, value));
+                }
+            },
+            GradientClipping::GlobalNorm(max_norm) => {
+                let norm: f64 = global_norm(gradients);
+
+                if norm > max_norm {
+                    gradients.iter_mut().for_each(|gradient| gradient.scale(max_norm / norm));
+                }
+            }
+        }
+    }
+}
+
+pub fn global_norm(gradients: &[Matrix]) -> f64 {
+    gradients.iter().flat_map(|gradient| gradient.values.iter()).map(|g| g * g).sum::<f64>().sqrt()
+}
@@ -0,0 +1,6 @@
+pub mod adagrad;
+pub mod adam;
+pub mod gradient_clipping;
+pub mod optimizer;
+pub mod rmsprop;
+pub mod sgd;
\ No newline at end of file
@@ -0,0 +1,238 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+
+use super::adagrad::Adagrad;
+use super::adam::{Adam, AdamW};
+use super::rmsprop::RmsProp;
+use super::sgd::{Momentum, Nesterov, Sgd};
+
+use std::fs::File;
+use std::io::Write;
+use std::io::{BufReader, BufRead, Lines};
+
+// Every optimizer updates the parameters of a model one at a time: the index identifies
+// the parameter (weights and biases of every layer, in the order given by the model)
+// so the optimizer can keep per-parameter state such as velocities or moments.
+pub trait Optimizer {
+    // name used to save and load the optimizer
+    fn name(&self) -> String;
+
+    fn hyperparameters(&self) -> Vec<f64>;
+
+    fn state(&self) -> &OptimizerState;
+
+    fn state_mut(&mut self) -> &mut OptimizerState;
+
+    fn update(&mut self, index: usize, parameter: &mut Matrix, gradient: &Matrix,
+        learning_rate: f64) -> NnResult<()>;
+
+    // called once per batch, before the parameters are updated
+    fn begin_step(&mut self) {
+        self.state_mut().step += 1;
+    }
+}
+
+pub struct OptimizerState {
+    // number of updates done so far (used for bias correction)
+    pub step: usize,
+
+    // slots[k][i] is the k-th state matrix (velocity, first moment...) of the i-th parameter
+    pub slots: Vec<Vec<Matrix>>
+}
+
+impl OptimizerState {
+    pub fn new(nb_slots: usize) -> OptimizerState {
+        OptimizerState {
+            step: 0,
+            slots: (0..nb_slots).map(|_| Vec::new()).collect()
+        }
+    }
+
+    // returns the state matrix of the given slot and parameter, creating it (zeroed) if needed
+    pub fn slot(&mut self, slot: usize, index: usize, parameter: &Matrix) -> NnResult<&mut Matrix> {
+        let slots = &mut self.slots[slot];
+
+        while slots.len() <= index {
+            slots.push(Matrix::new(0, 0));
+        }
+
+        if slots[index].values.is_empty() {
+            slots[index] = Matrix::new(parameter.x_length, parameter.y_length);
+        }
+
+        if slots[index].x_length != parameter.x_length || slots[index].y_length != parameter.y_length {
+            return Err(NnError::ShapeMismatch {
+                operation: "OptimizerState::slot",
+                left: (slots[index].y_length, slots[index].x_length),
+                right: (parameter.y_length, parameter.x_length)
+            });
+        }
+
+        Ok(&mut slots[index])
+    }
+}
+
+pub fn check_gradient(parameter: &Matrix, gradient: &Matrix) -> NnResult<()> {
+    if parameter.x_length != gradient.x_length || parameter.y_length != gradient.y_length {
+        return Err(NnError::ShapeMismatch {
+            operation: "Optimizer::update",
+            left: (parameter.y_length, parameter.x_length),
+            right: (gradient.y_length, gradient.x_length)
+        });
+    }
+    Ok(())
+}
+
+pub fn optimizer_from_name(name: &str, hyperparameters: &[f64]) -> NnResult<Box<dyn Optimizer>> {
+    let expected: usize = match name {
+        "Sgd" => 0,
+        "Momentum" | "Nesterov" | "Adagrad" => 1,
+        "RmsProp" => 2,
+        "Adam" => 3,
+        "AdamW" => 4,
+        _ => return Err(NnError::UnsupportedOptimizer(name.to_string()))
+    };
+
+    if hyperparameters.len() != expected {
+        return Err(NnError::UnsupportedOptimizer(
+            format!("{name} expects {expected} hyperparameters, got {}", hyperparameters.len())));
+    }
+
+    let h = hyperparameters;
+
+    Ok(match name {
+        "Sgd" => Box::new(Sgd::new()),
+        "Momentum" => Box::new(Momentum::new(h[0])),
+        "Nesterov" => Box::new(Nesterov::new(h[0])),
+        "RmsProp" => Box::new(RmsProp::new(h[0], h[1])),
+        "Adagrad" => Box::new(Adagrad::new(h[0])),
+        "Adam" => Box::new(Adam::new(h[0], h[1], h[2])),
+        _ => Box::new(AdamW::new(h[0], h[1], h[2], h[3]))
+    })
+}
+
+// The .opt file holds:
+// - the name of the optimizer
+// - its hyperparameters
+// - the step count
+// - the number of slots and of parameters
+// - for each slot and each parameter, the "y_length x_length" of the matrix followed by its rows
+pub fn save_optimizer(optimizer: &dyn Optimizer, filename: &str) -> NnResult<()> {
+
+    let optimizer_filename = format!("{filename}.opt");
+
+    let mut optimizer_file: File = File::create(&optimizer_filename)
+        .map_err(|e| NnError::io(&optimizer_filename, e))?;
+
+    let state = optimizer.state();
+    let nb_parameters: usize = state.slots.iter().map(|slot| slot.len()).max().unwrap_or(0);
+
+    let mut content: String = String::new();
+
+    content.push_str(&optimizer.name());
+    content.push('\n');
+
+    let hyperparameters: Vec<String> = optimizer.hyperparameters().iter().map(|x| x.to_string()).collect();
+    content.push_str(&hyperparameters.join(" "));
+    content.push('\n');
+
+    content.push_str(&state.step.to_string());
+    content.push('\n');
+
+    content.push_str(&format!("{} {}\n", state.slots.len(), nb_parameters));
+
+    for slot in state.slots.iter() {
+        for i in 0..nb_parameters {
+            // parameters that were never updated have no state yet
+            let empty = Matrix::new(0, 0);
+            let mat = slot.get(i).unwrap_or(&empty);
+
+            content.push_str(&format!("{} {}\n", mat.y_length, mat.x_length));
+
+            for y in 0..mat.y_length {
+                let row: Vec<String> = (0..mat.x_length).map(|x| mat.get(y, x).to_string()).collect();
+                content.push_str(&row.join(" "));
+                content.push('\n');
+            }
+        }
+    }
+
+    optimizer_file.write_all(content.as_bytes()).map_err(|e| NnError::io(&optimizer_filename, e))
+}
+
+pub fn load_optimizer(filename: &str) -> NnResult<Box<dyn Optimizer>> {
+
+    let optimizer_filename = format!("{filename}.opt");
+
+    let optimizer_file: File = File::open(&optimizer_filename)
+        .map_err(|e| NnError::io(&optimizer_filename, e))?;
+
+    let mut lines = BufReader::new(optimizer_file).lines();
+    let mut line_nb: usize = 0;
+
+    let name = next_line(&mut lines, &optimizer_filename, &mut line_nb)?;
+
+    let buffer = next_line(&mut lines, &optimizer_filename, &mut line_nb)?;
+    let hyperparameters: Vec<f64> = parse_values(&buffer, &optimizer_filename, line_nb)?;
+
+    let mut optimizer = optimizer_from_name(name.trim(), &hyperparameters)?;
+
+    let buffer = next_line(&mut lines, &optimizer_filename, &mut line_nb)?;
+    let step: usize = buffer.trim().parse::<usize>()
+        .map_err(|_| NnError::parse(&optimizer_filename, line_nb, "Cannot parse the step count."))?;
+
+    let buffer = next_line(&mut lines, &optimizer_filename, &mut line_nb)?;
+    let sizes: Vec<usize> = parse_values(&buffer, &optimizer_filename, line_nb)?;
+
+    if sizes.len() != 2 || sizes[0] != optimizer.state().slots.len() {
+        return Err(NnError::parse(&optimizer_filename, line_nb, "Unexpected number of slots for this optimizer."));
+    }
+
+    let state = optimizer.state_mut();
+    state.step = step;
+
+    for slot in state.slots.iter_mut() {
+        for _ in 0..sizes[1] {
+            let buffer = next_line(&mut lines, &optimizer_filename, &mut line_nb)?;
+            let dimensions: Vec<usize> = parse_values(&buffer, &optimizer_filename, line_nb)?;
+
+            if dimensions.len() != 2 {
+                return Err(NnError::parse(&optimizer_filename, line_nb, "Expected the dimensions of a matrix."));
+            }
+
+            let mut mat = Matrix::new(dimensions[1], dimensions[0]);
+
+            for y in 0..mat.y_length {
+                let buffer = next_line(&mut lines, &optimizer_filename, &mut line_nb)?;
+                let row: Vec<f64> = parse_values(&buffer, &optimizer_filename, line_nb)?;
+
+                if row.len() != mat.x_length {
+                    return Err(NnError::parse(&optimizer_filename, line_nb, "Unexpected number of values on this line."));
+                }
+
+                for (x, value) in row.iter().enumerate() {
+                    mat.set(y, x, *value);
+                }
+            }
+
+            slot.push(mat);
+        }
+    }
+
+    Ok(optimizer)
+}
+
+fn next_line(lines: &mut Lines<BufReader<File>>, filename: &str, line_nb: &mut usize) -> NnResult<String> {
+    *line_nb += 1;
+
+    match lines.next() {
+        Some(line) => line.map_err(|e| NnError::io(filename, e)),
+        None => Err(NnError::parse(filename, *line_nb, "Unexpected end of file."))
+    }
+}
+
+fn parse_values<T: std::str::FromStr>(buffer: &str, filename: &str, line_nb: usize) -> NnResult<Vec<T>> {
+    buffer.split_whitespace().map(|x| {
+        x.parse::<T>().map_err(|_| NnError::parse(filename, line_nb, &format!("Cannot parse the value {x}.")))
+    }).collect()
+}
@@ -0,0 +1,61 @@
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+
+use super::optimizer::{check_gradient, Optimizer, OptimizerState};
+
+// s = rho * s + (1 - rho) * g^2
+// p = p - lr * g / (sqrt(s) + epsilon)
+pub struct RmsProp {
+    pub rho: f64,
+    pub epsilon: f64,
+    state: OptimizerState
+}
+
+impl RmsProp {
+    pub fn new(rho: f64, epsilon: f64) -> RmsProp {
+        RmsProp {
+            rho,
+            epsilon,
+            state: OptimizerState::new(1)
+        }
+    }
+}
+
+impl Default for RmsProp {
+    fn default() -> RmsProp {
+        RmsProp::new(0.9, 1e-8)
+    }
+}
+
+impl Optimizer for RmsProp {
+    fn name(&self) -> String {
+        "RmsProp".to_string()
+    }
+
+    fn hyperparameters(&self) -> Vec<f64> {
+        vec![self.rho, self.epsilon]
+    }
+
+    fn state(&self) -> &OptimizerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut OptimizerState {
+        &mut self.state
+    }
+
+    fn update(&mut self, index: usize, parameter: &mut Matrix, gradient: &Matrix,
+        learning_rate: f64) -> NnResult<()> {
+
+        check_gradient(parameter, gradient)?;
+        let square_avg = self.state.slot(0, index, parameter)?;
+
+        for i in 0..parameter.values.len() {
+            let g: f64 = gradient.values[i];
+
+            square_avg.values[i] = self.rho * square_avg.values[i] + (1.0 - self.rho) * g * g;
+            parameter.values[i] -= learning_rate * g / (square_avg.values[i].sqrt() + self.epsilon);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use crate::errors::nn_error::NnResult;
+use crate::maths::matrices::Matrix;
+
+use super::optimizer::{check_gradient, Optimizer, OptimizerState};
+
+// vanilla stochastic gradient descent: p = p - lr * g
+pub struct Sgd {
+    state: OptimizerState
+}
+
+impl Sgd {
+    pub fn new() -> Sgd {
+        Sgd {
+            state: OptimizerState::new(0)
+        }
+    }
+}
+
+impl Default for Sgd {
+    fn default() -> Sgd {
+        Sgd::new()
+    }
+}
+
+impl Optimizer for Sgd {
+    fn name(&self) -> String {
+        "Sgd".to_string()
+    }
+
+    fn hyperparameters(&self) -> Vec<f64> {
+        Vec::new()
+    }
+
+    fn state(&self) -> &OptimizerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut OptimizerState {
+        &mut self.state
+    }
+
+    fn update(&mut self, _index: usize, parameter: &mut Matrix, gradient: &Matrix,
+        learning_rate: f64) -> NnResult<()> {
+
+        check_gradient(parameter, gradient)?;
+
+        for i in 0..parameter.values.len() {
+            parameter.values[i] -= learning_rate * gradient.values[i];
+        }
+        Ok(())
+    }
+}
+
+// SGD with momentum:
+// v = momentum * v - lr * g
+// p = p + v
+pub struct Momentum {
+    pub momentum: f64,
+    state: OptimizerState
+}
+
+impl Momentum {
+    pub fn new(momentum: f64) -> Momentum {
+        Momentum {
+            momentum,
+            state: OptimizerState::new(1)
+        }
+    }
+}
+
+impl Default for Momentum {
+    fn default() -> Momentum {
+        Momentum::new(0.9)
+    }
+}
+
+impl Optimizer for Momentum {
+    fn name(&self) -> String {
+        "Momentum".to_string()
+    }
+
+    fn hyperparameters(&self) -> Vec<f64> {
+        vec![self.momentum]
+    }
+
+    fn state(&self) -> &OptimizerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut OptimizerState {
+        &mut self.state
+    }
+
+    fn update(&mut self, index: usize, parameter: &mut Matrix, gradient: &Matrix,
+        learning_rate: f64) -> NnResult<()> {
+
+        check_gradient(parameter, gradient)?;
+        let velocity = self.state.slot(0, index, parameter)?;
+
+        for i in 0..parameter.values.len() {
+            velocity.values[i] = self.momentum * velocity.values[i] - learning_rate * gradient.values[i];
+            parameter.values[i] += velocity.values[i];
+        }
+        Ok(())
+    }
+}
+
+// Nesterov accelerated gradient, in the form that only needs the gradient at the current parameters:
+// v = momentum * v - lr * g
+// p = p + momentum * v - lr * g
+pub struct Nesterov {
+    pub momentum: f64,
+    state: OptimizerState
+}
+
+impl Nesterov {
+    pub fn new(momentum: f64) -> Nesterov {
+        Nesterov {
+            momentum,
+            state: OptimizerState::new(1)
+        }
+    }
+}
+
+impl Default for Nesterov {
+    fn default() -> Nesterov {
+        Nesterov::new(0.9)
+    }
+}
+
+impl Optimizer for Nesterov {
+    fn name(&self) -> String {
+        "Nesterov".to_string()
+    }
+
+    fn hyperparameters(&self) -> Vec<f64> {
+        vec![self.momentum]
+    }
+
+    fn state(&self) -> &OptimizerState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut OptimizerState {
+        &mut self.state
+    }
+
+    fn update(&mut self, index: usize, parameter: &mut Matrix, gradient: &Matrix,
+        learning_rate: f64) -> NnResult<()> {
+
+        check_gradient(parameter, gradient)?;
+        let velocity = self.state.slot(0, index, parameter)?;
+
+        for i in 0..parameter.values.len() {
+            velocity.values[i] = self.momentum * velocity.values[i] - learning_rate * gradient.values[i];
+            parameter.values[i] += self.momentum * velocity.values[i] - learning_rate * gradient.values[i];
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+pub use crate::activations::activation::Activation;
+pub use crate::activations::dense_activation::DenseActivation;
+pub use crate::data::class_weights::ClassWeights;
+pub use crate::data::create_data::{load_data, Sample};
+pub use crate::data::split_data::Labels;
+pub use crate::errors::nn_error::{NnError, NnResult};
+pub use crate::initializers::dense_initializer::DenseInitializer;
+pub use crate::layers::activation_layer::ActivationLayer;
+pub use crate::layers::attention_layer::{scaled_dot_product_attention, MultiHeadAttention};
+pub use crate::layers::conv_layer::Conv2D;
+pub use crate::layers::dense_layer::Dense;
+pub use crate::layers::dropout_layer::Dropout;
+pub use crate::layers::flatten_layer::Flatten;
+pub use crate::layers::layer::Layer;
+pub use crate::layers::normalization_layer::Normalization;
+pub use crate::layers::pooling_layer::{AveragePool2D, GlobalAveragePooling, MaxPool2D};
+pub use crate::layers::positional_layer::PositionalEncoding;
+pub use crate::layers::recurrent_layer::{Recurrent, RecurrentCell};
+pub use crate::layers::transformer_layer::TransformerEncoder;
+pub use crate::losses::dense_losses::{calculate_error, DenseLosses};
+pub use crate::losses::loss::Loss;
+pub use crate::maths::matrices::Matrix;
+pub use crate::metrics::classification_metrics::Average;
+pub use crate::metrics::metric::Metric;
+pub use crate::models::dense_model::DenseModel;
+pub use crate::models::model::{Model, SaveableModel};
+pub use crate::models::registry::Registry;
+pub use crate::models::sequential::Sequential;
+pub use crate::normalizations::dense_normalization::DenseNormalization;
+pub use crate::optimizers::adagrad::Adagrad;
+pub use crate::optimizers::adam::{Adam, AdamW};
+pub use crate::optimizers::gradient_clipping::GradientClipping;
+pub use crate::optimizers::optimizer::Optimizer;
+pub use crate::optimizers::rmsprop::RmsProp;
+pub use crate::optimizers::sgd::{Momentum, Nesterov, Sgd};
+pub use crate::regularizers::dense_constraint::DenseConstraint;
+pub use crate::regularizers::dense_dropout::DenseDropout;
+pub use crate::regularizers::dense_regularizer::DenseRegularizer;
+pub use crate::schedules::lr_schedule::{
+    Constant, CosineAnnealing, ExponentialDecay, LinearWarmup, LrSchedule, OneCycle, ReduceOnPlateau, StepDecay
+};
+pub use crate::sessions::early_stopping::{EarlyStopping, Mode};
+pub use crate::sessions::session::Session;
+pub use crate::shapes::dense_shape::DenseShape;
@@ -0,0 +1,67 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+use crate::utils::parsing::parse_argument;
+
+use std::fmt;
+use std::str::FromStr;
+
+// Constraint enforced on the weights of a layer after each update.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DenseConstraint {
+    NoConstraint,
+
+    // rescales the incoming weights of each neuron (a row of the weights) whose L2 norm exceeds the value
+    MaxNorm(f64)
+}
+
+impl DenseConstraint {
+    // a norm that is not positive would zero (or flip) every row of the weights
+    pub fn check(&self) -> NnResult<()> {
+        if let DenseConstraint::MaxNorm(max_norm) = *self {
+            if max_norm.is_nan() || max_norm <= 0.0 {
+                return Err(NnError::UnsupportedConstraint(self.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self, weights: &mut Matrix) {
+        match *self {
+            DenseConstraint::NoConstraint => (),
+            DenseConstraint::MaxNorm(max_norm) => {
+                for y in 0..weights.y_length {
+                    let row = &mut weights.values[y * weights.x_length..(y + 1) * weights.x_length];
+                    let norm: f64 = row.iter().map(|w| w * w).sum::<f64>().sqrt();
+
+                    if norm > max_norm {
+                        row.iter_mut().for_each(|w| *w *= max_norm / norm);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for DenseConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DenseConstraint::NoConstraint => write!(f, "NoConstraint"),
+            DenseConstraint::MaxNorm(max_norm) => write!(f, "MaxNorm({max_norm})")
+        }
+    }
+}
+
+impl FromStr for DenseConstraint {
+    type Err = NnError;
+
+    fn from_str(input: &str) -> Result<DenseConstraint, Self::Err> {
+        if input == "NoConstraint" {
+            return Ok(DenseConstraint::NoConstraint);
+        }
+
+        parse_argument(input, "MaxNorm")
+            .map(DenseConstraint::MaxNorm)
+            .filter(|constraint| constraint.check().is_ok())
+            .ok_or(NnError::UnsupportedConstraint(input.to_string()))
+    }
+}
@@ -0,0 +1,98 @@
+use crate::activations::{SELU_ALPHA, SELU_LAMBDA};
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+use crate::utils::parsing::parse_argument;
+
+use rand::Rng;
+
+use std::fmt;
+use std::str::FromStr;
+
+// Randomly drops the outputs of a layer while training (see DenseModel::set_training),
+// and leaves them untouched during inference. The rate is the probability of dropping each output.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DenseDropout {
+    NoDropout,
+
+    // inverted dropout: the kept outputs are scaled by 1 / (1 - rate), so nothing changes at inference
+    Dropout(f64),
+
+    // dropped outputs are set to the negative saturation value of SELU, then an affine transformation
+    // keeps the mean and variance of the outputs (Klambauer et al., 2017), for SELU networks
+    AlphaDropout(f64)
+}
+
+impl DenseDropout {
+    fn rate(&self) -> f64 {
+        match *self {
+            DenseDropout::NoDropout => 0.0,
+            DenseDropout::Dropout(rate) | DenseDropout::AlphaDropout(rate) => rate
+        }
+    }
+
+    // the rate has to be in [0, 1), as a rate of 1 would drop every output (and divide by 0)
+    pub fn check(&self) -> NnResult<()> {
+        if !(0.0..1.0).contains(&self.rate()) {
+            return Err(NnError::UnsupportedRegularizer(self.to_string()));
+        }
+        Ok(())
+    }
+
+    // returns the dropped values, and the derivative of each dropped value with respect to the
+    // original one (which is the mask used by back_propagate)
+    pub fn apply<R: Rng>(&self, values: &Matrix, rng: &mut R) -> (Matrix, Matrix) {
+        let rate: f64 = self.rate();
+        let mut dropped = Matrix::new(values.x_length, values.y_length);
+        let mut mask = Matrix::new(values.x_length, values.y_length);
+
+        // for every kind of dropout, dropped = a * (kept ? x : saturation) + b
+        let (a, b, saturation) = match *self {
+            DenseDropout::NoDropout => (1.0, 0.0, 0.0),
+            DenseDropout::Dropout(rate) => (1.0 / (1.0 - rate), 0.0, 0.0),
+            DenseDropout::AlphaDropout(rate) => {
+                let saturation: f64 = -SELU_LAMBDA * SELU_ALPHA;
+                let a: f64 = ((1.0 - rate) * (1.0 + rate * saturation * saturation)).powf(-0.5);
+
+                (a, -a * saturation * rate, saturation)
+            }
+        };
+
+        for ((d, m), x) in dropped.values.iter_mut().zip(mask.values.iter_mut()).zip(values.values.iter()) {
+            let kept: bool = rng.gen::<f64>() >= rate;
+
+            *d = a * (if kept {*x} else {saturation}) + b;
+            *m = if kept {a} else {0.0};
+        }
+        (dropped, mask)
+    }
+}
+
+impl fmt::Display for DenseDropout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DenseDropout::NoDropout => write!(f, "NoDropout"),
+            DenseDropout::Dropout(rate) => write!(f, "Dropout({rate})"),
+            DenseDropout::AlphaDropout(rate) => write!(f, "AlphaDropout({rate})")
+        }
+    }
+}
+
+impl FromStr for DenseDropout {
+    type Err = NnError;
+
+    fn from_str(input: &str) -> Result<DenseDropout, Self::Err> {
+        let dropout = if input == "NoDropout" {
+            Some(DenseDropout::NoDropout)
+        }
+        else if let Some(rate) = parse_argument(input, "Dropout") {
+            Some(DenseDropout::Dropout(rate))
+        }
+        else {
+            parse_argument(input, "AlphaDropout").map(DenseDropout::AlphaDropout)
+        };
+
+        dropout
+            .filter(|dropout| dropout.check().is_ok())
+            .ok_or(NnError::UnsupportedRegularizer(input.to_string()))
+    }
+}
@@ -0,0 +1,91 @@
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::maths::matrices::Matrix;
+use crate::utils::parsing::{parse_argument, parse_arguments};
+
+use std::fmt;
+use std::str::FromStr;
+
+// Penalty on the weights of a layer, added to the loss (and its gradient to the gradient of the weights).
+// Biases are not penalized.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DenseRegularizer {
+    NoRegularizer,
+    L1(f64), // l1 * sum(|w|)
+    L2(f64), // l2 * sum(w^2)
+    ElasticNet(f64, f64) // l1 * sum(|w|) + l2 * sum(w^2)
+}
+
+impl DenseRegularizer {
+    fn factors(&self) -> (f64, f64) {
+        match *self {
+            DenseRegularizer::NoRegularizer => (0.0, 0.0),
+            DenseRegularizer::L1(l1) => (l1, 0.0),
+            DenseRegularizer::L2(l2) => (0.0, l2),
+            DenseRegularizer::ElasticNet(l1, l2) => (l1, l2)
+        }
+    }
+
+    // a negative (or NaN) factor would reward large weights instead of penalizing them
+    pub fn check(&self) -> NnResult<()> {
+        let (l1, l2) = self.factors();
+
+        if !(l1 >= 0.0 && l2 >= 0.0) {
+            return Err(NnError::UnsupportedRegularizer(self.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn penalty(&self, weights: &Matrix) -> f64 {
+        let (l1, l2) = self.factors();
+
+        weights.values.iter().map(|w| l1 * w.abs() + l2 * w * w).sum()
+    }
+
+    pub fn gradient(&self, weights: &Matrix) -> Matrix {
+        let (l1, l2) = self.factors();
+        let mut gradient = Matrix::new(weights.x_length, weights.y_length);
+
+        for (g, w) in gradient.values.iter_mut().zip(weights.values.iter()) {
+            // the sign of 0 is taken as 0, so zero weights stay at zero
+            let sign: f64 = if *w > 0.0 {1.0} else if *w < 0.0 {-1.0} else {0.0};
+            *g = l1 * sign + 2.0 * l2 * w;
+        }
+        gradient
+    }
+}
+
+impl fmt::Display for DenseRegularizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DenseRegularizer::NoRegularizer => write!(f, "NoRegularizer"),
+            DenseRegularizer::L1(l1) => write!(f, "L1({l1})"),
+            DenseRegularizer::L2(l2) => write!(f, "L2({l2})"),
+            DenseRegularizer::ElasticNet(l1, l2) => write!(f, "ElasticNet({l1},{l2})")
+        }
+    }
+}
+
+impl FromStr for DenseRegularizer {
+    type Err = NnError;
+
+    fn from_str(input: &str) -> Result<DenseRegularizer, Self::Err> {
+        let regularizer = if input == "NoRegularizer" {
+            Some(DenseRegularizer::NoRegularizer)
+        }
+        else if let Some(l1) = parse_argument(input, "L1") {
+            Some(DenseRegularizer::L1(l1))
+        }
+        else if let Some(l2) = parse_argument(input, "L2") {
+            Some(DenseRegularizer::L2(l2))
+        }
+        else {
+            parse_arguments(input, "ElasticNet")
+                .filter(|values| values.len() == 2)
+                .map(|values| DenseRegularizer::ElasticNet(values[0], values[1]))
+        };
+
+        regularizer
+            .filter(|regularizer| regularizer.check().is_ok())
+            .ok_or(NnError::UnsupportedRegularizer(input.to_string()))
+    }
+}
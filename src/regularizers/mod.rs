@@ -0,0 +1,3 @@
+pub mod dense_constraint;
+pub mod dense_dropout;
+pub mod dense_regularizer;
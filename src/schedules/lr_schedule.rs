@@ -0,0 +1,214 @@
+use std::f64::consts::PI;
+
+// A schedule gives the learning rate of each epoch from the base learning rate of the session.
+pub trait LrSchedule {
+    fn learning_rate(&mut self, epoch: usize, base_learning_rate: f64) -> f64;
+
+    // called at the end of each epoch with its average loss
+    fn observe_loss(&mut self, _avg_loss: f64) {}
+}
+
+// keeps the base learning rate for every epoch
+pub struct Constant;
+
+impl LrSchedule for Constant {
+    fn learning_rate(&mut self, _epoch: usize, base_learning_rate: f64) -> f64 {
+        base_learning_rate
+    }
+}
+
+// multiplies the learning rate by gamma every step_size epochs
+pub struct StepDecay {
+    pub step_size: usize,
+    pub gamma: f64
+}
+
+impl StepDecay {
+    pub fn new(step_size: usize, gamma: f64) -> StepDecay {
+        StepDecay {
+            step_size,
+            gamma
+        }
+    }
+}
+
+impl LrSchedule for StepDecay {
+    fn learning_rate(&mut self, epoch: usize, base_learning_rate: f64) -> f64 {
+        let nb_steps = epoch / self.step_size.max(1);
+
+        base_learning_rate * self.gamma.powi(nb_steps as i32)
+    }
+}
+
+// lr = base * gamma^epoch
+pub struct ExponentialDecay {
+    pub gamma: f64
+}
+
+impl ExponentialDecay {
+    pub fn new(gamma: f64) -> ExponentialDecay {
+        ExponentialDecay {
+            gamma
+        }
+    }
+}
+
+impl LrSchedule for ExponentialDecay {
+    fn learning_rate(&mut self, epoch: usize, base_learning_rate: f64) -> f64 {
+        base_learning_rate * self.gamma.powi(epoch as i32)
+    }
+}
+
+// SGDR: cosine annealing from the base learning rate down to min_learning_rate over a period
+// of first_period epochs, restarting afterwards with a period multiplied by period_multiplier
+pub struct CosineAnnealing {
+    pub first_period: usize,
+    pub period_multiplier: usize,
+    pub min_learning_rate: f64
+}
+
+impl CosineAnnealing {
+    pub fn new(first_period: usize, period_multiplier: usize, min_learning_rate: f64) -> CosineAnnealing {
+        CosineAnnealing {
+            first_period,
+            period_multiplier,
+            min_learning_rate
+        }
+    }
+}
+
+impl LrSchedule for CosineAnnealing {
+    fn learning_rate(&mut self, epoch: usize, base_learning_rate: f64) -> f64 {
+        let mut period: usize = self.first_period.max(1);
+        let mut position: usize = epoch;
+
+        while position >= period {
+            position -= period;
+            period *= self.period_multiplier.max(1);
+        }
+
+        let progress: f64 = position as f64 / period as f64;
+
+        self.min_learning_rate
+            + 0.5 * (base_learning_rate - self.min_learning_rate) * (1.0 + (PI * progress).cos())
+    }
+}
+
+// linearly increases the learning rate during the first warmup_epochs,
+// then hands over to the wrapped schedule (counting epochs from the end of the warmup)
+pub struct LinearWarmup {
+    pub warmup_epochs: usize,
+    pub after: Box<dyn LrSchedule>
+}
+
+impl LinearWarmup {
+    pub fn new(warmup_epochs: usize, after: Box<dyn LrSchedule>) -> LinearWarmup {
+        LinearWarmup {
+            warmup_epochs,
+            after
+        }
+    }
+}
+
+impl LrSchedule for LinearWarmup {
+    fn learning_rate(&mut self, epoch: usize, base_learning_rate: f64) -> f64 {
+        if epoch < self.warmup_epochs {
+            return base_learning_rate * (epoch + 1) as f64 / self.warmup_epochs as f64;
+        }
+        self.after.learning_rate(epoch - self.warmup_epochs, base_learning_rate)
+    }
+
+    fn observe_loss(&mut self, avg_loss: f64) {
+        self.after.observe_loss(avg_loss);
+    }
+}
+
+// one-cycle policy: the base learning rate is the peak, reached after pct_start of total_epochs
+// starting from base / div_factor, then annealed down to base / (div_factor * final_div_factor)
+pub struct OneCycle {
+    pub total_epochs: usize,
+    pub pct_start: f64,
+    pub div_factor: f64,
+    pub final_div_factor: f64
+}
+
+impl OneCycle {
+    pub fn new(total_epochs: usize, pct_start: f64, div_factor: f64, final_div_factor: f64) -> OneCycle {
+        OneCycle {
+            total_epochs,
+            pct_start,
+            div_factor,
+            final_div_factor
+        }
+    }
+}
+
+impl LrSchedule for OneCycle {
+    fn learning_rate(&mut self, epoch: usize, base_learning_rate: f64) -> f64 {
+        let initial: f64 = base_learning_rate / self.div_factor;
+        let last: f64 = initial / self.final_div_factor;
+
+        let warmup_epochs: f64 = (self.total_epochs as f64 * self.pct_start).max(1.0);
+        let annealing_epochs: f64 = (self.total_epochs as f64 - 1.0 - warmup_epochs).max(1.0);
+        let epoch: f64 = epoch as f64;
+
+        if epoch < warmup_epochs {
+            return cosine_between(initial, base_learning_rate, epoch / warmup_epochs);
+        }
+
+        let progress: f64 = ((epoch - warmup_epochs) / annealing_epochs).min(1.0);
+        cosine_between(base_learning_rate, last, progress)
+    }
+}
+
+fn cosine_between(start: f64, end: f64, progress: f64) -> f64 {
+    end + 0.5 * (start - end) * (1.0 + (PI * progress).cos())
+}
+
+// multiplies the learning rate by factor when the average loss has not improved
+// by more than threshold for patience epochs, without going below min_learning_rate
+pub struct ReduceOnPlateau {
+    pub factor: f64,
+    pub patience: usize,
+    pub threshold: f64,
+    pub min_learning_rate: f64,
+
+    best_loss: f64,
+    nb_bad_epochs: usize,
+    multiplier: f64
+}
+
+impl ReduceOnPlateau {
+    pub fn new(factor: f64, patience: usize, threshold: f64, min_learning_rate: f64) -> ReduceOnPlateau {
+        ReduceOnPlateau {
+            factor,
+            patience,
+            threshold,
+            min_learning_rate,
+            best_loss: f64::INFINITY,
+            nb_bad_epochs: 0,
+            multiplier: 1.0
+        }
+    }
+}
+
+impl LrSchedule for ReduceOnPlateau {
+    fn learning_rate(&mut self, _epoch: usize, base_learning_rate: f64) -> f64 {
+        (base_learning_rate * self.multiplier).max(self.min_learning_rate)
+    }
+
+    fn observe_loss(&mut self, avg_loss: f64) {
+        if avg_loss < self.best_loss - self.threshold {
+            self.best_loss = avg_loss;
+            self.nb_bad_epochs = 0;
+            return;
+        }
+
+        self.nb_bad_epochs += 1;
+
+        if self.nb_bad_epochs > self.patience {
+            self.multiplier *= self.factor;
+            self.nb_bad_epochs = 0;
+        }
+    }
+}
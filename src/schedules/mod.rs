@@ -0,0 +1 @@
+pub mod lr_schedule;
\ No newline at end of file
@@ -0,0 +1,93 @@
+use crate::maths::matrices::Matrix;
+use crate::metrics::metric::Metric;
+
+// Whether a monitored value improves by decreasing (a loss, an error) or by increasing (an accuracy, a score).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Mode {
+    Min,
+    Max
+}
+
+// Stops the training when the monitored value (the loss by default) has not improved by more than min_delta
+// for patience epochs, optionally keeping the parameters of the best epoch.
+pub struct EarlyStopping {
+    pub patience: usize,
+    pub min_delta: f64,
+    pub restore_best_weights: bool,
+
+    // a metric watched instead of the loss, on the validation set when there is one (see Session::train)
+    pub monitor: Option<(Metric, Mode)>,
+
+    best_value: f64,
+    best_epoch: usize,
+    nb_bad_epochs: usize,
+    best_parameters: Option<Vec<Matrix>>
+}
+
+impl EarlyStopping {
+    pub fn new(patience: usize, min_delta: f64, restore_best_weights: bool) -> EarlyStopping {
+        EarlyStopping {
+            patience,
+            min_delta,
+            restore_best_weights,
+            monitor: None,
+            best_value: f64::INFINITY,
+            best_epoch: 0,
+            nb_bad_epochs: 0,
+            best_parameters: None
+        }
+    }
+
+    pub fn monitor(mut self, metric: Metric, mode: Mode) -> Self {
+        self.monitor = Some((metric, mode));
+        self.reset();
+        self
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.monitor.map_or(Mode::Min, |(_, mode)| mode)
+    }
+
+    // forgets what has been seen during a previous training
+    pub fn reset(&mut self) {
+        self.best_value = if self.mode() == Mode::Min {f64::INFINITY} else {f64::NEG_INFINITY};
+        self.best_epoch = 0;
+        self.nb_bad_epochs = 0;
+        self.best_parameters = None;
+    }
+
+    pub fn best_value(&self) -> f64 {
+        self.best_value
+    }
+
+    pub fn best_epoch(&self) -> usize {
+        self.best_epoch
+    }
+
+    // returns true when the training should stop, after patience epochs in a row without improvement
+    // the parameters are only copied when they have to be restored later on
+    pub fn observe(&mut self, epoch: usize, value: f64, parameters: impl FnOnce() -> Vec<Matrix>) -> bool {
+        let improved: bool = match self.mode() {
+            Mode::Min => value < self.best_value - self.min_delta,
+            Mode::Max => value > self.best_value + self.min_delta
+        };
+
+        if improved {
+            self.best_value = value;
+            self.best_epoch = epoch;
+            self.nb_bad_epochs = 0;
+
+            if self.restore_best_weights {
+                self.best_parameters = Some(parameters());
+            }
+            return false;
+        }
+
+        self.nb_bad_epochs += 1;
+        self.nb_bad_epochs >= self.patience
+    }
+
+    pub fn take_best_parameters(&mut self) -> Option<Vec<Matrix>> {
+        self.best_parameters.take()
+    }
+}
@@ -1 +1,2 @@
+pub mod early_stopping;
 pub mod session;
\ No newline at end of file
@@ -1,71 +1,355 @@
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
 
+use crate::data::class_weights::{sample_weights, ClassWeights};
 use crate::data::create_data::{Sample, load_data};
-use crate::models::dense_model::DenseModel;
-use crate::losses::dense_losses::calculate_error;
+use crate::data::split_data::{check_split, split_dataset, Labels};
+use crate::errors::nn_error::{NnError, NnResult};
+use crate::losses::dense_losses::DenseLosses;
+use crate::models::model::{Model, SaveableModel};
+use crate::models::registry::Registry;
+use crate::maths::matrices::Matrix;
+use crate::maths::random::{new_rng, NnRng};
+use crate::metrics::metric::Metric;
+use crate::optimizers::gradient_clipping::GradientClipping;
+use crate::optimizers::optimizer::{load_optimizer, save_optimizer, Optimizer};
+use crate::optimizers::sgd::Sgd;
+use crate::schedules::lr_schedule::{Constant, LrSchedule};
+
+use super::early_stopping::EarlyStopping;
 
 pub struct Session {
     pub dataset: Vec<Sample>,
 
+    // evaluated at the end of each epoch, without updating the weights (empty = no validation)
+    pub validation: Vec<Sample>,
+
     pub nb_epochs: usize,
+    // base learning rate, adjusted each epoch by lr_schedule
     pub learning_rate: f64,
+    pub lr_schedule: Box<dyn LrSchedule>,
+
+    // number of samples forwarded together before a single weights update (1 = online SGD)
+    pub batch_size: usize,
+
+    // keeps its per-parameter state between epochs, so a session trains a single model
+    pub optimizer: Box<dyn Optimizer>,
+
+    // applied to the gradients of each batch before the weights update (None = no clipping)
+    pub gradient_clipping: Option<GradientClipping>,
+
+    // multiply the weight of each sample in the training loss (see Sample::weight)
+    pub class_weights: ClassWeights,
+
+    // how the class of each sample is read, for the class weights, the stratified splits and the metrics
+    // (Labels::Sparse(nb_classes) with SparseCategoricalCrossEntropy)
+    pub labels: Labels,
 
     pub loss_threshold: f64,
     pub stop_on_loss_threshold: bool,
 
+    pub early_stopping: Option<EarlyStopping>,
+
+    // reported next to the loss, on the training set and on the validation set
+    pub metrics: Vec<Metric>,
+
+    // the average loss and learning rate are printed every log_interval epochs
+    pub log_interval: usize,
+
+    // drives the shuffling of the dataset and the validation splits, see set_seed
+    rng: NnRng,
+
+    // also reseeds the models loaded by load_checkpoint
+    seed: Option<u64>,
 }
 
 impl Session {
     pub fn new(input_path: String, output_path: String,
         nb_epochs: usize, learning_rate: f64,
-        loss_threshold: f64, stop_on_loss_threshold: bool) -> Session {
+        loss_threshold: f64, stop_on_loss_threshold: bool) -> NnResult<Session> {
         
-        let dataset: Vec<Sample> = load_data(&input_path, &output_path);
-
-        Session {
-            dataset: dataset,
-            nb_epochs: nb_epochs,
-            learning_rate: learning_rate,
-            loss_threshold: loss_threshold,
-            stop_on_loss_threshold: stop_on_loss_threshold,
+        let dataset: Vec<Sample> = load_data(&input_path, &output_path)?;
+
+        Ok(Session {
+            dataset,
+            validation: Vec::new(),
+            nb_epochs,
+            learning_rate,
+            lr_schedule: Box::new(Constant),
+            batch_size: 1,
+            optimizer: Box::new(Sgd::new()),
+            gradient_clipping: None,
+            class_weights: ClassWeights::Uniform,
+            labels: Labels::Encoded,
+            loss_threshold,
+            stop_on_loss_threshold,
+            early_stopping: None,
+            metrics: Vec::new(),
+            log_interval: 1000,
+            rng: new_rng(None),
+            seed: None,
+        })
+    }
+
+    // makes the shuffling and the splits of this session reproducible, as well as the dropout masks of
+    // the models it loads (a new model is seeded separately, see DenseModel::new_seeded)
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = new_rng(Some(seed));
+        self.seed = Some(seed);
+    }
+
+    pub fn load_validation(&mut self, input_path: &str, output_path: &str) -> NnResult<()> {
+        self.validation = load_data(input_path, output_path)?;
+        Ok(())
+    }
+
+    // moves a fraction of the dataset to the validation set,
+    // optionally keeping the same class distribution in both sets (a rejected split leaves the session unchanged)
+    pub fn split_validation(&mut self, fraction: f64, stratified: bool) -> NnResult<()> {
+        let labels: Option<Labels> = stratified.then_some(self.labels);
+        check_split(&self.dataset, fraction, labels)?;
+
+        let dataset = std::mem::take(&mut self.dataset);
+        let (dataset, validation) = split_dataset(dataset, fraction, labels, &mut self.rng)?;
+
+        self.dataset = dataset;
+        self.validation.extend(validation);
+        Ok(())
+    }
+
+    // outputs of the model for the given samples (one column per sample), without updating the weights
+    pub fn predict(&self, model: &mut dyn Model, samples: &[Sample]) -> NnResult<Matrix> {
+        let mut columns: Vec<Matrix> = Vec::with_capacity(samples.len());
+
+        for batch in samples.chunks(self.batch_size.max(1)) {
+            let (input, _) = batch_matrices(batch)?;
+
+            model.feed_forward(&input)?;
+            let result = model.result();
+
+            for x in 0..result.x_length {
+                columns.push(result.column(x));
+            }
+        }
+
+        Matrix::from_columns(&columns.iter().collect::<Vec<&Matrix>>())
+    }
+
+    // average loss (weighted by the weight of each sample, but not by the class weights,
+    // plus the regularization penalties) and metrics of the model on the given samples, without updating the weights
+    pub fn evaluate(&self, model: &mut dyn Model, samples: &[Sample]) -> NnResult<(f64, Vec<f64>)> {
+        let predictions = self.predict(model, samples)?;
+        let (_, desired_output) = batch_matrices(samples)?;
+        let weights = sample_weights(samples, None, self.labels)?;
+
+        let loss: f64 = model.loss().weighted_error(&predictions, &desired_output, &weights)?
+            + model.regularization_loss();
+
+        Ok((loss, self.compute_metrics(&predictions, &desired_output)?))
+    }
+
+    // a metric that is not defined for these samples (e.g. the area under the curve of a single class)
+    // is reported as NaN rather than stopping the training
+    fn compute_metrics(&self, predictions: &Matrix, desired_output: &Matrix) -> NnResult<Vec<f64>> {
+        self.metrics.iter().map(|metric| self.compute_metric(*metric, predictions, desired_output)).collect()
+    }
+
+    fn compute_metric(&self, metric: Metric, predictions: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+        match metric.compute(predictions, desired_output, self.labels) {
+            Err(NnError::InvalidDataset(_)) => Ok(f64::NAN),
+            result => result
+        }
+    }
+
+    // the metric watched by the early stopping, on the validation set when there is one,
+    // otherwise from the outputs seen during the epoch
+    fn monitored_metric(&self, model: &mut dyn Model, metric: Metric, predictions: &[Matrix]) -> NnResult<f64> {
+        let (predictions, samples) = if self.validation.is_empty() {
+            (Matrix::from_columns(&predictions.iter().collect::<Vec<&Matrix>>())?, &self.dataset)
+        } else {
+            (self.predict(model, &self.validation)?, &self.validation)
+        };
+        let (_, desired_output) = batch_matrices(samples)?;
+
+        self.compute_metric(metric, &predictions, &desired_output)
+    }
+
+    // one pass over the shuffled dataset, returning the sum of the losses of every sample
+    // and, when asked, the outputs of the model for each of them
+    fn train_epoch(&mut self, model: &mut dyn Model, class_weights: &[f64], learning_rate: f64,
+        keep_predictions: bool) -> NnResult<(f64, Vec<Matrix>)> {
+
+        let mut loss_buffer: f64 = 0.0;
+        let mut predictions: Vec<Matrix> = Vec::new();
+
+        for batch in self.dataset.chunks(self.batch_size.max(1)) {
+
+            let (input, output) = batch_matrices(batch)?;
+            let weights = sample_weights(batch, Some(class_weights), self.labels)?;
+
+            model.feed_forward(&input)?;
+
+            let error: f64 = model.loss().weighted_error(&model.result(), &output, &weights)?
+                + model.regularization_loss();
+
+            loss_buffer += error * batch.len() as f64;
+
+            if keep_predictions {
+                let result = model.result();
+
+                for x in 0..result.x_length {
+                    predictions.push(result.column(x));
+                }
+            }
+
+            let deltas = model.back_propagate_weighted(&output, &weights)?;
+            let mut gradients = model.gradients(&deltas)?;
+
+            if let Some(clipping) = self.gradient_clipping {
+                clipping.apply(&mut gradients);
+            }
+            model.update_weights(&gradients, self.optimizer.as_mut(), learning_rate)?;
         }
+        Ok((loss_buffer, predictions))
     }
 
-    pub fn train(&mut self, model: &mut DenseModel) {
+    pub fn train(&mut self, model: &mut dyn Model) -> NnResult<()> {
+
+        if let Some(clipping) = self.gradient_clipping {
+            clipping.check()?;
+        }
+
+        if model.loss().as_dense() == Some(DenseLosses::SparseCategoricalCrossEntropy) && self.labels == Labels::Encoded {
+            return Err(NnError::InvalidDataset(
+                "SparseCategoricalCrossEntropy reads class indices, set the session labels to Labels::Sparse".to_string()));
+        }
+
+        if let Some(early_stopping) = self.early_stopping.as_mut() {
+            early_stopping.reset();
+        }
+
+        // computed once, from the distribution of the whole training set
+        let class_weights: Vec<f64> = self.class_weights.compute(&self.dataset, self.labels)?;
 
-        let mut rng = thread_rng();
+        let monitored_metric: Option<Metric> = self.early_stopping.as_ref().and_then(|e| e.monitor).map(|(metric, _)| metric);
         
         for i in 0..self.nb_epochs {
             
-            self.dataset.shuffle(&mut rng);
-            let mut loss_buffer: f64 = 0.0;
-            
-            for j in 0..self.dataset.len() {
-
-                model.feed_forward(&self.dataset[j].input);
-                
-                let error: f64 = calculate_error(&model.loss, &model.result(), &self.dataset[j].output);
-                
-                loss_buffer += error;
-
-                let deltas = model.back_propagate(&self.dataset[j].output);
-                model.update_weights(&deltas, self.learning_rate);
-
-                if i == self.nb_epochs - 1{
-                    println!("Sample:");
-                    self.dataset[j].print_sample();
-                    println!("guessed output:");
-                    model.result().print();
+            self.dataset.shuffle(&mut self.rng);
+
+            let learning_rate: f64 = self.lr_schedule.learning_rate(i, self.learning_rate);
+
+            let log_epoch: bool = i % self.log_interval.max(1) == 0 || i == self.nb_epochs - 1;
+
+            let keep_predictions: bool = (log_epoch && !self.metrics.is_empty())
+                || (monitored_metric.is_some() && self.validation.is_empty());
+
+            // dropout is only active while the weights are updated, not during the validation
+            model.set_training(true);
+            let epoch = self.train_epoch(model, &class_weights, learning_rate, keep_predictions);
+            model.set_training(false);
+
+            // the training metrics are computed from the outputs seen during the epoch
+            let (loss_buffer, predictions) = epoch?;
+
+            let avg_loss: f64 = loss_buffer / (self.dataset.len() as f64);
+
+            // once there is a validation set, it drives the schedule and the early stopping
+            let validation: Option<(f64, Vec<f64>)> = if self.validation.is_empty() {
+                None
+            } else {
+                Some(self.evaluate(model, &self.validation)?)
+            };
+            let monitored_loss: f64 = validation.as_ref().map_or(avg_loss, |v| v.0);
+
+            self.lr_schedule.observe_loss(monitored_loss);
+
+            if log_epoch {
+                let mut report = format!("Epoch nb: {i} done: average loss = {avg_loss}");
+
+                if let Some((validation_loss, _)) = &validation {
+                    report.push_str(&format!(", validation loss = {validation_loss}"));
                 }
+
+                if !self.metrics.is_empty() {
+                    let (_, desired_output) = batch_matrices(&self.dataset)?;
+                    let predictions = Matrix::from_columns(&predictions.iter().collect::<Vec<&Matrix>>())?;
+                    let train_metrics = self.compute_metrics(&predictions, &desired_output)?;
+
+                    for (j, metric) in self.metrics.iter().enumerate() {
+                        report.push_str(&format!(", {metric} = {}", train_metrics[j]));
+
+                        if let Some((_, validation_metrics)) = &validation {
+                            report.push_str(&format!(", validation {metric} = {}", validation_metrics[j]));
+                        }
+                    }
+                }
+
+                println!("{report}, learning rate = {learning_rate}");
             }
 
-            let avg_loss: f64 = loss_buffer / (self.dataset.len() as f64);
-            if i % 1000 == 0 {
-                println!("Epoch nb: {i} done: average loss = {avg_loss}");
+            if self.stop_on_loss_threshold && avg_loss < self.loss_threshold {
+                println!("Epoch nb: {i}: average loss = {avg_loss} is below the threshold, stopping.");
+                break;
+            }
+
+            let monitored_value: f64 = match monitored_metric {
+                Some(metric) => self.monitored_metric(model, metric, &predictions)?,
+                None => monitored_loss
+            };
+
+            if let Some(early_stopping) = self.early_stopping.as_mut() {
+                // the running statistics are kept after the parameters, so that both come from the best epoch
+                let snapshot = || {
+                    let mut parameters: Vec<Matrix> = model.copy_parameters();
+                    parameters.extend(model.running_statistics());
+                    parameters
+                };
+
+                if early_stopping.observe(i, monitored_value, snapshot) {
+                    let best_epoch = early_stopping.best_epoch();
+                    println!("Epoch nb: {i}: no improvement since epoch {best_epoch}, stopping.");
+
+                    if let Some(mut parameters) = early_stopping.take_best_parameters() {
+                        let statistics: Vec<Matrix> = parameters.split_off(model.copy_parameters().len());
+                        model.set_parameters(&parameters)?;
+                        model.set_running_statistics(&statistics)?;
+                    }
+                    break;
+                }
             }
         }
 
+        Ok(())
+    }
+
+    // saves the model (.arch and .wab) along with the optimizer state (.opt)
+    pub fn save_checkpoint<M: SaveableModel>(&self, model: &M, filename: &str) -> NnResult<()> {
+        model.save(filename)?;
+        save_optimizer(self.optimizer.as_ref(), filename)
+    }
+
+    // restores the optimizer state of the session and returns the saved model (a DenseModel or a Sequential),
+    // so training can resume where it stopped (reproducibly once the session is seeded)
+    pub fn load_checkpoint<M: SaveableModel>(&mut self, filename: &str) -> NnResult<M> {
+        self.load_checkpoint_with(filename, &Registry::new())
+    }
+
+    // same as load_checkpoint, for models using custom activations, losses or layers
+    pub fn load_checkpoint_with<M: SaveableModel>(&mut self, filename: &str, registry: &Registry) -> NnResult<M> {
+        let mut model = M::load_model_with(filename, registry)?;
+        self.optimizer = load_optimizer(filename)?;
 
+        if let Some(seed) = self.seed {
+            model.set_seed(seed);
+        }
+
+        Ok(model)
     }
-}
\ No newline at end of file
+}
+
+fn batch_matrices(batch: &[Sample]) -> NnResult<(Matrix, Matrix)> {
+    let inputs: Vec<&Matrix> = batch.iter().map(|sample| &sample.input).collect();
+    let outputs: Vec<&Matrix> = batch.iter().map(|sample| &sample.output).collect();
+
+    Ok((Matrix::from_columns(&inputs)?, Matrix::from_columns(&outputs)?))
+}
@@ -0,0 +1,23 @@
+// Width (x), height (y) and depth (z, the channels) of the values of a layer for one sample.
+// Samples are stored as flat columns, channel after channel and row after row,
+// so the value at (x, y, z) is at index (z * y_length + y) * x_length + x.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DenseShape {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+    pub range: usize,
+}
+
+impl DenseShape {
+    pub fn new(x: usize, y: usize, z: usize) -> DenseShape {
+
+        DenseShape {
+            x,
+            y,
+            z,
+            range: x*y*z
+        }
+    }
+    
+}
\ No newline at end of file
@@ -0,0 +1,14 @@
+// Reads the parameters of the names saved in the .arch files, e.g. "LeakyRelu(0.01)" or "ElasticNet(0.1,0.2)".
+
+// reads the values of "Name(value, ...)"
+pub(crate) fn parse_arguments(input: &str, name: &str) -> Option<Vec<f64>> {
+    input.strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix('('))
+        .and_then(|rest| rest.strip_suffix(')'))
+        .and_then(|values| values.split(',').map(|value| value.trim().parse::<f64>().ok()).collect())
+}
+
+// reads the value of "Name(value)"
+pub(crate) fn parse_argument(input: &str, name: &str) -> Option<f64> {
+    parse_arguments(input, name).filter(|values| values.len() == 1).map(|values| values[0])
+}
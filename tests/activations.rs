@@ -0,0 +1,175 @@
+use rusty_nn::activations::dense_activation::{apply_activation, apply_prelu, DenseActivation};
+use rusty_nn::derivations::dense_derivation::apply_derivation;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::maths::special_functions::erf;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::optimizers::sgd::Sgd;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::TempFiles;
+
+const EPSILON: f64 = 1e-6;
+const TOLERANCE: f64 = 1e-6;
+
+// every element-wise activation (softmax is checked through its jacobian by the gradient check)
+fn element_wise() -> Vec<DenseActivation> {
+    vec![
+        DenseActivation::Sigmoid,
+        DenseActivation::Relu,
+        DenseActivation::LeakyRelu(0.01),
+        DenseActivation::LeakyRelu(0.2),
+        DenseActivation::Tanh,
+        DenseActivation::Gelu,
+        DenseActivation::GeluTanh,
+        DenseActivation::Elu(1.0),
+        DenseActivation::Elu(0.5),
+        DenseActivation::Selu,
+        DenseActivation::Swish,
+        DenseActivation::Mish,
+        DenseActivation::Softplus,
+        DenseActivation::Softsign,
+        DenseActivation::HardSigmoid,
+        DenseActivation::HardTanh,
+        DenseActivation::Identity
+    ]
+}
+
+fn activate(activation: &DenseActivation, x: f64) -> f64 {
+    let mut mat = Matrix::vec_to_col_mat(&[x]);
+    apply_activation(activation, &mut mat).unwrap();
+    mat.values[0]
+}
+
+#[test]
+fn names_round_trip() {
+    let mut activations = element_wise();
+    activations.push(DenseActivation::NoActivation);
+    activations.push(DenseActivation::Softmax);
+    activations.push(DenseActivation::PRelu);
+
+    for activation in activations {
+        let name = activation.to_string();
+        assert_eq!(name.parse::<DenseActivation>().unwrap().to_string(), name);
+    }
+
+    assert_eq!("Silu".parse::<DenseActivation>().unwrap().to_string(), "Swish");
+    assert!("Unknown".parse::<DenseActivation>().is_err());
+    assert!("LeakyRelu(x)".parse::<DenseActivation>().is_err());
+
+    // architectures saved before the activations carried their parameters
+    assert_eq!("LeakyRelu".parse::<DenseActivation>().unwrap(), DenseActivation::LeakyRelu(0.01));
+    assert_eq!("Elu".parse::<DenseActivation>().unwrap(), DenseActivation::Elu(1.0));
+    assert_eq!("LeakyRelu(0.2)".parse::<DenseActivation>().unwrap(), DenseActivation::LeakyRelu(0.2));
+}
+
+#[test]
+fn derivatives_match_finite_differences() {
+    // away from the kinks at -3, -1, 0, 1 and 3
+    let points = [-5.0, -2.5, -1.3, -0.7, -0.2, 0.3, 0.8, 1.6, 2.2, 4.0];
+
+    for activation in element_wise() {
+        for x in points {
+            let numerical = (activate(&activation, x + EPSILON) - activate(&activation, x - EPSILON))
+                / (2.0 * EPSILON);
+            let analytic = apply_derivation(&activation, x).unwrap();
+
+            assert!((analytic - numerical).abs() < TOLERANCE,
+                "{activation} at {x}: analytic {analytic}, numerical {numerical}");
+        }
+    }
+}
+
+#[test]
+fn known_values() {
+    let cases = [
+        (DenseActivation::Gelu, 1.0, 0.8413447460685429),
+        (DenseActivation::GeluTanh, 1.0, 0.8411919906082768),
+        (DenseActivation::LeakyRelu(0.1), -2.0, -0.2),
+        (DenseActivation::LeakyRelu(0.1), 3.0, 3.0),
+        (DenseActivation::Elu(1.0), -1.0, -0.6321205588285577),
+        (DenseActivation::Elu(2.0), -1.0, -1.2642411176571153),
+        (DenseActivation::Selu, -1.0, -1.1113307378125625),
+        (DenseActivation::Swish, 1.0, 0.7310585786300049),
+        (DenseActivation::Mish, 1.0, 0.8650983882673103),
+        (DenseActivation::Softplus, 0.0, std::f64::consts::LN_2),
+        (DenseActivation::Softsign, 3.0, 0.75),
+        (DenseActivation::HardSigmoid, 1.5, 0.75),
+        (DenseActivation::HardTanh, -2.0, -1.0),
+        (DenseActivation::Identity, -2.5, -2.5)
+    ];
+
+    for (activation, x, expected) in cases {
+        let value = activate(&activation, x);
+        assert!((value - expected).abs() < 1e-12, "{activation} at {x}: {value}, expected {expected}");
+    }
+}
+
+#[test]
+fn activations_stay_finite_on_large_inputs() {
+    for activation in element_wise() {
+        for x in [-1000.0, 1000.0] {
+            assert!(activate(&activation, x).is_finite(), "{activation} at {x}");
+            assert!(apply_derivation(&activation, x).unwrap().is_finite(), "{activation}' at {x}");
+        }
+    }
+}
+
+#[test]
+fn erf_values() {
+    assert_eq!(erf(0.0), 0.0);
+    assert!((erf(0.5) - 0.5204998778130465).abs() < 1e-15);
+    assert!((erf(-1.0) + 0.8427007929497149).abs() < 1e-15);
+    assert!((erf(2.0) - 0.9953222650189527).abs() < 1e-15);
+    assert_eq!(erf(10.0), 1.0);
+}
+
+#[test]
+fn prelu_needs_its_slopes() {
+    let mut mat = Matrix::vec_to_col_mat(&[-2.0, 3.0]);
+    assert!(apply_activation(&DenseActivation::PRelu, &mut mat).is_err());
+    assert!(apply_derivation(&DenseActivation::PRelu, 1.0).is_err());
+
+    apply_prelu(&mut mat, &Matrix::vec_to_col_mat(&[0.5, 0.5])).unwrap();
+    assert_eq!(mat.values, vec![-1.0, 3.0]);
+
+    assert!(apply_prelu(&mut mat, &Matrix::vec_to_col_mat(&[0.5])).is_err());
+}
+
+#[test]
+fn prelu_slopes_are_trained_and_saved() {
+    let shapes = vec![DenseShape::new(2, 1, 1), DenseShape::new(3, 1, 1), DenseShape::new(1, 1, 1)];
+    let mut model = DenseModel::new_seeded(vec![DenseActivation::PRelu, DenseActivation::Sigmoid],
+        DenseLosses::MeanSquaredError, shapes, Some(5)).unwrap();
+
+    let input = Matrix::from_columns(&[&Matrix::vec_to_col_mat(&[-1.0, -2.0]),
+        &Matrix::vec_to_col_mat(&[1.5, -0.5])]).unwrap();
+    let output = Matrix::from_columns(&[&Matrix::vec_to_col_mat(&[1.0]),
+        &Matrix::vec_to_col_mat(&[0.0])]).unwrap();
+
+    let initial_slopes = model.copy_parameters()[4].copy();
+    assert_eq!(initial_slopes.values, vec![0.25; 3]);
+
+    for _ in 0..20 {
+        model.feed_forward(&input).unwrap();
+        let deltas = model.back_propagate(&output).unwrap();
+        let gradients = model.gradients(&deltas).unwrap();
+        model.update_weights(&gradients, &mut Sgd::new(), 0.5).unwrap();
+    }
+
+    let slopes = model.copy_parameters()[4].copy();
+    assert_ne!(slopes.values, initial_slopes.values);
+
+    let files = TempFiles::new("prelu_model");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+    let mut loaded = DenseModel::load_model(&filename).unwrap();
+
+    assert_eq!(loaded.copy_parameters()[4].values, slopes.values);
+
+    model.feed_forward(&input).unwrap();
+    loaded.feed_forward(&input).unwrap();
+    assert_eq!(model.result().values, loaded.result().values);
+}
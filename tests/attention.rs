@@ -0,0 +1,202 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::layers::attention_layer::{scaled_dot_product_attention, MultiHeadAttention};
+use rusty_nn::layers::dense_layer::Dense;
+use rusty_nn::layers::flatten_layer::Flatten;
+use rusty_nn::layers::layer::Layer;
+use rusty_nn::layers::positional_layer::PositionalEncoding;
+use rusty_nn::layers::transformer_layer::TransformerEncoder;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::gradient_check::parameter_errors;
+use rusty_nn::models::registry::Registry;
+use rusty_nn::models::sequential::Sequential;
+use rusty_nn::optimizers::adam::Adam;
+use rusty_nn::regularizers::dense_dropout::DenseDropout;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::{activation, TempFiles};
+
+// three sequences of 4 steps of 3 features (one per column), and their class out of 2
+fn batch() -> (Matrix, Matrix) {
+    let mut input = Matrix::new(3, 12);
+    input.values = (0..36).map(|i| ((i * 29 % 47) as f64 / 23.0 - 1.0) * if i % 5 == 0 {-1.5} else {1.0}).collect();
+    let mut output = Matrix::new(3, 2);
+    output.values = vec![1.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+    (input, output)
+}
+
+fn model(mut layers: Vec<Box<dyn Layer>>, seed: u64) -> Sequential {
+    layers.push(Box::new(Flatten::new()));
+    common::classifier(DenseShape::new(3, 4, 1), layers, 2, seed)
+}
+
+#[test]
+fn attention_gradients_match_numerical_ones() {
+    let (input, output) = batch();
+
+    let stacks: Vec<Vec<Box<dyn Layer>>> = vec![
+        vec![Box::new(MultiHeadAttention::new(2, 3))],
+        vec![Box::new(PositionalEncoding::learned()), Box::new(MultiHeadAttention::new(1, 2))],
+        vec![Box::new(PositionalEncoding::sinusoidal()), Box::new(TransformerEncoder::new(2, 2, 5).activated(DenseActivation::Tanh)),
+            Box::new(TransformerEncoder::new(1, 3, 4).activated(DenseActivation::PRelu))]
+    ];
+
+    for layers in stacks {
+        let tags: Vec<String> = layers.iter().map(|layer| layer.tag()).collect();
+        let mut model = model(layers, 3);
+        let (errors, update_errors) = parameter_errors(&mut model, &input, &output, 1e-5).unwrap();
+
+        for (p, (error, update_error)) in errors.iter().zip(update_errors.iter()).enumerate() {
+            assert!(*error < 1e-4 && *update_error < 1e-4, "{tags:?}, parameter {p}: {error}, {update_error}");
+        }
+    }
+}
+
+#[test]
+fn scaled_dot_product_attention_weights_the_values() {
+    let mut query = Matrix::new(2, 2);
+    query.values = vec![1.0, 0.0, 0.0, 0.0];
+    let mut key = Matrix::new(2, 3);
+    key.values = vec![2.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+    let mut value = Matrix::new(1, 3);
+    value.values = vec![1.0, 2.0, 4.0];
+
+    let (output, weights) = scaled_dot_product_attention(&query, &key, &value).unwrap();
+
+    // the second query does not prefer any key
+    let scores: Vec<f64> = vec![2f64.sqrt().exp(), 1.0, 1.0];
+    let sum: f64 = scores.iter().sum();
+    let expected: Vec<f64> = scores.iter().map(|s| s / sum).chain([1.0 / 3.0; 3]).collect();
+
+    for (w, e) in weights.values.iter().zip(expected.iter()) {
+        assert!((w - e).abs() < 1e-12, "{:?}", weights.values);
+    }
+    assert!((output.values[0] - (scores[0] + 2.0 + 4.0) / sum).abs() < 1e-12);
+    assert!((output.values[1] - 7.0 / 3.0).abs() < 1e-12);
+
+    assert!(matches!(scaled_dot_product_attention(&query, &value, &value), Err(NnError::ShapeMismatch {..})));
+}
+
+#[test]
+fn positional_encodings_order_the_steps() {
+    let (input, _) = batch();
+
+    // swaps the first two steps of every sample
+    let mut swapped = input.copy();
+    for s in 0..3 {
+        for i in 0..3 {
+            swapped.set(i, s, input.get(3 + i, s));
+            swapped.set(3 + i, s, input.get(i, s));
+        }
+    }
+
+    let outputs = |layers: Vec<Box<dyn Layer>>| -> (Matrix, Matrix) {
+        let mut model = Sequential::with_input_shape(DenseShape::new(3, 4, 1), layers, Box::new(DenseLosses::MeanSquaredError), Some(1)).unwrap();
+        model.feed_forward(&input).unwrap();
+        let output = model.result();
+        model.feed_forward(&swapped).unwrap();
+        (output, model.result())
+    };
+
+    // without positions, attention only swaps the outputs of the swapped steps
+    let (output, swapped_output) = outputs(vec![Box::new(TransformerEncoder::new(2, 2, 4))]);
+    for s in 0..3 {
+        for i in 0..3 {
+            assert!((output.get(i, s) - swapped_output.get(3 + i, s)).abs() < 1e-12);
+            assert!((output.get(9 + i, s) - swapped_output.get(9 + i, s)).abs() < 1e-12);
+        }
+    }
+
+    let (output, swapped_output) = outputs(vec![Box::new(PositionalEncoding::sinusoidal()), Box::new(TransformerEncoder::new(2, 2, 4))]);
+    assert!((output.get(9, 0) - swapped_output.get(9, 0)).abs() > 1e-6);
+
+    let (encoded, _) = outputs(vec![Box::new(PositionalEncoding::sinusoidal())]);
+    let step: f64 = 1.0 / 10000f64.powf(2.0 / 3.0);
+    assert_eq!(encoded.get(0, 0), input.get(0, 0));
+    assert_eq!(encoded.get(1, 0), input.get(1, 0) + 1.0);
+    assert!((encoded.get(5, 1) - input.get(5, 1) - step.sin()).abs() < 1e-12);
+    assert!((encoded.get(10, 2) - input.get(10, 2) - (3.0f64).cos()).abs() < 1e-12);
+}
+
+#[test]
+fn encoder_dropout_only_drops_while_training() {
+    let (input, _) = batch();
+    let mut model = model(vec![Box::new(TransformerEncoder::new(2, 2, 4).with_dropout(DenseDropout::Dropout(0.5)))], 2);
+
+    model.feed_forward(&input).unwrap();
+    let inference = model.result();
+    model.feed_forward(&input).unwrap();
+    assert_eq!(model.result().values, inference.values);
+
+    model.set_training(true);
+    model.feed_forward(&input).unwrap();
+    assert_ne!(model.result().values, inference.values);
+}
+
+#[test]
+fn session_trains_a_transformer() {
+    // does the last step hold the greatest first feature
+    let mut inputs: Vec<Vec<f64>> = Vec::new();
+    let mut outputs: Vec<Vec<f64>> = Vec::new();
+
+    for s in 0..48 {
+        let values: Vec<f64> = (0..8).map(|i| ((s * 13 + i * 7) % 17) as f64 / 8.0 - 1.0).collect();
+        let firsts: Vec<f64> = values.iter().step_by(2).cloned().collect();
+        let last_is_greatest: bool = firsts[..3].iter().all(|x| *x < firsts[3]);
+
+        inputs.push(values);
+        outputs.push(vec![if last_is_greatest {1.0} else {0.0}]);
+    }
+
+    let files = TempFiles::new("attention_session");
+    let mut session = common::session(&files, DenseShape::new(2, 4, 1), &inputs, DenseShape::new(1, 1, 1), &outputs, 100, 0.01);
+    session.set_seed(4);
+    session.batch_size = 12;
+    session.log_interval = 1000;
+    session.optimizer = Box::new(Adam::default());
+
+    let layers: Vec<Box<dyn Layer>> = vec![Box::new(PositionalEncoding::sinusoidal()), Box::new(TransformerEncoder::new(2, 4, 8)),
+        Box::new(Flatten::new()), Box::new(Dense::new(1)), activation(DenseActivation::Sigmoid)];
+    let mut model = Sequential::with_input_shape(DenseShape::new(2, 4, 1), layers,
+        Box::new(DenseLosses::BinaryCrossEntropy), Some(5)).unwrap();
+
+    let (initial_loss, _) = session.evaluate(&mut model, &session.dataset).unwrap();
+    session.train(&mut model).unwrap();
+    let (loss, _) = session.evaluate(&mut model, &session.dataset).unwrap();
+
+    assert!(loss < initial_loss / 3.0, "{initial_loss} -> {loss}");
+}
+
+#[test]
+fn attention_models_are_saved() {
+    let (input, _) = batch();
+    let mut model = model(vec![Box::new(PositionalEncoding::learned()), Box::new(MultiHeadAttention::new(2, 3)),
+        Box::new(TransformerEncoder::new(1, 4, 6).activated(DenseActivation::Gelu).with_dropout(DenseDropout::Dropout(0.1)))], 7);
+
+    let files = TempFiles::new("attention_model");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+
+    let mut loaded = Sequential::load_model(&filename).unwrap();
+
+    let tags: Vec<String> = loaded.layers().iter().map(|layer| layer.tag()).collect();
+    assert_eq!(tags[..3], ["PositionalEncoding Learned", "MultiHeadAttention 2 3", "TransformerEncoder 1 4 6 Gelu Dropout(0.1)"]);
+
+    model.feed_forward(&input).unwrap();
+    loaded.feed_forward(&input).unwrap();
+    assert_eq!(model.result().values, loaded.result().values);
+
+    let registry = Registry::new();
+    assert_eq!(registry.layer("PositionalEncoding").unwrap().tag(), "PositionalEncoding Sinusoidal");
+    assert_eq!(registry.layer("TransformerEncoder 2 2 8").unwrap().tag(), "TransformerEncoder 2 2 8 Relu NoDropout");
+    assert!(registry.layer("PositionalEncoding Rotary").is_err());
+    assert!(registry.layer("MultiHeadAttention 2").is_err());
+    assert!(registry.layer("TransformerEncoder 2 2 8 Unknown").is_err());
+
+    let invalid = Sequential::with_input_shape(DenseShape::new(3, 4, 2), vec![Box::new(MultiHeadAttention::new(2, 3))],
+        Box::new(DenseLosses::MeanSquaredError), None);
+    assert!(matches!(invalid, Err(NnError::ShapeMismatch {..})));
+}
@@ -0,0 +1,96 @@
+use rusty_nn::prelude::*;
+
+mod common;
+
+use common::TempFiles;
+
+// four samples of 3 values, one per column, and their expected outputs
+fn batch() -> (Matrix, Matrix) {
+    let mut input = Matrix::new(4, 3);
+    input.values = vec![0.5, -1.0, 0.2, 0.9, 0.3, -0.7, 0.8, -0.1, -0.4, 0.6, 0.1, -0.9];
+    let mut output = Matrix::new(4, 2);
+    output.values = vec![1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0];
+    (input, output)
+}
+
+// a saved model, loaded again each time the same parameters are needed
+fn saved_model(files: &TempFiles) -> String {
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(5, 1, 1), DenseShape::new(2, 1, 1)];
+    let model = DenseModel::new(vec![DenseActivation::Tanh, DenseActivation::Sigmoid], DenseLosses::MeanSquaredError, shapes);
+
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+    filename
+}
+
+fn saved_parameters(model: &DenseModel, filename: &str) -> Vec<f64> {
+    model.save(filename).unwrap();
+    std::fs::read_to_string(format!("{filename}.wab")).unwrap()
+        .split_whitespace().map(|x| x.parse().unwrap()).collect()
+}
+
+#[test]
+fn batches_feed_forward_each_sample() {
+    let files = TempFiles::new("batches_forward");
+    let filename = saved_model(&files);
+    let (input, _) = batch();
+
+    let mut model = DenseModel::load_model(&filename).unwrap();
+    model.feed_forward(&input).unwrap();
+    let result = model.result();
+    assert_eq!((result.y_length, result.x_length), (2, 4));
+
+    for x in 0..4 {
+        model.feed_forward(&input.column(x)).unwrap();
+        assert_eq!(result.column(x).values, model.result().values, "sample {x}");
+    }
+}
+
+#[test]
+fn batches_back_propagate_each_sample() {
+    let files = TempFiles::new("batches_backward");
+    let filename = saved_model(&files);
+    let (input, output) = batch();
+
+    let mut model = DenseModel::load_model(&filename).unwrap();
+    model.feed_forward(&input).unwrap();
+    let deltas = model.back_propagate(&output).unwrap();
+
+    for x in 0..4 {
+        model.feed_forward(&input.column(x)).unwrap();
+        let sample_deltas = model.back_propagate(&output.column(x)).unwrap();
+
+        for l in 1..deltas.len() {
+            assert_eq!(deltas[l].column(x).values, sample_deltas[l].values, "sample {x}, layer {l}");
+        }
+    }
+}
+
+#[test]
+fn batch_updates_average_the_sample_updates() {
+    let files = TempFiles::new("batches_update");
+    let filename = saved_model(&files);
+    let (input, output) = batch();
+
+    let update = |input: &Matrix, output: &Matrix| {
+        let mut model = DenseModel::load_model(&filename).unwrap();
+        model.feed_forward(input).unwrap();
+        let deltas = model.back_propagate(output).unwrap();
+        let gradients = model.gradients(&deltas).unwrap();
+        model.update_weights(&gradients, &mut Sgd::new(), 0.5).unwrap();
+        saved_parameters(&model, &files.path("updated"))
+    };
+
+    let batched = update(&input, &output);
+
+    let mut averaged = vec![0.0; batched.len()];
+    for x in 0..4 {
+        for (sum, parameter) in averaged.iter_mut().zip(update(&input.column(x), &output.column(x))) {
+            *sum += parameter / 4.0;
+        }
+    }
+
+    for (batched, averaged) in batched.iter().zip(averaged) {
+        assert!((batched - averaged).abs() < 1e-12, "{batched} instead of {averaged}");
+    }
+}
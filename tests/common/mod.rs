@@ -0,0 +1,103 @@
+// Helpers shared by the integration tests, each test file declaring `mod common;`
+// (so not every file uses every helper).
+#![allow(dead_code)]
+
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::data::create_data::Sample;
+use rusty_nn::layers::activation_layer::ActivationLayer;
+use rusty_nn::layers::dense_layer::Dense;
+use rusty_nn::layers::layer::Layer;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::sequential::Sequential;
+use rusty_nn::sessions::session::Session;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub fn activation(activation: DenseActivation) -> Box<dyn Layer> {
+    Box::new(ActivationLayer::new(Box::new(activation)))
+}
+
+// four samples of 3 values, one per column, and their class out of 3
+pub fn batch() -> (Matrix, Matrix) {
+    let mut input = Matrix::new(4, 3);
+    input.values = vec![0.5, -1.0, 0.2, 0.9, 0.3, -0.7, 0.8, -0.1, -0.4, 0.6, 0.1, -0.9];
+    let mut output = Matrix::new(4, 3);
+    output.values = vec![1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+    (input, output)
+}
+
+// the inputs and the outputs of the samples, one per column
+pub fn columns(inputs: &[Vec<f64>], outputs: &[Vec<f64>]) -> (Matrix, Matrix) {
+    let inputs: Vec<Matrix> = inputs.iter().map(|x| Matrix::vec_to_col_mat(x)).collect();
+    let outputs: Vec<Matrix> = outputs.iter().map(|x| Matrix::vec_to_col_mat(x)).collect();
+
+    (Matrix::from_columns(&inputs.iter().collect::<Vec<&Matrix>>()).unwrap(),
+     Matrix::from_columns(&outputs.iter().collect::<Vec<&Matrix>>()).unwrap())
+}
+
+pub fn batch_of(samples: &[Sample]) -> (Matrix, Matrix) {
+    let inputs: Vec<&Matrix> = samples.iter().map(|sample| &sample.input).collect();
+    let outputs: Vec<&Matrix> = samples.iter().map(|sample| &sample.output).collect();
+
+    (Matrix::from_columns(&inputs).unwrap(), Matrix::from_columns(&outputs).unwrap())
+}
+
+// the layers followed by a softmax over nb_classes, trained with the categorical cross-entropy
+pub fn classifier(input_shape: DenseShape, mut layers: Vec<Box<dyn Layer>>, nb_classes: usize, seed: u64) -> Sequential {
+    layers.push(Box::new(Dense::new(nb_classes)));
+    layers.push(activation(DenseActivation::Softmax));
+
+    Sequential::with_input_shape(input_shape, layers, Box::new(DenseLosses::CategoricalCrossEntropy), Some(seed)).unwrap()
+}
+
+// A directory of the temporary directory only used by one test, removed with everything it holds when dropped.
+pub struct TempFiles {
+    directory: PathBuf
+}
+
+impl TempFiles {
+    pub fn new(name: &str) -> TempFiles {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let directory = std::env::temp_dir().join(format!("rusty_nn_{name}_{}_{}",
+            std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+        std::fs::create_dir_all(&directory).unwrap();
+
+        TempFiles {directory}
+    }
+
+    pub fn path(&self, file: &str) -> String {
+        self.directory.join(file).to_str().unwrap().to_string()
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+// writes the samples in the data files read by Session::new, then opens a session over them
+pub fn session(files: &TempFiles, input_shape: DenseShape, inputs: &[Vec<f64>], output_shape: DenseShape,
+    outputs: &[Vec<f64>], nb_epochs: usize, learning_rate: f64) -> Session {
+
+    let write = |file: &str, shape: DenseShape, rows: &[Vec<f64>]| -> String {
+        let path: String = files.path(file);
+        let mut content: String = format!("{} {} {} {}\n", rows.len(), shape.x, shape.y, shape.z);
+
+        for row in rows {
+            content.push_str(&row.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(" "));
+            content.push('\n');
+        }
+        std::fs::write(&path, content).unwrap();
+        path
+    };
+
+    let input_path: String = write("input.txt", input_shape, inputs);
+    let output_path: String = write("output.txt", output_shape, outputs);
+
+    Session::new(input_path, output_path, nb_epochs, learning_rate, 0.0, false).unwrap()
+}
@@ -0,0 +1,204 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::initializers::dense_initializer::DenseInitializer;
+use rusty_nn::layers::conv_layer::Conv2D;
+use rusty_nn::layers::flatten_layer::Flatten;
+use rusty_nn::layers::layer::Layer;
+use rusty_nn::layers::pooling_layer::{AveragePool2D, GlobalAveragePooling, MaxPool2D};
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::maths::random::new_rng;
+use rusty_nn::models::gradient_check::parameter_errors;
+use rusty_nn::models::sequential::Sequential;
+use rusty_nn::optimizers::adam::Adam;
+use rusty_nn::regularizers::dense_regularizer::DenseRegularizer;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::{activation, TempFiles};
+
+// three 5 * 5 samples with 2 channels (one per column), and their class out of 3
+fn batch() -> (Matrix, Matrix) {
+    let mut input = Matrix::new(3, 50);
+    input.values = (0..150).map(|i| ((i * 37 % 101) as f64 / 50.0 - 1.0) * if i % 7 == 0 {-1.0} else {1.0}).collect();
+    let mut output = Matrix::new(3, 3);
+    output.values = vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+    (input, output)
+}
+
+fn layers(pooling: Box<dyn Layer>) -> Vec<Box<dyn Layer>> {
+    vec![
+        Box::new(Conv2D::new(3, (3, 2)).padded((1, 1)).dilated((1, 2)).regularized(DenseRegularizer::L2(0.01))),
+        activation(DenseActivation::Tanh),
+        pooling,
+        Box::new(Conv2D::new(2, (2, 2)).strided((1, 2)).initialized(DenseInitializer::HeNormal, DenseInitializer::Constant(0.1))),
+        activation(DenseActivation::Sigmoid),
+        Box::new(Flatten::new())
+    ]
+}
+
+// the layers followed by a softmax over the 3 classes of batch
+fn classifier(pooling: Box<dyn Layer>, drained: bool, seed: u64) -> Sequential {
+    let mut layers = layers(pooling);
+
+    // the second convolution does not fit after a global pooling
+    if drained {
+        layers.drain(3..5);
+    }
+    common::classifier(DenseShape::new(5, 5, 2), layers, 3, seed)
+}
+
+#[test]
+fn convolution_gradients_match_numerical_ones() {
+    let (input, output) = batch();
+    let poolings: Vec<Box<dyn Layer>> = vec![Box::new(MaxPool2D::new((2, 2)).strided((1, 1))),
+        Box::new(AveragePool2D::new((2, 3)).strided((1, 1))), Box::new(GlobalAveragePooling::new())];
+
+    for pooling in poolings {
+        let tag: String = pooling.tag();
+        let mut model = classifier(pooling, tag == "GlobalAveragePooling", 5);
+        let (errors, update_errors) = parameter_errors(&mut model, &input, &output, 1e-5).unwrap();
+
+        for (p, (error, update_error)) in errors.iter().zip(update_errors.iter()).enumerate() {
+            assert!(*error < 1e-4 && *update_error < 1e-4, "{tag}, parameter {p}: {error}, {update_error}");
+        }
+    }
+}
+
+#[test]
+fn layers_produce_shaped_outputs() {
+    let shapes = |layers: Vec<Box<dyn Layer>>| -> DenseShape {
+        Sequential::with_input_shape(DenseShape::new(7, 6, 3), layers, Box::new(DenseLosses::MeanSquaredError), None)
+            .unwrap().output_shape()
+    };
+
+    assert_eq!(shapes(vec![Box::new(Conv2D::new(4, (3, 3)))]), DenseShape::new(5, 4, 4));
+    assert_eq!(shapes(vec![Box::new(Conv2D::new(4, (3, 3)).padded((1, 1)))]), DenseShape::new(7, 6, 4));
+    assert_eq!(shapes(vec![Box::new(Conv2D::new(2, (3, 2)).strided((2, 2)).dilated((2, 1)))]), DenseShape::new(2, 3, 2));
+    assert_eq!(shapes(vec![Box::new(MaxPool2D::new((2, 2)))]), DenseShape::new(3, 3, 3));
+    assert_eq!(shapes(vec![Box::new(AveragePool2D::new((3, 2)).strided((1, 2)))]), DenseShape::new(5, 3, 3));
+    assert_eq!(shapes(vec![Box::new(GlobalAveragePooling::new())]), DenseShape::new(1, 1, 3));
+
+    let invalid: Vec<Box<dyn Layer>> = vec![Box::new(Conv2D::new(1, (8, 1))), Box::new(Conv2D::new(1, (0, 1))),
+        Box::new(Conv2D::new(1, (3, 3)).dilated((4, 1))), Box::new(Conv2D::new(1, (1, 1)).strided((0, 1))),
+        Box::new(MaxPool2D::new((1, 7))), Box::new(AveragePool2D::new((2, 2)).strided((0, 0)))];
+
+    for layer in invalid {
+        let tag: String = layer.tag();
+        let result = Sequential::with_input_shape(DenseShape::new(7, 6, 3), vec![layer], Box::new(DenseLosses::MeanSquaredError), None);
+        assert!(matches!(result, Err(NnError::ShapeMismatch {..})), "{tag}");
+    }
+}
+
+#[test]
+fn layers_compute_known_values() {
+    // a 3 * 3 sample with 2 channels
+    let mut input = Matrix::new(1, 18);
+    input.values = (1..=18).map(|i| i as f64).collect();
+
+    let mut conv = Sequential::with_input_shape(DenseShape::new(3, 3, 2), vec![Box::new(Conv2D::new(1, (2, 2)))],
+        Box::new(DenseLosses::MeanSquaredError), None).unwrap();
+
+    // the kernel sums the top left value of the first channel and the bottom right one of the second
+    let mut weights = Matrix::new(8, 1);
+    weights.values = vec![1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0];
+    conv.set_parameters(&[weights, Matrix::vec_to_col_mat(&[0.5])]).unwrap();
+
+    conv.feed_forward(&input).unwrap();
+    assert_eq!(conv.result().values, vec![15.5, 17.5, 21.5, 23.5]);
+
+    let pool = |layer: Box<dyn Layer>| -> Vec<f64> {
+        let mut model = Sequential::with_input_shape(DenseShape::new(3, 3, 2), vec![layer], Box::new(DenseLosses::MeanSquaredError), None).unwrap();
+        model.feed_forward(&input).unwrap();
+        model.result().values
+    };
+
+    assert_eq!(pool(Box::new(MaxPool2D::new((2, 2)).strided((1, 1)))), vec![5.0, 6.0, 8.0, 9.0, 14.0, 15.0, 17.0, 18.0]);
+    assert_eq!(pool(Box::new(AveragePool2D::new((3, 1)))), vec![2.0, 5.0, 8.0, 11.0, 14.0, 17.0]);
+    assert_eq!(pool(Box::new(GlobalAveragePooling::new())), vec![5.0, 14.0]);
+}
+
+#[test]
+fn max_pooling_back_propagates_the_batch_of_its_forward() {
+    let mut input = Matrix::new(1, 18);
+    input.values = (1..=18).map(|i| i as f64).collect();
+
+    let mut rng = new_rng(None);
+    let mut pool = MaxPool2D::new((2, 2)).strided((1, 1));
+    pool.build(&DenseShape::new(3, 3, 2), &mut rng).unwrap();
+    pool.forward(&input, true, &mut rng).unwrap();
+
+    assert!(matches!(pool.backward(&Matrix::new(2, 8)), Err(NnError::ShapeMismatch {..})));
+    assert!(matches!(pool.backward(&Matrix::new(1, 4)), Err(NnError::ShapeMismatch {..})));
+
+    let mut gradient = Matrix::new(1, 8);
+    gradient.values = vec![1.0; 8];
+    let input_gradient = pool.backward(&gradient).unwrap();
+    assert_eq!(input_gradient.values.iter().sum::<f64>(), 8.0);
+    assert_eq!(input_gradient.values[8], 1.0);
+}
+
+#[test]
+fn session_trains_an_image_classifier() {
+    // 4 * 4 grayscale images of a vertical (first class) or a horizontal (second class) line
+    let mut images: Vec<Vec<f64>> = Vec::new();
+    let mut classes: Vec<Vec<f64>> = Vec::new();
+
+    for line in 0..4 {
+        for (vertical, class) in [(true, [1.0, 0.0]), (false, [0.0, 1.0])] {
+            images.push((0..16).map(|i| if (vertical && i % 4 == line) || (!vertical && i / 4 == line) {1.0} else {0.0}).collect());
+            classes.push(class.to_vec());
+        }
+    }
+
+    let files = TempFiles::new("convolution_session");
+    let mut session = common::session(&files, DenseShape::new(4, 4, 1), &images, DenseShape::new(2, 1, 1), &classes, 200, 0.02);
+    session.set_seed(3);
+    session.batch_size = 8;
+    session.log_interval = 1000;
+    session.optimizer = Box::new(Adam::default());
+
+    let layers: Vec<Box<dyn Layer>> = vec![Box::new(Conv2D::new(4, (2, 2)).padded((1, 1))), activation(DenseActivation::Relu),
+        Box::new(MaxPool2D::new((2, 2))), Box::new(Flatten::new())];
+    let mut model = common::classifier(DenseShape::new(4, 4, 1), layers, 2, 3);
+
+    session.train(&mut model).unwrap();
+    let (loss, _) = session.evaluate(&mut model, &session.dataset).unwrap();
+    let predictions = session.predict(&mut model, &session.dataset).unwrap();
+
+    assert!(loss < 0.1, "{loss}");
+    // the training shuffled the dataset
+    for (s, sample) in session.dataset.iter().enumerate() {
+        let expected: usize = if sample.output.values[0] == 1.0 {0} else {1};
+        assert!(predictions.get(expected, s) > 0.5, "sample {s}");
+    }
+}
+
+#[test]
+fn convolution_models_are_saved() {
+    let (input, _) = batch();
+    let mut model = classifier(Box::new(MaxPool2D::new((2, 2)).strided((1, 1))), false, 9);
+
+    let files = TempFiles::new("convolution_model");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+
+    let mut loaded = Sequential::load_model(&filename).unwrap();
+    assert_eq!(loaded.input_shape(), DenseShape::new(5, 5, 2));
+
+    let tags: Vec<String> = loaded.layers().iter().map(|layer| layer.tag()).collect();
+    assert_eq!(tags[0], "Conv2D 3 3 2 1 1 1 1 1 2 L2(0.01)");
+    assert_eq!(tags[2], "MaxPool2D 2 2 1 1");
+    assert_eq!(tags[3], "Conv2D 2 2 2 1 2 0 0 1 1 NoRegularizer");
+
+    model.feed_forward(&input).unwrap();
+    loaded.feed_forward(&input).unwrap();
+    assert_eq!(model.result().values, loaded.result().values);
+
+    let registry = rusty_nn::models::registry::Registry::new();
+    assert_eq!(registry.layer("AveragePool2D 2 3 1 1").unwrap().tag(), "AveragePool2D 2 3 1 1");
+    assert_eq!(registry.layer("GlobalAveragePooling").unwrap().tag(), "GlobalAveragePooling");
+    assert!(registry.layer("MaxPool2D 2 2").is_err());
+    assert!(registry.layer("Conv2D 1 2 2 1 1 0 0 1 1 Unknown").is_err());
+}
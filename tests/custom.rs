@@ -0,0 +1,124 @@
+use rusty_nn::activations::activation::Activation;
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::errors::nn_error::{NnError, NnResult};
+use rusty_nn::losses::loss::Loss;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::models::gradient_check::gradient_check;
+use rusty_nn::models::registry::Registry;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::TempFiles;
+
+// scale * tanh(x)
+struct ScaledTanh {
+    scale: f64
+}
+
+impl Activation for ScaledTanh {
+    fn name(&self) -> String {
+        format!("ScaledTanh({})", self.scale)
+    }
+
+    fn forward(&self, mat: &mut Matrix, _parameters: &Matrix) -> NnResult<()> {
+        for value in mat.values.iter_mut() {
+            *value = self.scale * value.tanh();
+        }
+        Ok(())
+    }
+
+    fn derivative(&self, x: f64) -> NnResult<f64> {
+        Ok(self.scale * (1.0 - x.tanh().powi(2)))
+    }
+}
+
+// sum of the quartic differences of each sample
+struct QuarticError;
+
+impl Loss for QuarticError {
+    fn name(&self) -> String {
+        "QuarticError".to_string()
+    }
+
+    fn error(&self, values: &Matrix, desired_output: &Matrix) -> NnResult<f64> {
+        let sum: f64 = values.values.iter().zip(desired_output.values.iter()).map(|(v, d)| (v - d).powi(4)).sum();
+        Ok(sum / values.x_length as f64)
+    }
+
+    fn gradient(&self, values: &Matrix, desired_output: &Matrix) -> NnResult<Matrix> {
+        let mut gradient = values.copy();
+
+        for (g, d) in gradient.values.iter_mut().zip(desired_output.values.iter()) {
+            *g = 4.0 * (*g - d).powi(3);
+        }
+        Ok(gradient)
+    }
+}
+
+fn registry() -> Registry {
+    let mut registry = Registry::new();
+
+    registry.register_activation("ScaledTanh", |name| {
+        name.strip_prefix("ScaledTanh(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .and_then(|scale| scale.parse::<f64>().ok())
+            .map(|scale| Box::new(ScaledTanh {scale}) as Box<dyn Activation>)
+            .ok_or(NnError::UnsupportedActivation(name.to_string()))
+    });
+    registry.register_loss("QuarticError", |_| Ok(Box::new(QuarticError)));
+
+    registry
+}
+
+fn model() -> DenseModel {
+    let activations: Vec<Box<dyn Activation>> = vec![Box::new(ScaledTanh {scale: 1.5}), Box::new(DenseActivation::Sigmoid)];
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(4, 1, 1), DenseShape::new(2, 1, 1)];
+
+    DenseModel::new_custom(activations, Box::new(QuarticError), shapes, Some(7)).unwrap()
+}
+
+fn batch() -> (Matrix, Matrix) {
+    common::columns(&[vec![0.5, -0.2, 0.9], vec![-0.7, 0.3, 0.1]], &[vec![0.9, 0.1], vec![0.2, 0.6]])
+}
+
+#[test]
+fn custom_activation_and_loss_gradients() {
+    let (input, output) = batch();
+    let mut model = model();
+
+    for (l, layer) in gradient_check(&mut model, &input, &output, 1e-5).unwrap().iter().enumerate() {
+        assert!(layer.max_error() < 1e-4, "layer {l}: {}", layer.max_error());
+    }
+}
+
+#[test]
+fn custom_names_are_resolved_by_the_registry() {
+    let (input, _) = batch();
+    let model = model();
+
+    let files = TempFiles::new("custom_model");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+
+    assert!(matches!(DenseModel::load_model(&filename), Err(NnError::UnsupportedActivation(_))));
+
+    let mut model = model;
+    let mut loaded = DenseModel::load_model_with(&filename, &registry()).unwrap();
+
+    model.feed_forward(&input).unwrap();
+    loaded.feed_forward(&input).unwrap();
+    assert_eq!(model.result().values, loaded.result().values);
+    assert_eq!(loaded.loss().name(), "QuarticError");
+}
+
+#[test]
+fn built_in_names_need_no_registration() {
+    let registry = Registry::new();
+
+    assert_eq!(registry.activation("LeakyRelu(0.2)").unwrap().name(), "LeakyRelu(0.2)");
+    assert_eq!(registry.loss("MeanSquaredError").unwrap().name(), "MeanSquaredError");
+    assert!(registry.activation("ScaledTanh(2)").is_err());
+    assert!(registry.loss("QuarticError").is_err());
+}
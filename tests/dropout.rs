@@ -0,0 +1,192 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::initializers::dense_initializer::DenseInitializer;
+use rusty_nn::layers::dropout_layer::Dropout;
+use rusty_nn::layers::layer::Layer;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::maths::random::new_rng;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::models::gradient_check::gradient_check;
+use rusty_nn::models::sequential::Sequential;
+use rusty_nn::regularizers::dense_dropout::DenseDropout;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::TempFiles;
+
+fn model(dropout: DenseDropout) -> DenseModel {
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(8, 1, 1), DenseShape::new(2, 1, 1)];
+    let mut model = DenseModel::new_seeded(vec![DenseActivation::Tanh, DenseActivation::Sigmoid],
+        DenseLosses::BinaryCrossEntropy, shapes, Some(11)).unwrap();
+    model.set_dropout(0, dropout).unwrap();
+    model
+}
+
+fn sample() -> (Matrix, Matrix) {
+    (Matrix::vec_to_col_mat(&[0.5, -1.0, 0.3]), Matrix::vec_to_col_mat(&[1.0, 0.0]))
+}
+
+fn mean_and_variance(values: &[f64]) -> (f64, f64) {
+    let mean: f64 = values.iter().sum::<f64>() / values.len() as f64;
+    (mean, values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64)
+}
+
+#[test]
+fn inference_ignores_dropout() {
+    let (input, _) = sample();
+    let mut plain = model(DenseDropout::NoDropout);
+    let mut dropped = model(DenseDropout::Dropout(0.5));
+
+    plain.feed_forward(&input).unwrap();
+    dropped.feed_forward(&input).unwrap();
+    assert!(!dropped.is_training());
+    assert_eq!(plain.result().values, dropped.result().values);
+
+    dropped.set_training(true);
+    let outputs: Vec<Vec<f64>> = (0..5).map(|_| {
+        dropped.feed_forward(&input).unwrap();
+        dropped.result().values
+    }).collect();
+    assert!(outputs.iter().any(|output| *output != plain.result().values));
+}
+
+#[test]
+fn inverted_dropout_keeps_the_mean() {
+    let mut values = Matrix::new(1, 10000);
+    values.values.iter_mut().for_each(|x| *x = 0.7);
+
+    let (dropped, mask) = DenseDropout::Dropout(0.25).apply(&values, &mut new_rng(Some(3)));
+
+    for ((d, m), x) in dropped.values.iter().zip(mask.values.iter()).zip(values.values.iter()) {
+        assert!(*m == 0.0 || (*m - 1.0 / 0.75).abs() < 1e-12);
+        assert!((d - m * x).abs() < 1e-12);
+    }
+
+    let (mean, _) = mean_and_variance(&dropped.values);
+    assert!((mean - 0.7).abs() < 0.02, "{mean}");
+}
+
+#[test]
+fn alpha_dropout_keeps_mean_and_variance() {
+    // standard normal values, as the outputs of a self-normalizing layer
+    let mut values = Matrix::new(1, 20000);
+    DenseInitializer::XavierNormal.initialize(&mut values, 1, 1, &mut new_rng(Some(4)));
+
+    let (dropped, _) = DenseDropout::AlphaDropout(0.2).apply(&values, &mut new_rng(Some(5)));
+    let (mean, variance) = mean_and_variance(&dropped.values);
+
+    assert!(mean.abs() < 0.03, "{mean}");
+    assert!((variance - 1.0).abs() < 0.05, "{variance}");
+}
+
+#[test]
+fn back_propagation_respects_the_mask() {
+    let (input, output) = sample();
+
+    for dropout in [DenseDropout::Dropout(0.5), DenseDropout::AlphaDropout(0.5)] {
+        let mut model = model(dropout);
+        model.set_training(true);
+
+        model.feed_forward(&input).unwrap();
+        let deltas = model.back_propagate(&output).unwrap();
+        let gradients = model.gradients(&deltas).unwrap();
+
+        // a dropped hidden neuron gets no gradient, whatever it sent to the output layer
+        let dropped: Vec<bool> = deltas[1].values.iter().map(|delta| *delta == 0.0).collect();
+        assert!(dropped.iter().any(|d| *d) && dropped.iter().any(|d| !*d));
+
+        for (neuron, is_dropped) in dropped.iter().enumerate() {
+            let row = &gradients[0].values[neuron * 3..(neuron + 1) * 3];
+            assert_eq!(row.iter().all(|g| *g == 0.0), *is_dropped);
+            assert_eq!(gradients[1].values[neuron] == 0.0, *is_dropped);
+        }
+
+        // with inverted dropout, the output layer sees nothing from the dropped neurons
+        if let DenseDropout::Dropout(_) = dropout {
+            for (neuron, is_dropped) in dropped.iter().enumerate() {
+                let column: Vec<f64> = (0..2).map(|y| gradients[2].values[y * 8 + neuron]).collect();
+                assert_eq!(column.iter().all(|g| *g == 0.0), *is_dropped);
+            }
+        }
+    }
+}
+
+#[test]
+fn gradient_check_runs_without_dropout() {
+    let (input, output) = sample();
+    let mut model = model(DenseDropout::Dropout(0.5));
+    model.set_training(true);
+
+    for check in gradient_check(&mut model, &input, &output, 1e-5).unwrap() {
+        assert!(check.max_error() < 1e-4);
+    }
+    assert!(model.is_training());
+}
+
+#[test]
+fn dropouts_are_saved() {
+    for dropout in [DenseDropout::NoDropout, DenseDropout::Dropout(0.3), DenseDropout::AlphaDropout(0.05)] {
+        assert_eq!(dropout.to_string().parse::<DenseDropout>().unwrap(), dropout);
+    }
+    assert!("Dropout(1)".parse::<DenseDropout>().is_err());
+    assert!("Dropout(-0.1)".parse::<DenseDropout>().is_err());
+
+    let mut model = model(DenseDropout::AlphaDropout(0.1));
+    assert!(model.set_dropout(1, DenseDropout::Dropout(0.5)).is_err());
+
+    // as are the rates that FromStr rejects, leaving the model unchanged
+    for rate in [1.0, -0.1, f64::NAN] {
+        for dropout in [DenseDropout::Dropout(rate), DenseDropout::AlphaDropout(rate)] {
+            assert!(matches!(model.set_dropout(0, dropout), Err(NnError::UnsupportedRegularizer(_))), "{dropout}");
+        }
+    }
+    assert_eq!(model.dropouts(), &[DenseDropout::AlphaDropout(0.1)]);
+
+    let layers: Vec<Box<dyn Layer>> = vec![Box::new(Dropout::new(DenseDropout::Dropout(1.0)))];
+    assert!(matches!(Sequential::new(3, layers, Box::new(DenseLosses::MeanSquaredError), None),
+        Err(NnError::UnsupportedRegularizer(_))));
+
+    let files = TempFiles::new("dropout_model");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+
+    let loaded = DenseModel::load_model(&filename).unwrap();
+    assert_eq!(loaded.dropouts(), model.dropouts());
+    assert!(!loaded.is_training());
+}
+
+#[test]
+fn resumed_trainings_draw_the_same_masks() {
+    let files = TempFiles::new("dropout_resume");
+    let checkpoint = files.path("checkpoint");
+    let inputs: Vec<Vec<f64>> = vec![vec![0.5, -1.0, 0.3], vec![-0.2, 0.8, 0.1], vec![0.9, 0.4, -0.6]];
+    let outputs: Vec<Vec<f64>> = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]];
+
+    let session = common::session(&files, DenseShape::new(3, 1, 1), &inputs, DenseShape::new(2, 1, 1), &outputs, 3, 0.1);
+    session.save_checkpoint(&model(DenseDropout::Dropout(0.5)), &checkpoint).unwrap();
+
+    let resume = |seed: u64| -> Vec<Vec<f64>> {
+        let mut session = common::session(&files, DenseShape::new(3, 1, 1), &inputs, DenseShape::new(2, 1, 1), &outputs, 3, 0.1);
+        session.set_seed(seed);
+
+        let mut model: DenseModel = session.load_checkpoint(&checkpoint).unwrap();
+        session.train(&mut model).unwrap();
+        model.copy_parameters().iter().map(|p| p.values.clone()).collect()
+    };
+
+    assert_eq!(resume(4), resume(4));
+    assert_ne!(resume(4), resume(5));
+
+    // a model loaded outside of a session is reseeded explicitly
+    let (input, _) = sample();
+    let masks = |seed: u64| -> Vec<f64> {
+        let mut loaded = DenseModel::load_model(&checkpoint).unwrap();
+        loaded.set_seed(seed);
+        loaded.set_training(true);
+        loaded.feed_forward(&input).unwrap();
+        loaded.result().values
+    };
+    assert_eq!(masks(2), masks(2));
+}
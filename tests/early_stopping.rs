@@ -0,0 +1,214 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::data::create_data::Sample;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::metrics::metric::Metric;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::normalizations::dense_normalization::DenseNormalization;
+use rusty_nn::schedules::lr_schedule::LrSchedule;
+use rusty_nn::sessions::early_stopping::{EarlyStopping, Mode};
+use rusty_nn::sessions::session::Session;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+mod common;
+
+use common::TempFiles;
+
+fn parameters(values: f64) -> Vec<Matrix> {
+    vec![Matrix::vec_to_col_mat(&[values])]
+}
+
+#[test]
+fn patience_counts_the_epochs_without_improvement() {
+    let mut early_stopping = EarlyStopping::new(2, 0.0, false);
+
+    // stops at the second epoch in a row without improvement
+    assert!(!early_stopping.observe(0, 1.0, || parameters(0.0)));
+    assert!(!early_stopping.observe(1, 1.5, || parameters(1.0)));
+    assert!(early_stopping.observe(2, 1.0, || parameters(2.0)));
+
+    assert_eq!(early_stopping.best_epoch(), 0);
+    assert_eq!(early_stopping.best_value(), 1.0);
+
+    // the parameters are only kept to be restored
+    assert!(early_stopping.take_best_parameters().is_none());
+
+    // an improvement starts the count again
+    early_stopping.reset();
+    for (epoch, loss) in [1.0, 1.1, 0.9, 1.1].iter().enumerate() {
+        assert!(!early_stopping.observe(epoch, *loss, || parameters(0.0)));
+    }
+    assert!(early_stopping.observe(4, 1.1, || parameters(0.0)));
+    assert_eq!(early_stopping.best_epoch(), 2);
+}
+
+#[test]
+fn improvements_must_exceed_min_delta() {
+    let mut early_stopping = EarlyStopping::new(2, 0.1, true);
+
+    assert!(!early_stopping.observe(0, 1.0, || parameters(0.0)));
+    assert!(!early_stopping.observe(1, 0.95, || parameters(1.0)));
+    assert!(!early_stopping.observe(2, 0.85, || parameters(2.0)));
+    assert!(!early_stopping.observe(3, 0.8, || parameters(3.0)));
+    assert!(early_stopping.observe(4, 0.76, || parameters(4.0)));
+
+    assert_eq!(early_stopping.best_epoch(), 2);
+    assert_eq!(early_stopping.best_value(), 0.85);
+    assert_eq!(early_stopping.take_best_parameters().unwrap()[0].values, vec![2.0]);
+}
+
+#[test]
+fn monitored_metrics_can_be_maximized() {
+    let mut early_stopping = EarlyStopping::new(2, 0.1, false).monitor(Metric::Accuracy, Mode::Max);
+
+    assert!(!early_stopping.observe(0, 0.5, || parameters(0.0)));
+    assert!(!early_stopping.observe(1, 0.75, || parameters(1.0)));
+    assert!(!early_stopping.observe(2, 0.5, || parameters(2.0)));
+    assert!(early_stopping.observe(3, 0.8, || parameters(3.0)));
+
+    assert_eq!(early_stopping.best_epoch(), 1);
+    assert_eq!(early_stopping.best_value(), 0.75);
+
+    early_stopping.reset();
+    assert!(!early_stopping.observe(0, 0.0, || parameters(0.0)));
+    assert_eq!(early_stopping.best_value(), 0.0);
+}
+
+// four samples of two classes, trained on full batches so that the batch normalization is active
+fn session(files: &TempFiles, outputs: &[Vec<f64>], nb_epochs: usize) -> Session {
+    let inputs: Vec<Vec<f64>> = vec![vec![0.5, -0.2], vec![-0.7, 0.3], vec![0.1, 0.9], vec![-0.4, -0.6]];
+    let mut session = common::session(files, DenseShape::new(2, 1, 1), &inputs, DenseShape::new(1, 1, 1), outputs, nb_epochs, 0.5);
+    session.batch_size = 4;
+    session.set_seed(6);
+    session
+}
+
+fn normalized_model() -> DenseModel {
+    let shapes = vec![DenseShape::new(2, 1, 1), DenseShape::new(4, 1, 1), DenseShape::new(1, 1, 1)];
+    let mut model = DenseModel::new_seeded(vec![DenseActivation::Tanh, DenseActivation::Sigmoid],
+        DenseLosses::BinaryCrossEntropy, shapes, Some(3)).unwrap();
+    model.set_normalization(0, DenseNormalization::BatchNorm(0.5)).unwrap();
+    model
+}
+
+fn values(matrices: &[Matrix]) -> Vec<Vec<f64>> {
+    matrices.iter().map(|m| m.values.clone()).collect()
+}
+
+#[test]
+fn sessions_restore_the_best_epoch() {
+    let files = TempFiles::new("early_stopping_restore");
+    let outputs: Vec<Vec<f64>> = vec![vec![1.0], vec![0.0], vec![1.0], vec![0.0]];
+
+    // the validation set has the opposite labels, so its loss rises while the model learns
+    // (a new session each time, as the training shuffles the dataset in place)
+    let train = |nb_epochs: usize, early_stopping: Option<EarlyStopping>| {
+        let mut session = session(&files, &outputs, nb_epochs);
+        session.validation = session.dataset.iter()
+            .map(|sample| Sample::new(sample.input.values.clone(), vec![1.0 - sample.output.values[0]]))
+            .collect();
+        session.early_stopping = early_stopping;
+
+        let mut model = normalized_model();
+        session.train(&mut model).unwrap();
+        (session, model)
+    };
+
+    let (session, mut model) = train(20, Some(EarlyStopping::new(2, 0.0, true)));
+
+    let best_epoch = session.early_stopping.as_ref().unwrap().best_epoch();
+    let best_loss = session.early_stopping.as_ref().unwrap().best_value();
+    assert!(best_epoch + 3 < 20);
+    assert_eq!(session.evaluate(&mut model, &session.validation).unwrap().0, best_loss);
+
+    // the same training stopped right after the best epoch
+    let (_, best) = train(best_epoch + 1, None);
+
+    assert_eq!(values(&model.copy_parameters()), values(&best.copy_parameters()));
+    assert_eq!(values(&model.running_statistics()), values(&best.running_statistics()));
+    assert!(model.running_statistics()[0].values.iter().any(|v| *v != 0.0 && *v != 1.0));
+
+    // the same with a metric of the validation set
+    let (mut session, mut model) = train(20, Some(EarlyStopping::new(2, 0.0, true).monitor(Metric::MeanAbsoluteError, Mode::Min)));
+    let early_stopping = session.early_stopping.as_ref().unwrap();
+    let best_value = early_stopping.best_value();
+    assert!(early_stopping.best_epoch() + 3 < 20);
+
+    session.metrics = vec![Metric::MeanAbsoluteError];
+    assert_eq!(session.evaluate(&mut model, &session.validation).unwrap().1, vec![best_value]);
+
+    // without restoring, the parameters are those of the last epoch
+    let (_, last) = train(20, Some(EarlyStopping::new(2, 0.0, false)));
+    assert_ne!(values(&last.copy_parameters()), values(&best.copy_parameters()));
+}
+
+// keeps the base learning rate for the first epochs then stops the learning,
+// and records the loss of every epoch
+struct Recording {
+    nb_learning_epochs: usize,
+    losses: Rc<RefCell<Vec<f64>>>
+}
+
+impl LrSchedule for Recording {
+    fn learning_rate(&mut self, epoch: usize, base_learning_rate: f64) -> f64 {
+        if epoch < self.nb_learning_epochs {base_learning_rate} else {0.0}
+    }
+
+    fn observe_loss(&mut self, avg_loss: f64) {
+        self.losses.borrow_mut().push(avg_loss);
+    }
+}
+
+#[test]
+fn sessions_stop_once_the_loss_stalls() {
+    let files = TempFiles::new("early_stopping_stall");
+    let outputs: Vec<Vec<f64>> = vec![vec![1.0], vec![0.0], vec![1.0], vec![0.0]];
+
+    let train = |early_stopping: Option<EarlyStopping>| {
+        let losses = Rc::new(RefCell::new(Vec::new()));
+        let mut session = session(&files, &outputs, 20);
+        session.lr_schedule = Box::new(Recording {nb_learning_epochs: 3, losses: losses.clone()});
+        session.early_stopping = early_stopping;
+
+        session.train(&mut normalized_model()).unwrap();
+        let losses = losses.borrow().clone();
+        (session, losses)
+    };
+
+    // the loss falls during the first 4 epochs, then stays at the one of epoch 3
+    let (session, losses) = train(Some(EarlyStopping::new(2, 1e-9, false)));
+    assert!(losses[1] < losses[0] && losses[3] < losses[2]);
+    assert_eq!(session.early_stopping.as_ref().unwrap().best_epoch(), 3);
+    assert_eq!(losses.len(), 6);
+
+    // as does the mean absolute error of the outputs seen during each epoch
+    let monitor = EarlyStopping::new(2, 1e-9, false).monitor(Metric::MeanAbsoluteError, Mode::Min);
+    let (session, metric_losses) = train(Some(monitor));
+    assert_eq!(metric_losses, losses);
+    assert_eq!(session.early_stopping.as_ref().unwrap().best_epoch(), 3);
+
+    assert_eq!(train(None).1.len(), 20);
+}
+
+#[test]
+fn sessions_stop_below_the_loss_threshold() {
+    let files = TempFiles::new("early_stopping_threshold");
+    let outputs: Vec<Vec<f64>> = vec![vec![1.0], vec![0.0], vec![1.0], vec![0.0]];
+
+    let train = |nb_epochs: usize, loss_threshold: f64, stop_on_loss_threshold: bool| {
+        let mut session = session(&files, &outputs, nb_epochs);
+        session.loss_threshold = loss_threshold;
+        session.stop_on_loss_threshold = stop_on_loss_threshold;
+
+        let mut model = normalized_model();
+        session.train(&mut model).unwrap();
+        values(&model.copy_parameters())
+    };
+
+    // every loss is below the threshold, so the training stops after the first epoch
+    assert_eq!(train(10, 100.0, true), train(1, 0.0, false));
+    assert_ne!(train(10, 100.0, false), train(1, 0.0, false));
+}
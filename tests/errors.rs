@@ -0,0 +1,119 @@
+use rusty_nn::activations::dense_activation::{apply_activation, DenseActivation};
+use rusty_nn::data::create_data::load_data;
+use rusty_nn::derivations::dense_derivation::apply_derivation;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::losses::dense_losses::{calculate_error, derivative_error, DenseLosses};
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::TempFiles;
+
+#[test]
+fn matrix_operations_check_their_shapes() {
+    let a = Matrix::new(3, 2);
+    let b = Matrix::new(2, 3);
+
+    assert!(matches!(Matrix::try_dot(&a, &a),
+        Err(NnError::ShapeMismatch { operation: "Matrix::dot", left: (2, 3), right: (2, 3) })));
+    assert!(matches!(Matrix::try_add(&a, &b),
+        Err(NnError::ShapeMismatch { operation: "Matrix::add", left: (2, 3), right: (3, 2) })));
+
+    let product = Matrix::try_dot(&a, &b).unwrap();
+    assert_eq!((product.y_length, product.x_length), (2, 2));
+    assert_eq!(Matrix::try_add(&a, &a).unwrap().values, vec![0.0; 6]);
+}
+
+#[test]
+fn matrix_indices_are_checked() {
+    let mut mat = Matrix::new(3, 2);
+
+    assert!(matches!(mat.try_get(2, 0),
+        Err(NnError::IndexOutOfRange { operation: "Matrix::get", y: 2, x: 0, y_length: 2, x_length: 3 })));
+    assert!(matches!(mat.try_set(0, 3, 1.0),
+        Err(NnError::IndexOutOfRange { operation: "Matrix::set", y: 0, x: 3, .. })));
+
+    mat.try_set(1, 2, 4.0).unwrap();
+    assert_eq!(mat.try_get(1, 2).unwrap(), 4.0);
+    assert_eq!(mat.values[5], 4.0);
+}
+
+#[test]
+fn unsupported_functions_are_errors() {
+    let mut mat = Matrix::new(1, 2);
+
+    assert!(matches!(apply_activation(&DenseActivation::NoActivation, &mut mat), Err(NnError::UnsupportedActivation(_))));
+    assert!(matches!(apply_derivation(&DenseActivation::NoActivation, 0.5), Err(NnError::UnsupportedActivation(_))));
+    assert!(matches!(calculate_error(&DenseLosses::NoLoss, &mat, &mat), Err(NnError::UnsupportedLoss(_))));
+    assert!(matches!(derivative_error(&DenseLosses::NoLoss, 2, 0.5, 1.0), Err(NnError::UnsupportedLoss(_))));
+    assert!(matches!(calculate_error(&DenseLosses::MeanSquaredError, &mat, &Matrix::new(1, 3)), Err(NnError::ShapeMismatch { .. })));
+
+    assert!(matches!("Sigmoid2".parse::<DenseActivation>(), Err(NnError::UnsupportedActivation(name)) if name == "Sigmoid2"));
+    assert!(matches!("Triplet".parse::<DenseLosses>(), Err(NnError::UnsupportedLoss(_))));
+}
+
+#[test]
+fn models_check_the_shape_of_their_samples() {
+    let shapes = vec![DenseShape::new(2, 1, 1), DenseShape::new(3, 1, 1), DenseShape::new(1, 1, 1)];
+    let mut model = DenseModel::new(vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid], DenseLosses::MeanSquaredError, shapes);
+
+    assert!(matches!(model.feed_forward(&Matrix::new(1, 3)), Err(NnError::ShapeMismatch { .. })));
+
+    model.feed_forward(&Matrix::new(1, 2)).unwrap();
+    assert!(matches!(model.back_propagate(&Matrix::new(1, 2)), Err(NnError::ShapeMismatch { .. })));
+}
+
+#[test]
+fn data_files_report_their_errors() {
+    let files = TempFiles::new("errors_data");
+    let write = |file: &str, content: &str| -> String {
+        let path = files.path(file);
+        std::fs::write(&path, content).unwrap();
+        path
+    };
+
+    let inputs = write("inputs.txt", "2 2 1 1\n0.5 1\n-1 0\n");
+    let outputs = write("outputs.txt", "2 1 1 1\n1\n0\n");
+    assert_eq!(load_data(&inputs, &outputs).unwrap().len(), 2);
+
+    let missing = files.path("missing.txt");
+    assert!(matches!(load_data(&missing, &outputs), Err(NnError::Io { file, .. }) if file == missing));
+
+    let bad_options = write("options.txt", "2 two 1 1\n0.5 1\n-1 0\n");
+    assert!(matches!(load_data(&bad_options, &outputs), Err(NnError::Parse { line: 1, .. })));
+
+    let bad_value = write("values.txt", "2 2 1 1\n0.5 1\n-1 zero\n");
+    assert!(matches!(load_data(&bad_value, &outputs), Err(NnError::Parse { line: 3, .. })));
+
+    let fewer = write("fewer.txt", "1 1 1 1\n1\n");
+    assert!(matches!(load_data(&inputs, &fewer), Err(NnError::InvalidDataset(_))));
+}
+
+#[test]
+fn saved_models_report_their_errors() {
+    let files = TempFiles::new("errors_model");
+    let filename = files.path("model");
+
+    assert!(matches!(DenseModel::load_model(&filename), Err(NnError::Io { .. })));
+
+    let load = |architecture: &str, weights: &str| {
+        std::fs::write(format!("{filename}.arch"), architecture).unwrap();
+        std::fs::write(format!("{filename}.wab"), weights).unwrap();
+        DenseModel::load_model(&filename)
+    };
+
+    assert!(matches!(load("1\n2\nSigmoid\nMeanSquaredError\n", ""), Err(NnError::Parse { line: 1, .. })));
+    assert!(matches!(load("2\n2 1 3\nSigmoid\nMeanSquaredError\n", ""), Err(NnError::Parse { line: 2, .. })));
+    assert!(matches!(load("2\n2 1\nSigmoid Relu\nMeanSquaredError\n", ""), Err(NnError::Parse { line: 3, .. })));
+    assert!(matches!(load("2\n2 1\nSwash\nMeanSquaredError\n", ""), Err(NnError::UnsupportedActivation(_))));
+    assert!(matches!(load("2\n2 1\nSigmoid\n", ""), Err(NnError::Parse { line: 4, .. })));
+    assert!(matches!(load("2\n2 1\nSigmoid\nMeanSquaredError\n", "0.5\n"), Err(NnError::Parse { line: 1, .. })));
+    assert!(matches!(load("2\n2 1\nSigmoid\nMeanSquaredError\n", "0.5 1\n"), Err(NnError::Parse { line: 2, .. })));
+
+    let mut model = load("2\n2 1\nSigmoid\nMeanSquaredError\n", "0.5 1\n-2\n").unwrap();
+    model.feed_forward(&Matrix::vec_to_col_mat(&[1.0, 2.0])).unwrap();
+    // sigmoid(0.5 * 1 + 1 * 2 - 2)
+    assert_eq!(model.result().values, vec![1.0 / (1.0 + (-0.5f64).exp())]);
+}
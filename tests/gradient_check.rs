@@ -0,0 +1,177 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::models::gradient_check::gradient_check;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+const EPSILON: f64 = 1e-5;
+const TOLERANCE: f64 = 1e-4;
+
+fn losses() -> Vec<DenseLosses> {
+    vec![
+        DenseLosses::CategoricalCrossEntropy,
+        DenseLosses::BinaryCrossEntropy,
+        DenseLosses::MeanSquaredError,
+        DenseLosses::MeanAbsoluteError,
+        DenseLosses::Huber(0.1),
+        DenseLosses::LogCosh,
+        DenseLosses::Quantile(0.9),
+        DenseLosses::Poisson,
+        DenseLosses::MeanSquaredLogError,
+        DenseLosses::Hinge,
+        DenseLosses::SquaredHinge,
+        DenseLosses::Focal(2.0, 0.25),
+        DenseLosses::KlDivergence,
+        DenseLosses::LabelSmoothing(0.1)
+    ]
+}
+
+// the activation under test is used by the hidden layers, and by the output layer
+// whenever its outputs are valid for the loss (cross-entropies need outputs in (0, 1))
+fn model_for(activation: &DenseActivation, loss: &DenseLosses) -> DenseModel {
+    let hidden = |activation: &DenseActivation| match activation {
+        // softmax is an output layer only activation
+        DenseActivation::Softmax => DenseActivation::Tanh,
+        _ => activation.to_string().parse::<DenseActivation>().unwrap()
+    };
+
+    let output = match (activation, loss) {
+        (DenseActivation::Sigmoid | DenseActivation::Softmax, _) | (_, DenseLosses::MeanSquaredError) =>
+            activation.to_string().parse::<DenseActivation>().unwrap(),
+        _ => DenseActivation::Sigmoid
+    };
+
+    let shapes = vec![
+        DenseShape::new(3, 1, 1),
+        DenseShape::new(5, 1, 1),
+        DenseShape::new(4, 1, 1),
+        DenseShape::new(3, 1, 1)
+    ];
+
+    let loss = loss.to_string().parse::<DenseLosses>().unwrap();
+
+    DenseModel::new_seeded(vec![hidden(activation), hidden(activation), output], loss, shapes, Some(3)).unwrap()
+}
+
+fn batch() -> (Matrix, Matrix) {
+    common::columns(&[vec![0.5, -0.2, 0.9], vec![-0.7, 0.3, 0.1], vec![0.2, 0.8, -0.4], vec![0.0, -0.5, 0.6]],
+        &[vec![0.2, 0.7, 0.1], vec![0.6, 0.3, 0.1], vec![0.1, 0.1, 0.8], vec![0.3, 0.4, 0.3]])
+}
+
+fn check(activation: DenseActivation) {
+    let (input, output) = batch();
+
+    for loss in losses() {
+        let mut model = model_for(&activation, &loss);
+        let layers = gradient_check(&mut model, &input, &output, EPSILON).unwrap();
+
+        for (l, layer) in layers.iter().enumerate() {
+            assert!(layer.max_error() < TOLERANCE,
+                "{activation} x {loss}, layer {l}: weights {}, biases {}, activation {}, update {}",
+                layer.weights_error, layer.biases_error, layer.activation_error, layer.update_error);
+        }
+    }
+}
+
+#[test]
+fn sigmoid_gradients() {
+    check(DenseActivation::Sigmoid);
+}
+
+#[test]
+fn relu_gradients() {
+    check(DenseActivation::Relu);
+}
+
+#[test]
+fn leaky_relu_gradients() {
+    check(DenseActivation::LeakyRelu(0.01));
+    check(DenseActivation::LeakyRelu(0.3));
+}
+
+#[test]
+fn prelu_gradients() {
+    check(DenseActivation::PRelu);
+}
+
+#[test]
+fn softmax_gradients() {
+    check(DenseActivation::Softmax);
+}
+
+#[test]
+fn tanh_gradients() {
+    check(DenseActivation::Tanh);
+}
+
+#[test]
+fn gelu_gradients() {
+    check(DenseActivation::Gelu);
+}
+
+#[test]
+fn gelu_tanh_gradients() {
+    check(DenseActivation::GeluTanh);
+}
+
+#[test]
+fn elu_gradients() {
+    check(DenseActivation::Elu(1.0));
+    check(DenseActivation::Elu(0.5));
+}
+
+#[test]
+fn selu_gradients() {
+    check(DenseActivation::Selu);
+}
+
+#[test]
+fn swish_gradients() {
+    check(DenseActivation::Swish);
+}
+
+#[test]
+fn mish_gradients() {
+    check(DenseActivation::Mish);
+}
+
+#[test]
+fn softplus_gradients() {
+    check(DenseActivation::Softplus);
+}
+
+#[test]
+fn softsign_gradients() {
+    check(DenseActivation::Softsign);
+}
+
+#[test]
+fn hard_sigmoid_gradients() {
+    check(DenseActivation::HardSigmoid);
+}
+
+#[test]
+fn hard_tanh_gradients() {
+    check(DenseActivation::HardTanh);
+}
+
+#[test]
+fn identity_gradients() {
+    check(DenseActivation::Identity);
+}
+
+#[test]
+fn unsupported_activation_and_losses_are_errors() {
+    let (input, output) = batch();
+    let shapes = || vec![DenseShape::new(3, 1, 1), DenseShape::new(3, 1, 1)];
+
+    let mut model = DenseModel::new(vec![DenseActivation::NoActivation],
+        DenseLosses::MeanSquaredError, shapes());
+    assert!(gradient_check(&mut model, &input, &output, EPSILON).is_err());
+
+    let mut model = DenseModel::new(vec![DenseActivation::Sigmoid], DenseLosses::NoLoss, shapes());
+    assert!(gradient_check(&mut model, &input, &output, EPSILON).is_err());
+}
@@ -0,0 +1,149 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::initializers::dense_initializer::DenseInitializer;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::maths::random::new_rng;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::TempFiles;
+
+// the weights of a layer of 100 neurons with 200 inputs
+fn initialized(initializer: DenseInitializer) -> Matrix {
+    let mut weights = Matrix::new(200, 100);
+    initializer.initialize(&mut weights, 200, 100, &mut new_rng(Some(5)));
+    weights
+}
+
+fn mean_and_variance(values: &[f64]) -> (f64, f64) {
+    let mean: f64 = values.iter().sum::<f64>() / values.len() as f64;
+    (mean, values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64)
+}
+
+#[test]
+fn scaled_initializers_have_their_variance() {
+    // a uniform distribution over [-limit, limit] has a variance of limit^2 / 3, which is
+    // the variance of the matching normal distribution: 2 / (fan_in + fan_out), 2 / fan_in and 1 / fan_in
+    let cases: [(DenseInitializer, DenseInitializer, f64); 3] = [
+        (DenseInitializer::XavierUniform, DenseInitializer::XavierNormal, 2.0 / 300.0),
+        (DenseInitializer::HeUniform, DenseInitializer::HeNormal, 2.0 / 200.0),
+        (DenseInitializer::LeCunUniform, DenseInitializer::LeCunNormal, 1.0 / 200.0)
+    ];
+
+    for (uniform, normal, expected) in cases {
+        let limit: f64 = (3.0 * expected).sqrt();
+        let weights = initialized(uniform);
+        assert!(weights.values.iter().all(|x| x.abs() <= limit), "{uniform}");
+
+        // the values reach the bounds
+        assert!(weights.values.iter().any(|x| *x > 0.99 * limit) && weights.values.iter().any(|x| *x < -0.99 * limit));
+
+        for initializer in [uniform, normal] {
+            let (mean, variance) = mean_and_variance(&initialized(initializer).values);
+            assert!(mean.abs() < 0.05 * expected.sqrt(), "{initializer}: mean {mean}");
+            assert!((variance / expected - 1.0).abs() < 0.05, "{initializer}: variance {variance} instead of {expected}");
+        }
+    }
+
+    assert!(initialized(DenseInitializer::Uniform).values.iter().all(|x| (0.0..1.0).contains(x)));
+    assert!(initialized(DenseInitializer::Constant(0.3)).values.iter().all(|x| *x == 0.3));
+}
+
+fn assert_identity(mat: &Matrix) {
+    assert_eq!(mat.x_length, mat.y_length);
+
+    for y in 0..mat.y_length {
+        for x in 0..mat.x_length {
+            let expected: f64 = if x == y {1.0} else {0.0};
+            assert!((mat.get(y, x) - expected).abs() < 1e-12, "({y}, {x}): {}", mat.get(y, x));
+        }
+    }
+}
+
+#[test]
+fn orthogonal_weights_are_orthonormal() {
+    let mut rng = new_rng(Some(7));
+
+    // square and wide matrices have orthonormal rows, tall ones orthonormal columns
+    for (nb_rows, nb_columns) in [(5, 5), (3, 6), (6, 3)] {
+        let mut weights = Matrix::new(nb_columns, nb_rows);
+        DenseInitializer::Orthogonal.initialize(&mut weights, nb_columns, nb_rows, &mut rng);
+
+        if nb_rows <= nb_columns {
+            assert_identity(&Matrix::try_dot(&weights, &weights.transpose()).unwrap());
+        }
+        if nb_columns <= nb_rows {
+            assert_identity(&Matrix::try_dot(&weights.transpose(), &weights).unwrap());
+        }
+    }
+}
+
+#[test]
+fn initializers_are_saved() {
+    for initializer in [DenseInitializer::Uniform, DenseInitializer::XavierNormal, DenseInitializer::HeUniform,
+        DenseInitializer::LeCunNormal, DenseInitializer::Orthogonal, DenseInitializer::Zeros, DenseInitializer::Constant(-0.25)] {
+        assert_eq!(initializer.to_string().parse::<DenseInitializer>().unwrap(), initializer);
+    }
+    assert!("Constant(a)".parse::<DenseInitializer>().is_err());
+    assert!("Constant(1,2)".parse::<DenseInitializer>().is_err());
+    assert_eq!("Constant( 2 )".parse::<DenseInitializer>().unwrap(), DenseInitializer::Constant(2.0));
+    assert!("Glorot".parse::<DenseInitializer>().is_err());
+
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(4, 1, 1), DenseShape::new(2, 1, 1)];
+    let model = DenseModel::with_initializers(vec![DenseActivation::Relu, DenseActivation::Sigmoid],
+        DenseLosses::BinaryCrossEntropy, shapes,
+        vec![DenseInitializer::Orthogonal, DenseInitializer::LeCunUniform],
+        vec![DenseInitializer::Constant(0.1), DenseInitializer::Zeros], Some(3)).unwrap();
+
+    let files = TempFiles::new("initializers");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+
+    // lines 5 and 6 of the architecture hold the initializers of the weights and of the biases
+    let architecture = std::fs::read_to_string(format!("{filename}.arch")).unwrap();
+    let lines: Vec<&str> = architecture.lines().collect();
+    assert_eq!(lines[4], "Orthogonal LeCunUniform");
+    assert_eq!(lines[5], "Constant(0.1) Zeros");
+
+    let loaded = DenseModel::load_model(&filename).unwrap();
+    let copy = files.path("copy");
+    loaded.save(&copy).unwrap();
+    assert_eq!(std::fs::read_to_string(format!("{copy}.arch")).unwrap(), architecture);
+}
+
+#[test]
+fn saved_layer_lines_report_their_errors() {
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(4, 1, 1), DenseShape::new(2, 1, 1)];
+    let model = DenseModel::new_seeded(vec![DenseActivation::Relu, DenseActivation::Sigmoid],
+        DenseLosses::BinaryCrossEntropy, shapes, Some(3)).unwrap();
+
+    let files = TempFiles::new("initializers_lines");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+
+    let architecture = std::fs::read_to_string(format!("{filename}.arch")).unwrap();
+    let lines: Vec<&str> = architecture.lines().collect();
+    let load = |lines: &[&str]| {
+        std::fs::write(format!("{filename}.arch"), lines.join("\n") + "\n").unwrap();
+        DenseModel::load_model(&filename)
+    };
+
+    // a value that cannot be read is reported with its line
+    for line_nb in 5..=10 {
+        let mut edited = lines.clone();
+        edited[line_nb - 1] = "Unknown";
+        assert!(matches!(load(&edited), Err(NnError::Parse { line, .. }) if line == line_nb), "line {line_nb}");
+    }
+
+    // as is a truncated architecture
+    for nb_lines in 5..10 {
+        assert!(matches!(load(&lines[..nb_lines]), Err(NnError::Parse { line, .. }) if line == nb_lines + 1), "{nb_lines} lines");
+    }
+
+    // while an architecture that stops after the loss was saved before these lines existed
+    assert_eq!(load(&lines[..4]).unwrap().regularizers().len(), 2);
+    load(&lines).unwrap();
+}
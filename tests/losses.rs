@@ -0,0 +1,242 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::losses::dense_losses::{calculate_error, dense_targets, derivative_error, DenseLosses};
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::models::gradient_check::gradient_check;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+const EPSILON: f64 = 1e-6;
+
+fn regression_losses() -> Vec<DenseLosses> {
+    vec![
+        DenseLosses::MeanSquaredError,
+        DenseLosses::MeanAbsoluteError,
+        DenseLosses::Huber(1.0),
+        DenseLosses::Huber(0.25),
+        DenseLosses::LogCosh,
+        DenseLosses::Quantile(0.1),
+        DenseLosses::Quantile(0.5),
+        DenseLosses::Quantile(0.9),
+        DenseLosses::Poisson,
+        DenseLosses::MeanSquaredLogError
+    ]
+}
+
+fn error(loss: &DenseLosses, guess: &[f64], desired: &[f64]) -> f64 {
+    calculate_error(loss, &Matrix::vec_to_col_mat(guess), &Matrix::vec_to_col_mat(desired)).unwrap()
+}
+
+#[test]
+fn names_round_trip() {
+    let mut losses = regression_losses();
+    losses.extend([DenseLosses::NoLoss, DenseLosses::CategoricalCrossEntropy, DenseLosses::BinaryCrossEntropy]);
+
+    for loss in losses {
+        assert_eq!(loss.to_string().parse::<DenseLosses>().unwrap(), loss);
+    }
+
+    assert_eq!(DenseLosses::Huber(1.5).to_string(), "Huber(1.5)");
+
+    for name in ["Huber", "Huber(-1)", "Quantile(1)", "Quantile(0)", "Quantile(x)", "Unknown"] {
+        assert!(name.parse::<DenseLosses>().is_err(), "{name}");
+    }
+}
+
+#[test]
+fn derivatives_match_finite_differences() {
+    // no residual sits on the kinks of MAE, Huber and Quantile
+    let guess = [0.3, 1.7, 2.5, 0.05];
+    let desired = [0.9, 1.2, 0.0, 0.4];
+
+    for loss in regression_losses() {
+        for i in 0..guess.len() {
+            let (mut plus, mut minus) = (guess, guess);
+            plus[i] += EPSILON;
+            minus[i] -= EPSILON;
+
+            let numerical = (error(&loss, &plus, &desired) - error(&loss, &minus, &desired)) / (2.0 * EPSILON);
+            let analytic = derivative_error(&loss, guess.len(), guess[i], desired[i]).unwrap();
+
+            assert!((analytic - numerical).abs() < 1e-6,
+                "{loss}, output {i}: analytic {analytic}, numerical {numerical}");
+        }
+    }
+}
+
+#[test]
+fn known_values() {
+    let guess = [1.0, 3.0];
+    let desired = [2.0, 0.0];
+
+    let cases = [
+        (DenseLosses::MeanAbsoluteError, 2.0),
+        (DenseLosses::Huber(1.0), (0.5 + 2.5) / 2.0),
+        (DenseLosses::LogCosh, (1f64.cosh().ln() + 3f64.cosh().ln()) / 2.0),
+        // under-prediction by 1 and over-prediction by 3
+        (DenseLosses::Quantile(0.9), (0.9 + 0.1 * 3.0) / 2.0),
+        (DenseLosses::Poisson, (1.0 - 2.0 * 1f64.ln() + 3.0) / 2.0),
+        (DenseLosses::MeanSquaredLogError, ((2f64.ln() - 3f64.ln()).powi(2) + 4f64.ln().powi(2)) / 2.0)
+    ];
+
+    for (loss, expected) in cases {
+        let value = error(&loss, &guess, &desired);
+        assert!((value - expected).abs() < 1e-7, "{loss}: {value}, expected {expected}");
+    }
+}
+
+#[test]
+fn robust_losses_stay_finite_on_large_residuals() {
+    for loss in [DenseLosses::MeanAbsoluteError, DenseLosses::Huber(1.0), DenseLosses::LogCosh] {
+        let value = error(&loss, &[1000.0], &[-1000.0]);
+        assert!(value.is_finite() && value > 0.0, "{loss}: {value}");
+    }
+
+    assert!((error(&DenseLosses::LogCosh, &[1000.0], &[0.0]) - (1000.0 - 2f64.ln())).abs() < 1e-9);
+    assert!(error(&DenseLosses::Poisson, &[0.0], &[1.0]).is_finite());
+}
+
+fn classification_losses() -> Vec<DenseLosses> {
+    vec![
+        DenseLosses::CategoricalCrossEntropy,
+        DenseLosses::BinaryCrossEntropy,
+        DenseLosses::Hinge,
+        DenseLosses::SquaredHinge,
+        DenseLosses::Focal(2.0, 0.25),
+        DenseLosses::Focal(0.0, 0.5),
+        DenseLosses::KlDivergence,
+        DenseLosses::LabelSmoothing(0.1)
+    ]
+}
+
+#[test]
+fn classification_names_round_trip() {
+    let mut losses = classification_losses();
+    losses.push(DenseLosses::SparseCategoricalCrossEntropy);
+
+    for loss in losses {
+        assert_eq!(loss.to_string().parse::<DenseLosses>().unwrap(), loss);
+    }
+
+    assert_eq!(DenseLosses::Focal(2.0, 0.25).to_string(), "Focal(2,0.25)");
+
+    for name in ["Focal(2)", "Focal(2,1.5)", "Focal(-1,0.5)", "LabelSmoothing(1)"] {
+        assert!(name.parse::<DenseLosses>().is_err(), "{name}");
+    }
+}
+
+#[test]
+fn classification_derivatives_match_finite_differences() {
+    // probabilities away from 0 and 1, hinge margins away from 1
+    let guess = [0.2, 0.7, 0.45];
+    let desired = [0.0, 1.0, 0.3];
+
+    for loss in classification_losses() {
+        let targets = dense_targets(&loss, &Matrix::vec_to_col_mat(&guess), &Matrix::vec_to_col_mat(&desired)).unwrap();
+
+        for i in 0..guess.len() {
+            let (mut plus, mut minus) = (guess, guess);
+            plus[i] += EPSILON;
+            minus[i] -= EPSILON;
+
+            let numerical = (error(&loss, &plus, &desired) - error(&loss, &minus, &desired)) / (2.0 * EPSILON);
+            let analytic = derivative_error(&loss, guess.len(), guess[i], targets.values[i]).unwrap();
+
+            assert!((analytic - numerical).abs() < 1e-6,
+                "{loss}, output {i}: analytic {analytic}, numerical {numerical}");
+        }
+    }
+}
+
+#[test]
+fn focal_loss_with_no_focus_is_weighted_binary_cross_entropy() {
+    let guess = [0.2, 0.9];
+    let desired = [1.0, 0.0];
+
+    let bce = error(&DenseLosses::BinaryCrossEntropy, &guess, &desired);
+    let focal = error(&DenseLosses::Focal(0.0, 0.5), &guess, &desired);
+    assert!((focal - 0.5 * bce).abs() < 1e-12);
+
+    // focusing reduces the loss of the well classified outputs
+    let easy = error(&DenseLosses::Focal(2.0, 0.5), &[0.9], &[1.0]);
+    assert!(easy < 0.01 * error(&DenseLosses::Focal(0.0, 0.5), &[0.9], &[1.0]));
+}
+
+#[test]
+fn kl_divergence_is_cross_entropy_minus_entropy() {
+    let guess = [0.2, 0.5, 0.3];
+    let desired = [0.1, 0.6, 0.3];
+
+    let entropy: f64 = desired.iter().map(|y: &f64| -y * y.ln()).sum();
+    let cross_entropy = error(&DenseLosses::CategoricalCrossEntropy, &guess, &desired);
+
+    assert!((error(&DenseLosses::KlDivergence, &guess, &desired) - (cross_entropy - entropy)).abs() < 1e-12);
+    assert!(error(&DenseLosses::KlDivergence, &desired, &desired).abs() < 1e-12);
+}
+
+#[test]
+fn hinge_accepts_both_label_conventions() {
+    let guess = [0.4, -2.0];
+
+    assert_eq!(error(&DenseLosses::Hinge, &guess, &[1.0, 0.0]), error(&DenseLosses::Hinge, &guess, &[1.0, -1.0]));
+    assert!((error(&DenseLosses::Hinge, &guess, &[1.0, 0.0]) - 0.3).abs() < 1e-12);
+    assert!((error(&DenseLosses::SquaredHinge, &guess, &[1.0, 0.0]) - 0.18).abs() < 1e-12);
+}
+
+#[test]
+fn sparse_labels_match_one_hot_cross_entropy() {
+    let values = Matrix::from_columns(&[&Matrix::vec_to_col_mat(&[0.2, 0.5, 0.3]),
+        &Matrix::vec_to_col_mat(&[0.6, 0.1, 0.3])]).unwrap();
+    let one_hot = Matrix::from_columns(&[&Matrix::vec_to_col_mat(&[0.0, 1.0, 0.0]),
+        &Matrix::vec_to_col_mat(&[0.0, 0.0, 1.0])]).unwrap();
+    let mut labels = Matrix::new(2, 1);
+    labels.values = vec![1.0, 2.0];
+
+    let sparse = calculate_error(&DenseLosses::SparseCategoricalCrossEntropy, &values, &labels).unwrap();
+    let dense = calculate_error(&DenseLosses::CategoricalCrossEntropy, &values, &one_hot).unwrap();
+    assert!((sparse - dense).abs() < 1e-12);
+
+    for invalid in [vec![1.0, 3.0], vec![0.5, 1.0], vec![-1.0, 0.0]] {
+        labels.values = invalid;
+        assert!(calculate_error(&DenseLosses::SparseCategoricalCrossEntropy, &values, &labels).is_err());
+    }
+    assert!(calculate_error(&DenseLosses::SparseCategoricalCrossEntropy, &values, &one_hot).is_err());
+}
+
+#[test]
+fn label_smoothing_targets() {
+    let values = Matrix::vec_to_col_mat(&[0.25, 0.25, 0.5, 0.0]);
+    let targets = dense_targets(&DenseLosses::LabelSmoothing(0.2), &values,
+        &Matrix::vec_to_col_mat(&[0.0, 0.0, 1.0, 0.0])).unwrap();
+
+    for (target, expected) in targets.values.iter().zip([0.05, 0.05, 0.85, 0.05]) {
+        assert!((target - expected).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn sparse_labels_train_a_softmax_model() {
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(4, 1, 1), DenseShape::new(3, 1, 1)];
+    let mut model = DenseModel::new_seeded(vec![DenseActivation::Tanh, DenseActivation::Softmax],
+        DenseLosses::SparseCategoricalCrossEntropy, shapes, Some(11)).unwrap();
+
+    let input = Matrix::from_columns(&[&Matrix::vec_to_col_mat(&[0.5, -0.2, 0.9]),
+        &Matrix::vec_to_col_mat(&[-0.7, 0.3, 0.1])]).unwrap();
+    let mut labels = Matrix::new(2, 1);
+    labels.values = vec![2.0, 0.0];
+
+    for (l, layer) in gradient_check(&mut model, &input, &labels, 1e-5).unwrap().iter().enumerate() {
+        assert!(layer.max_error() < 1e-4, "layer {l}: {}", layer.max_error());
+    }
+}
+
+#[test]
+fn cross_entropies_are_finite_on_confident_mistakes() {
+    let loss = error(&DenseLosses::CategoricalCrossEntropy, &[0.0, 1.0], &[1.0, 0.0]);
+    assert!(loss.is_finite() && loss > 20.0);
+
+    let loss = error(&DenseLosses::BinaryCrossEntropy, &[1.0], &[0.0]);
+    assert!(loss.is_finite() && loss > 20.0);
+
+    assert!(derivative_error(&DenseLosses::CategoricalCrossEntropy, 2, 0.0, 1.0).unwrap().is_finite());
+    assert!(derivative_error(&DenseLosses::BinaryCrossEntropy, 1, 1.0, 0.0).unwrap().is_finite());
+}
@@ -0,0 +1,157 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::data::split_data::Labels;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::metrics::classification_metrics::{
+    accuracy, confusion_matrix, f1_score, pr_auc, precision, recall, roc_auc, top_k_accuracy, Average
+};
+use rusty_nn::metrics::metric::Metric;
+use rusty_nn::metrics::regression_metrics::{
+    mean_absolute_error, mean_absolute_percentage_error, r2_score, root_mean_squared_error
+};
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::TempFiles;
+
+fn assert_close(value: f64, expected: f64) {
+    assert!((value - expected).abs() < 1e-12, "{value} instead of {expected}");
+}
+
+// five samples of 3 classes: the third and the fourth ones are misclassified,
+// but their class has the second greatest output
+fn classified() -> (Matrix, Matrix) {
+    common::columns(
+        &[vec![0.7, 0.2, 0.1], vec![0.1, 0.6, 0.3], vec![0.2, 0.5, 0.3], vec![0.3, 0.4, 0.3], vec![0.1, 0.2, 0.7]],
+        &[vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0], vec![0.0, 0.0, 1.0], vec![1.0, 0.0, 0.0], vec![0.0, 0.0, 1.0]])
+}
+
+#[test]
+fn accuracies() {
+    let (predictions, desired_output) = classified();
+
+    assert_close(accuracy(&predictions, &desired_output).unwrap(), 0.6);
+    assert_close(top_k_accuracy(&predictions, &desired_output, 1).unwrap(), 0.6);
+    assert_close(top_k_accuracy(&predictions, &desired_output, 2).unwrap(), 1.0);
+
+    assert!(matches!(accuracy(&predictions, &predictions.column(0)), Err(NnError::ShapeMismatch {..})));
+}
+
+#[test]
+fn confusion_matrix_and_averaged_scores() {
+    let (predictions, desired_output) = classified();
+
+    // one row per desired class, one column per predicted class
+    let confusion = confusion_matrix(&predictions, &desired_output).unwrap();
+    assert_eq!(confusion.values, vec![1.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0]);
+
+    // (precision, recall, f1) of each class: (1, 1/2, 2/3), (1/3, 1, 1/2) and (1, 1/2, 2/3), supported by 2, 1 and 2 samples
+    assert_close(precision(&predictions, &desired_output, Average::Micro).unwrap(), 0.6);
+    assert_close(precision(&predictions, &desired_output, Average::Macro).unwrap(), 7.0 / 9.0);
+    assert_close(recall(&predictions, &desired_output, Average::Macro).unwrap(), 2.0 / 3.0);
+    assert_close(recall(&predictions, &desired_output, Average::Weighted).unwrap(), 0.6);
+
+    assert_close(f1_score(&predictions, &desired_output, Average::Micro).unwrap(), 0.6);
+    assert_close(f1_score(&predictions, &desired_output, Average::Macro).unwrap(), 11.0 / 18.0);
+    assert_close(f1_score(&predictions, &desired_output, Average::Weighted).unwrap(), 19.0 / 30.0);
+}
+
+#[test]
+fn areas_under_the_curves() {
+    let (predictions, desired_output) = classified();
+
+    // the positives of the first two classes are scored above all their negatives, while the first positive
+    // of the third class only beats one negative and ties with two others (5/6)
+    assert_close(roc_auc(&predictions, &desired_output).unwrap(), 17.0 / 18.0);
+
+    // the third class ranks its positives first and third (average precision of (1 + 2/3) / 2)
+    assert_close(pr_auc(&predictions, &desired_output).unwrap(), 17.0 / 18.0);
+
+    // a single output is thresholded at 0.5
+    let mut binary = Matrix::new(4, 1);
+    binary.values = vec![0.9, 0.4, 0.6, 0.2];
+    let mut labels = Matrix::new(4, 1);
+    labels.values = vec![1.0, 0.0, 0.0, 1.0];
+
+    assert_close(accuracy(&binary, &labels).unwrap(), 0.5);
+    assert_eq!(confusion_matrix(&binary, &labels).unwrap().values, vec![1.0, 1.0, 1.0, 1.0]);
+    assert_close(roc_auc(&binary, &labels).unwrap(), 0.5);
+    assert_close(pr_auc(&binary, &labels).unwrap(), 0.75);
+
+    let mut negatives = Matrix::new(4, 1);
+    negatives.values = vec![0.0; 4];
+    assert!(matches!(roc_auc(&binary, &negatives), Err(NnError::InvalidDataset(_))));
+}
+
+#[test]
+fn regression_metrics() {
+    let mut predictions = Matrix::new(2, 2);
+    predictions.values = vec![1.0, 2.0, 3.0, 4.0];
+    let mut desired_output = Matrix::new(2, 2);
+    desired_output.values = vec![1.5, 2.0, 2.0, 5.0];
+
+    assert_close(mean_absolute_error(&predictions, &desired_output).unwrap(), 0.625);
+    assert_close(root_mean_squared_error(&predictions, &desired_output).unwrap(), 0.75);
+
+    // 1 - 2.25 / 7.6875
+    assert_close(r2_score(&predictions, &desired_output).unwrap(), 29.0 / 41.0);
+    assert_close(mean_absolute_percentage_error(&predictions, &desired_output).unwrap(), 31.0 / 120.0);
+
+    assert_close(r2_score(&desired_output, &desired_output).unwrap(), 1.0);
+}
+
+#[test]
+fn undefined_metrics_are_reported_as_nan_by_a_session() {
+    // only positive samples
+    let files = TempFiles::new("metrics_session");
+    let inputs: Vec<Vec<f64>> = vec![vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]];
+    let mut session = common::session(&files, DenseShape::new(2, 1, 1), &inputs, DenseShape::new(1, 1, 1), &vec![vec![1.0]; 3], 3, 0.1);
+    session.metrics = vec![Metric::RocAuc, Metric::Accuracy, Metric::PrAuc];
+
+    let shapes = vec![DenseShape::new(2, 1, 1), DenseShape::new(1, 1, 1)];
+    let mut model = DenseModel::new_seeded(vec![DenseActivation::Sigmoid], DenseLosses::BinaryCrossEntropy, shapes, Some(1)).unwrap();
+
+    session.train(&mut model).unwrap();
+
+    let (_, metrics) = session.evaluate(&mut model, &session.dataset).unwrap();
+    assert!(metrics[0].is_nan());
+    assert!(!metrics[1].is_nan());
+    assert!(!metrics[2].is_nan());
+}
+
+#[test]
+fn sparse_labels_are_read_as_classes() {
+    let mut predictions = Matrix::new(4, 3);
+    predictions.values = vec![0.7, 0.1, 0.2, 0.3, 0.2, 0.8, 0.5, 0.3, 0.1, 0.1, 0.3, 0.4];
+    let (_, one_hot) = common::batch();
+    let labels = |classes: [f64; 4]| {
+        let mut labels = Matrix::new(4, 1);
+        labels.values = classes.to_vec();
+        labels
+    };
+    let sparse = labels([0.0, 1.0, 2.0, 0.0]);
+
+    for metric in [Metric::Accuracy, Metric::TopKAccuracy(2), Metric::F1Score(Average::Macro), Metric::RocAuc, Metric::PrAuc] {
+        assert_eq!(metric.compute(&predictions, &sparse, Labels::Sparse(3)).unwrap(),
+            metric.compute(&predictions, &one_hot, Labels::Encoded).unwrap(), "{metric}");
+    }
+    assert!(matches!(Metric::Accuracy.compute(&predictions, &sparse, Labels::Encoded), Err(NnError::ShapeMismatch { .. })));
+    assert!(Metric::Accuracy.compute(&predictions, &labels([0.0, 1.0, 3.0, 0.0]), Labels::Sparse(3)).is_err());
+
+    // a session reports them with its labels
+    let files = TempFiles::new("metrics_sparse");
+    let inputs: Vec<Vec<f64>> = (0..4).map(|x| one_hot.column(x).values).collect();
+    let outputs: Vec<Vec<f64>> = sparse.values.iter().map(|class| vec![*class]).collect();
+    let mut session = common::session(&files, DenseShape::new(3, 1, 1), &inputs, DenseShape::new(1, 1, 1), &outputs, 200, 0.5);
+    session.labels = Labels::Sparse(3);
+    session.metrics = vec![Metric::Accuracy, Metric::Precision(Average::Micro)];
+
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(3, 1, 1)];
+    let mut model = DenseModel::new_seeded(vec![DenseActivation::Softmax], DenseLosses::SparseCategoricalCrossEntropy, shapes, Some(2)).unwrap();
+
+    session.train(&mut model).unwrap();
+    assert_eq!(session.evaluate(&mut model, &session.dataset).unwrap().1, vec![1.0, 1.0]);
+}
@@ -0,0 +1,270 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::layers::dense_layer::Dense;
+use rusty_nn::layers::layer::Layer;
+use rusty_nn::layers::normalization_layer::Normalization;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::models::gradient_check::gradient_check;
+use rusty_nn::models::model::Model;
+use rusty_nn::models::sequential::Sequential;
+use rusty_nn::normalizations::dense_normalization::DenseNormalization;
+use rusty_nn::normalizations::DEFAULT_BATCH_NORM_MOMENTUM;
+use rusty_nn::optimizers::adam::Adam;
+use rusty_nn::optimizers::sgd::Sgd;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::{batch, TempFiles};
+
+fn model(output: DenseActivation, loss: DenseLosses, normalization: DenseNormalization) -> DenseModel {
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(5, 1, 1), DenseShape::new(4, 1, 1), DenseShape::new(3, 1, 1)];
+    let mut model = DenseModel::new_seeded(vec![DenseActivation::Tanh, DenseActivation::Relu, output], loss, shapes, Some(21)).unwrap();
+
+    for l in 0..3 {
+        model.set_normalization(l, normalization).unwrap();
+    }
+    model
+}
+
+#[test]
+fn normalized_gradients_match_numerical_ones() {
+    let (input, output) = batch();
+
+    for normalization in [DenseNormalization::BatchNorm(0.9), DenseNormalization::LayerNorm] {
+        for (activation, loss) in [(DenseActivation::Softmax, DenseLosses::CategoricalCrossEntropy),
+            (DenseActivation::Sigmoid, DenseLosses::BinaryCrossEntropy), (DenseActivation::Identity, DenseLosses::MeanSquaredError)] {
+
+            let mut model = model(activation, loss, normalization);
+
+            for (l, layer) in gradient_check(&mut model, &input, &output, 1e-5).unwrap().iter().enumerate() {
+                assert!(layer.max_error() < 1e-4, "{normalization} x {loss}, layer {l}: weights {}, biases {}, normalization {}",
+                    layer.weights_error, layer.biases_error, layer.normalization_error);
+            }
+        }
+    }
+}
+
+#[test]
+fn layer_norm_normalizes_each_sample() {
+    let (mut values, _) = batch();
+    let normalization = DenseNormalization::LayerNorm;
+    let parameters = normalization.initial_parameters(3);
+
+    normalization.forward(&mut values, &parameters, &mut Matrix::new(0, 0), false).unwrap();
+
+    for x in 0..values.x_length {
+        let column: Vec<f64> = (0..3).map(|y| values.get(y, x)).collect();
+        let mean: f64 = column.iter().sum::<f64>() / 3.0;
+        let variance: f64 = column.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / 3.0;
+
+        assert!(mean.abs() < 1e-12);
+        assert!((variance - 1.0).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn batch_norm_uses_running_statistics_at_inference() {
+    let (input, _) = batch();
+
+    // without momentum, the running statistics are the ones of the last batch
+    let mut model = model(DenseActivation::Sigmoid, DenseLosses::BinaryCrossEntropy, DenseNormalization::BatchNorm(0.0));
+
+    model.set_training(true);
+    model.feed_forward(&input).unwrap();
+    let training_output = model.result();
+
+    let statistics = model.running_statistics();
+    assert!(statistics.iter().all(|s| s.y_length == 2 && s.values.iter().any(|v| *v != 0.0 && *v != 1.0)));
+
+    model.set_training(false);
+    model.feed_forward(&input).unwrap();
+    assert_eq!(model.running_statistics()[0].values, statistics[0].values);
+
+    for (a, b) in training_output.values.iter().zip(model.result().values.iter()) {
+        assert!((a - b).abs() < 1e-9, "{a} vs {b}");
+    }
+
+    // a single sample is normalized with the same statistics at inference
+    model.feed_forward(&input.column(1)).unwrap();
+    assert!((model.result().values[0] - training_output.get(0, 1)).abs() < 1e-9);
+}
+
+#[test]
+fn scale_and_shift_are_trained() {
+    let (input, output) = batch();
+    let mut model = model(DenseActivation::Sigmoid, DenseLosses::BinaryCrossEntropy, DenseNormalization::LayerNorm);
+    let initial = model.copy_parameters();
+    let mut optimizer = Adam::default();
+
+    model.feed_forward(&input).unwrap();
+    let initial_error = model.loss().error(&model.result(), &output).unwrap();
+
+    for _ in 0..50 {
+        model.feed_forward(&input).unwrap();
+        let deltas = model.back_propagate(&output).unwrap();
+        let gradients = model.gradients(&deltas).unwrap();
+        model.update_weights(&gradients, &mut optimizer, 0.01).unwrap();
+    }
+
+    let parameters = model.copy_parameters();
+    for l in 9..12 {
+        assert_eq!(parameters[l].y_length, 2);
+        assert_ne!(parameters[l].values, initial[l].values);
+    }
+
+    model.feed_forward(&input).unwrap();
+    assert!(model.loss().error(&model.result(), &output).unwrap() < initial_error);
+}
+
+#[test]
+fn normalizations_are_saved() {
+    for normalization in [DenseNormalization::NoNormalization, DenseNormalization::BatchNorm(0.99), DenseNormalization::LayerNorm] {
+        assert_eq!(normalization.to_string().parse::<DenseNormalization>().unwrap(), normalization);
+    }
+    assert_eq!("BatchNorm".parse::<DenseNormalization>().unwrap(), DenseNormalization::BatchNorm(DEFAULT_BATCH_NORM_MOMENTUM));
+    assert!("BatchNorm(1)".parse::<DenseNormalization>().is_err());
+    assert!("GroupNorm".parse::<DenseNormalization>().is_err());
+
+    let (input, output) = batch();
+    let mut model = model(DenseActivation::Softmax, DenseLosses::CategoricalCrossEntropy, DenseNormalization::BatchNorm(0.5));
+    model.set_normalization(1, DenseNormalization::LayerNorm).unwrap();
+    model.set_training(true);
+
+    for _ in 0..5 {
+        model.feed_forward(&input).unwrap();
+        let deltas = model.back_propagate(&output).unwrap();
+        let gradients = model.gradients(&deltas).unwrap();
+        model.update_weights(&gradients, &mut Adam::default(), 0.01).unwrap();
+    }
+    model.set_training(false);
+
+    let files = TempFiles::new("normalized_model");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+    let mut loaded = DenseModel::load_model(&filename).unwrap();
+
+    assert_eq!(loaded.normalizations(), model.normalizations());
+    assert!(loaded.running_statistics()[1].values.is_empty());
+
+    for (a, b) in loaded.running_statistics().iter().zip(model.running_statistics().iter()) {
+        assert_eq!(a.values, b.values);
+    }
+    for (a, b) in loaded.copy_parameters().iter().zip(model.copy_parameters().iter()) {
+        assert_eq!(a.values, b.values);
+    }
+
+    model.feed_forward(&input).unwrap();
+    loaded.feed_forward(&input).unwrap();
+    assert_eq!(model.result().values, loaded.result().values);
+}
+
+#[test]
+fn batch_norm_trains_single_samples_with_running_statistics() {
+    let (input, output) = batch();
+    let mut model = model(DenseActivation::Sigmoid, DenseLosses::BinaryCrossEntropy, DenseNormalization::BatchNorm(0.9));
+
+    model.set_training(true);
+    model.feed_forward(&input).unwrap();
+
+    // a single sample is normalized the same way while training and at inference, without updating the statistics
+    let statistics = model.running_statistics();
+    model.feed_forward(&input.column(0)).unwrap();
+    let training_output = model.result();
+    assert_eq!(model.running_statistics()[0].values, statistics[0].values);
+
+    model.set_training(false);
+    model.feed_forward(&input.column(0)).unwrap();
+    assert_eq!(model.result().values, training_output.values);
+
+    // and its gradients still train the weights, gamma and beta of the output layer
+    let initial = model.copy_parameters();
+    model.set_training(true);
+    model.feed_forward(&input.column(0)).unwrap();
+    let deltas = model.back_propagate(&output.column(0)).unwrap();
+    let gradients = model.gradients(&deltas).unwrap();
+    model.update_weights(&gradients, &mut Sgd::new(), 0.1).unwrap();
+    let parameters = model.copy_parameters();
+    for l in [4, 11] {
+        assert_ne!(parameters[l].values, initial[l].values);
+    }
+
+    assert!(matches!(model.set_running_statistics(&statistics[..2]), Err(NnError::ShapeMismatch {..})));
+    assert!(matches!(model.set_running_statistics(&[]), Err(NnError::ShapeMismatch {..})));
+    model.set_running_statistics(&statistics).unwrap();
+}
+
+#[test]
+fn sessions_train_batch_norm_with_an_uneven_last_batch() {
+    let files = TempFiles::new("normalization_session");
+    let inputs: Vec<Vec<f64>> = (0..5).map(|i| vec![(i as f64).sin(), (i as f64 * 0.5).cos(), i as f64 / 5.0]).collect();
+    let outputs: Vec<Vec<f64>> = (0..5).map(|i| vec![(i % 2) as f64, 0.0, 1.0]).collect();
+
+    // batches of 2, 2 and 1 samples, then of a single sample each (the default)
+    for batch_size in [2, 1] {
+        let mut session = common::session(&files, DenseShape::new(3, 1, 1), &inputs, DenseShape::new(3, 1, 1), &outputs, 3, 0.1);
+        session.batch_size = batch_size;
+        session.set_seed(4);
+
+        let mut model = model(DenseActivation::Sigmoid, DenseLosses::BinaryCrossEntropy, DenseNormalization::BatchNorm(0.9));
+        let initial = model.running_statistics();
+        session.train(&mut model).unwrap();
+
+        let trained = model.running_statistics();
+        assert_eq!(trained[0].values != initial[0].values, batch_size > 1);
+    }
+}
+
+#[test]
+fn sequential_normalization_layers_match_dense_model() {
+    let (input, output) = batch();
+    let mut dense = model(DenseActivation::Softmax, DenseLosses::CategoricalCrossEntropy, DenseNormalization::BatchNorm(0.5));
+
+    let mut layers: Vec<Box<dyn Layer>> = Vec::new();
+    for (nb_outputs, activation) in [(5, DenseActivation::Tanh), (4, DenseActivation::Relu), (3, DenseActivation::Softmax)] {
+        layers.push(Box::new(Dense::new(nb_outputs)));
+        layers.push(Box::new(Normalization::new(DenseNormalization::BatchNorm(0.5))));
+        layers.push(common::activation(activation));
+    }
+    let mut sequential = Sequential::new(3, layers, Box::new(DenseLosses::CategoricalCrossEntropy), None).unwrap();
+
+    // the same parameters in the order of each model
+    let parameters = dense.copy_parameters();
+    sequential.set_parameters(&[&parameters[0], &parameters[1], &parameters[9], &parameters[2], &parameters[3],
+        &parameters[10], &parameters[4], &parameters[5], &parameters[11]].map(Matrix::copy)).unwrap();
+
+    let mut weights = Matrix::new(4, 1);
+    weights.values = vec![1.0; 4];
+
+    dense.set_training(true);
+    sequential.set_training(true);
+    for _ in 0..3 {
+        for model in [&mut dense as &mut dyn Model, &mut sequential] {
+            model.feed_forward(&input).unwrap();
+            let deltas = model.back_propagate_weighted(&output, &weights).unwrap();
+            let gradients = model.gradients(&deltas).unwrap();
+            model.update_weights(&gradients, &mut Sgd::new(), 0.1).unwrap();
+        }
+    }
+    dense.set_training(false);
+    sequential.set_training(false);
+
+    assert_eq!(Model::running_statistics(&sequential).iter().map(|s| s.values.clone()).collect::<Vec<Vec<f64>>>(),
+        dense.running_statistics().iter().map(|s| s.values.clone()).collect::<Vec<Vec<f64>>>());
+
+    let files = TempFiles::new("sequential_normalization");
+    let filename = files.path("model");
+    sequential.save(&filename).unwrap();
+    let mut loaded = Sequential::load_model(&filename).unwrap();
+    assert_eq!(loaded.layers()[1].tag(), "Normalization BatchNorm(0.5)");
+
+    for model in [&mut dense as &mut dyn Model, &mut sequential, &mut loaded] {
+        model.feed_forward(&input).unwrap();
+    }
+    for (s, d) in sequential.result().values.iter().zip(dense.result().values.iter()) {
+        assert!((s - d).abs() < 1e-12);
+    }
+    assert_eq!(loaded.result().values, sequential.result().values);
+}
@@ -0,0 +1,138 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::optimizers::adagrad::Adagrad;
+use rusty_nn::optimizers::adam::{Adam, AdamW};
+use rusty_nn::optimizers::optimizer::{load_optimizer, save_optimizer, Optimizer};
+use rusty_nn::optimizers::rmsprop::RmsProp;
+use rusty_nn::optimizers::sgd::{Momentum, Nesterov, Sgd};
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::{batch, TempFiles};
+
+fn column(values: &[f64]) -> Matrix {
+    Matrix::vec_to_col_mat(values)
+}
+
+// the parameter [1, -2] after two steps of learning rate 0.1, with the gradients [0.5, -1] then [0.2, 0.4]
+fn two_steps(optimizer: &mut dyn Optimizer) -> (Vec<f64>, Vec<f64>) {
+    let mut parameter = column(&[1.0, -2.0]);
+
+    optimizer.begin_step();
+    optimizer.update(0, &mut parameter, &column(&[0.5, -1.0]), 0.1).unwrap();
+    let first = parameter.values.clone();
+
+    optimizer.begin_step();
+    optimizer.update(0, &mut parameter, &column(&[0.2, 0.4]), 0.1).unwrap();
+
+    (first, parameter.values)
+}
+
+fn assert_steps(optimizer: &mut dyn Optimizer, first: [f64; 2], second: [f64; 2]) {
+    let name = optimizer.name();
+    let (a, b) = two_steps(optimizer);
+
+    for (value, expected) in a.iter().chain(b.iter()).zip(first.iter().chain(second.iter())) {
+        assert!((value - expected).abs() < 1e-12, "{name}: {value} instead of {expected}");
+    }
+    assert_eq!(optimizer.state().step, 2);
+}
+
+#[test]
+fn sgd_and_momentum_steps() {
+    assert_steps(&mut Sgd::new(), [0.95, -1.9], [0.93, -1.94]);
+
+    // v = [-0.05, 0.1] then 0.9 * v - 0.1 * [0.2, 0.4] = [-0.065, 0.05]
+    assert_steps(&mut Momentum::new(0.9), [0.95, -1.9], [0.885, -1.85]);
+
+    // the parameter moves by 0.9 * v - 0.1 * g: [-0.095, 0.19] then [-0.0785, 0.005]
+    assert_steps(&mut Nesterov::new(0.9), [0.905, -1.81], [0.8265, -1.805]);
+}
+
+#[test]
+fn adaptive_steps() {
+    // the squared gradients add up to [0.25, 1] then [0.29, 1.16]
+    assert_steps(&mut Adagrad::new(0.0), [0.9, -1.9],
+        [0.9 - 0.02 / 0.29f64.sqrt(), -1.9 - 0.04 / 1.16f64.sqrt()]);
+
+    // the squared gradients average to [0.025, 0.1] then [0.0265, 0.106]
+    let first = [1.0 - 0.05 / 0.025f64.sqrt(), -2.0 + 0.1 / 0.1f64.sqrt()];
+    assert_steps(&mut RmsProp::new(0.9, 0.0), first,
+        [first[0] - 0.02 / 0.0265f64.sqrt(), first[1] - 0.04 / 0.106f64.sqrt()]);
+}
+
+#[test]
+fn adam_corrects_the_bias_of_its_moments() {
+    // once corrected, the first moments are the gradient, so the first step is the learning rate
+    // (0.1 * 0.05 / sqrt(0.00025) = 0.316 without correction)
+    // then m = [0.065, -0.05] and v = [0.00028975, 0.001159], divided by 1 - 0.9^2 and 1 - 0.999^2
+    let step = |m: f64, v: f64| 0.1 * (m / 0.19) / (v / 0.001999).sqrt();
+    let second = [0.9 - step(0.065, 0.00028975), -1.9 - step(-0.05, 0.001159)];
+
+    assert_steps(&mut Adam::new(0.9, 0.999, 0.0), [0.9, -1.9], second);
+
+    // the weight decay shrinks the parameters by lr * 0.1 = 1% before each step, and leaves the moments as they are
+    let first = [1.0 * 0.99 - 0.1, -2.0 * 0.99 + 0.1];
+    assert_steps(&mut AdamW::new(0.9, 0.999, 0.0, 0.1), first,
+        [first[0] * 0.99 - step(0.065, 0.00028975), first[1] * 0.99 - step(-0.05, 0.001159)]);
+}
+
+#[test]
+fn optimizers_check_the_gradients() {
+    let mut optimizer = Adam::default();
+    optimizer.begin_step();
+    assert!(optimizer.update(0, &mut column(&[1.0, 2.0]), &column(&[1.0]), 0.1).is_err());
+}
+
+// saving the optimizer halfway and going on with the loaded one gives the uninterrupted training
+#[test]
+fn saved_optimizers_resume_the_training() {
+    let (input, output) = batch();
+    let files = TempFiles::new("optimizer_resume");
+    let filename = files.path("optimizer");
+
+    let model = || {
+        let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(5, 1, 1), DenseShape::new(3, 1, 1)];
+        DenseModel::new_seeded(vec![DenseActivation::Tanh, DenseActivation::Softmax],
+            DenseLosses::CategoricalCrossEntropy, shapes, Some(8)).unwrap()
+    };
+
+    let train = |model: &mut DenseModel, optimizer: &mut dyn Optimizer, nb_steps: usize| {
+        for _ in 0..nb_steps {
+            model.feed_forward(&input).unwrap();
+            let deltas = model.back_propagate(&output).unwrap();
+            let gradients = model.gradients(&deltas).unwrap();
+            model.update_weights(&gradients, optimizer, 0.05).unwrap();
+        }
+    };
+
+    let optimizers: Vec<fn() -> Box<dyn Optimizer>> = vec![
+        || Box::new(Sgd::new()), || Box::new(Momentum::new(0.9)), || Box::new(Nesterov::new(0.8)),
+        || Box::new(Adagrad::default()), || Box::new(RmsProp::default()), || Box::new(Adam::default()),
+        || Box::new(AdamW::default())
+    ];
+
+    for optimizer in optimizers {
+        let mut uninterrupted = model();
+        train(&mut uninterrupted, optimizer().as_mut(), 6);
+
+        let mut resumed = model();
+        let mut first_half = optimizer();
+        train(&mut resumed, first_half.as_mut(), 3);
+        save_optimizer(first_half.as_ref(), &filename).unwrap();
+
+        let mut loaded = load_optimizer(&filename).unwrap();
+        assert_eq!(loaded.name(), first_half.name());
+        assert_eq!(loaded.hyperparameters(), first_half.hyperparameters());
+        assert_eq!(loaded.state().step, 3);
+
+        train(&mut resumed, loaded.as_mut(), 3);
+
+        for (a, b) in resumed.copy_parameters().iter().zip(uninterrupted.copy_parameters().iter()) {
+            assert_eq!(a.values, b.values, "{}", loaded.name());
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use rusty_nn::prelude::*;
+
+mod common;
+
+use common::TempFiles;
+
+// a training and its saved model only need the prelude
+fn train_and_save(files: &TempFiles) -> NnResult<DenseModel> {
+    let inputs: Vec<Vec<f64>> = vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]];
+    let outputs: Vec<Vec<f64>> = vec![vec![0.0], vec![1.0], vec![1.0], vec![1.0]];
+
+    let mut session: Session = common::session(files, DenseShape::new(2, 1, 1), &inputs, DenseShape::new(1, 1, 1), &outputs, 5, 0.5);
+    let dataset: &Vec<Sample> = &session.dataset;
+    assert_eq!(dataset.len(), 4);
+
+    let shapes = vec![DenseShape::new(2, 1, 1), DenseShape::new(3, 1, 1), DenseShape::new(1, 1, 1)];
+    let mut model = DenseModel::new(vec![DenseActivation::Sigmoid, DenseActivation::Sigmoid], DenseLosses::MeanSquaredError, shapes);
+    session.train(&mut model)?;
+
+    model.save(&files.path("model"))?;
+    Ok(model)
+}
+
+#[test]
+fn the_prelude_trains_saves_and_loads_models() {
+    let files = TempFiles::new("prelude");
+    let mut model = train_and_save(&files).unwrap();
+    let mut loaded = DenseModel::load_model(&files.path("model")).unwrap();
+
+    let input: Matrix = Matrix::vec_to_col_mat(&[1.0, 0.0]);
+    model.feed_forward(&input).unwrap();
+    loaded.feed_forward(&input).unwrap();
+
+    let error = calculate_error(&DenseLosses::MeanSquaredError, &model.result(), &loaded.result()).unwrap();
+    assert!(error < 1e-12);
+
+    let missing: NnResult<Vec<Sample>> = load_data(&files.path("missing.txt"), &files.path("missing.txt"));
+    assert!(matches!(missing, Err(NnError::Io { .. })));
+}
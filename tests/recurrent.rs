@@ -0,0 +1,201 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::data::create_data::Sample;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::layers::dense_layer::Dense;
+use rusty_nn::layers::layer::Layer;
+use rusty_nn::layers::recurrent_layer::{Recurrent, RecurrentCell};
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::gradient_check::parameter_errors;
+use rusty_nn::models::registry::Registry;
+use rusty_nn::models::sequential::Sequential;
+use rusty_nn::optimizers::adam::Adam;
+use rusty_nn::regularizers::dense_regularizer::DenseRegularizer;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::{activation, TempFiles};
+
+const CELLS: [RecurrentCell; 3] = [RecurrentCell::SimpleRnn, RecurrentCell::Lstm, RecurrentCell::Gru];
+
+// three sequences of 2 features padded with zeros to 5 steps, one per column, and their class out of 3
+fn batch() -> (Matrix, Matrix) {
+    let sequences: Vec<Vec<Vec<f64>>> = vec![
+        vec![vec![0.5, -1.0], vec![0.2, 0.9], vec![-0.3, 0.7], vec![0.8, -0.1], vec![-0.4, 0.6]],
+        vec![vec![-0.9, 0.1], vec![0.4, 0.4]],
+        vec![vec![0.3, -0.6], vec![-0.7, -0.2], vec![0.6, 0.5]]
+    ];
+
+    let samples: Vec<Sample> = sequences.iter().zip([[1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]])
+        .map(|(steps, output)| Sample::sequence(steps, 5, 0.0, output.to_vec()).unwrap())
+        .collect();
+
+    common::batch_of(&samples)
+}
+
+fn classifier(layers: Vec<Box<dyn Layer>>, seed: u64) -> Sequential {
+    common::classifier(DenseShape::new(2, 5, 1), layers, 3, seed)
+}
+
+#[test]
+fn recurrent_gradients_match_numerical_ones() {
+    let (input, output) = batch();
+
+    for cell in CELLS {
+        for masked in [false, true] {
+            let mut first = Recurrent::new(cell, 3).returning_sequences().regularized(DenseRegularizer::L2(0.01));
+            let mut second = Recurrent::new(cell, 4);
+
+            if masked {
+                first = first.masked(0.0);
+                second = second.masked(0.0);
+            }
+
+            let mut model = classifier(vec![Box::new(first), Box::new(second)], 4);
+            let (errors, update_errors) = parameter_errors(&mut model, &input, &output, 1e-5).unwrap();
+
+            assert_eq!(errors.len(), 8);
+            for (p, (error, update_error)) in errors.iter().zip(update_errors.iter()).enumerate() {
+                assert!(*error < 1e-4 && *update_error < 1e-4, "{cell}, masked: {masked}, parameter {p}: {error}, {update_error}");
+            }
+        }
+    }
+}
+
+#[test]
+fn masked_steps_are_skipped() {
+    let short = Sample::sequence(&[vec![0.5, -1.0], vec![0.2, 0.9]], 2, 0.0, vec![1.0]).unwrap();
+    let padded = Sample::sequence(&[vec![0.5, -1.0], vec![0.2, 0.9]], 5, 0.0, vec![1.0]).unwrap();
+    assert_eq!(padded.input.values, vec![0.5, -1.0, 0.2, 0.9, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+
+    for cell in CELLS {
+        // the parameters do not depend on the number of steps, so both models draw the same ones
+        let mut model = Sequential::with_input_shape(DenseShape::new(2, 2, 1), vec![Box::new(Recurrent::new(cell, 3))],
+            Box::new(DenseLosses::MeanSquaredError), Some(7)).unwrap();
+        let mut masked = Sequential::with_input_shape(DenseShape::new(2, 5, 1), vec![Box::new(Recurrent::new(cell, 3).masked(0.0))],
+            Box::new(DenseLosses::MeanSquaredError), Some(7)).unwrap();
+        let mut unmasked = Sequential::with_input_shape(DenseShape::new(2, 5, 1), vec![Box::new(Recurrent::new(cell, 3))],
+            Box::new(DenseLosses::MeanSquaredError), Some(7)).unwrap();
+
+        model.feed_forward(&short.input).unwrap();
+        masked.feed_forward(&padded.input).unwrap();
+        unmasked.feed_forward(&padded.input).unwrap();
+
+        assert_eq!(model.result().values, masked.result().values, "{cell}");
+        assert_ne!(model.result().values, unmasked.result().values, "{cell}");
+
+        let mut sequences = Sequential::with_input_shape(DenseShape::new(2, 5, 1),
+            vec![Box::new(Recurrent::new(cell, 3).returning_sequences().masked(-1.0))],
+            Box::new(DenseLosses::MeanSquaredError), Some(7)).unwrap();
+        let padded = Sample::sequence(&[vec![0.5, -1.0], vec![0.2, 0.9]], 5, -1.0, vec![1.0]).unwrap();
+
+        sequences.feed_forward(&padded.input).unwrap();
+        assert_eq!(sequences.output_shape(), DenseShape::new(3, 5, 1));
+        assert_eq!(sequences.result().values[3..6], model.result().values[..]);
+        assert!(sequences.result().values[6..].iter().all(|x| *x == -1.0));
+    }
+
+    assert!(matches!(Sample::sequence(&[vec![1.0], vec![2.0], vec![3.0]], 2, 0.0, vec![1.0]), Err(NnError::InvalidDataset(_))));
+    assert!(Sample::sequence(&[vec![1.0, 2.0], vec![3.0]], 2, 0.0, vec![1.0]).is_err());
+    assert!(Sample::sequence(&[], 2, 0.0, vec![1.0]).is_err());
+}
+
+#[test]
+fn truncation_limits_backpropagation_through_time() {
+    let (input, output) = batch();
+
+    for cell in CELLS {
+        let gradients = |truncation: usize| -> (Vec<Matrix>, Matrix) {
+            let mut model = classifier(vec![Box::new(Recurrent::new(cell, 3).truncated(truncation))], 2);
+            model.feed_forward(&input).unwrap();
+            let deltas = model.back_propagate(&output).unwrap();
+            (model.gradients(&deltas).unwrap(), deltas[0].copy())
+        };
+
+        let (full, full_input) = gradients(0);
+        let (whole, _) = gradients(5);
+        let (truncated, truncated_input) = gradients(2);
+
+        assert_eq!(full.iter().map(|g| g.values.clone()).collect::<Vec<Vec<f64>>>(),
+            whole.iter().map(|g| g.values.clone()).collect::<Vec<Vec<f64>>>(), "{cell}");
+        assert_ne!(full[0].values, truncated[0].values, "{cell}");
+
+        // only the last 2 steps (4 values per sample) get a gradient
+        assert!(truncated_input.values[..18].iter().all(|x| *x == 0.0), "{cell}");
+        assert!(truncated_input.values[18..].iter().any(|x| *x != 0.0), "{cell}");
+        assert!(full_input.values[..18].iter().any(|x| *x != 0.0), "{cell}");
+    }
+}
+
+#[test]
+fn session_trains_a_sequence_classifier() {
+    // is the sum of a sequence of 2 to 6 steps positive
+    let mut inputs: Vec<Vec<f64>> = Vec::new();
+    let mut outputs: Vec<Vec<f64>> = Vec::new();
+
+    for s in 0..40 {
+        let length: usize = 2 + s % 5;
+        let steps: Vec<Vec<f64>> = (0..length).map(|t| vec![if (s * 7 + t * 3) % 5 < 2 {-1.0} else {0.5} * (1.0 + (t % 2) as f64)]).collect();
+        let positive: bool = steps.iter().map(|step| step[0]).sum::<f64>() > 0.0;
+
+        let sample = Sample::sequence(&steps, 6, 0.0, vec![if positive {1.0} else {0.0}]).unwrap();
+        inputs.push(sample.input.values);
+        outputs.push(sample.output.values);
+    }
+
+    let files = TempFiles::new("recurrent_session");
+
+    for cell in CELLS {
+        let mut session = common::session(&files, DenseShape::new(1, 6, 1), &inputs, DenseShape::new(1, 1, 1), &outputs, 150, 0.02);
+        session.set_seed(5);
+        session.batch_size = 10;
+        session.log_interval = 1000;
+        session.optimizer = Box::new(Adam::default());
+
+        let layers: Vec<Box<dyn Layer>> = vec![Box::new(Recurrent::new(cell, 6).masked(0.0)),
+            Box::new(Dense::new(1)), activation(DenseActivation::Sigmoid)];
+        let mut model = Sequential::with_input_shape(DenseShape::new(1, 6, 1), layers,
+            Box::new(DenseLosses::BinaryCrossEntropy), Some(6)).unwrap();
+
+        let (initial_loss, _) = session.evaluate(&mut model, &session.dataset).unwrap();
+        session.train(&mut model).unwrap();
+        let (loss, _) = session.evaluate(&mut model, &session.dataset).unwrap();
+
+        assert!(loss < initial_loss / 4.0, "{cell}: {initial_loss} -> {loss}");
+    }
+}
+
+#[test]
+fn recurrent_models_are_saved() {
+    let (input, _) = batch();
+    let mut model = classifier(vec![
+        Box::new(Recurrent::new(RecurrentCell::Gru, 3).returning_sequences().masked(0.0).regularized(DenseRegularizer::L1(0.001))),
+        Box::new(Recurrent::new(RecurrentCell::Lstm, 4).truncated(3)),
+    ], 8);
+
+    let files = TempFiles::new("recurrent_model");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+
+    let mut loaded = Sequential::load_model(&filename).unwrap();
+
+    let tags: Vec<String> = loaded.layers().iter().map(|layer| layer.tag()).collect();
+    assert_eq!(tags[0], "GRU 3 Sequences 0 0 L1(0.001)");
+    assert_eq!(tags[1], "LSTM 4 Last 3 NoMask NoRegularizer");
+
+    model.feed_forward(&input).unwrap();
+    loaded.feed_forward(&input).unwrap();
+    assert_eq!(model.result().values, loaded.result().values);
+
+    let registry = Registry::new();
+    assert_eq!(registry.layer("SimpleRNN 5").unwrap().tag(), "SimpleRNN 5 Last 0 NoMask NoRegularizer");
+    assert_eq!(registry.layer("LSTM 2 Sequences 4 -1.5").unwrap().tag(), "LSTM 2 Sequences 4 -1.5 NoRegularizer");
+    assert!(registry.layer("GRU").is_err());
+    assert!(registry.layer("GRU 2 Everything").is_err());
+    assert!(registry.layer("RNN 2").is_err());
+
+    let invalid = Sequential::with_input_shape(DenseShape::new(2, 5, 2), vec![Box::new(Recurrent::new(RecurrentCell::Lstm, 3))],
+        Box::new(DenseLosses::MeanSquaredError), None);
+    assert!(matches!(invalid, Err(NnError::ShapeMismatch {..})));
+}
@@ -0,0 +1,183 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::layers::dense_layer::Dense;
+use rusty_nn::layers::layer::Layer;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::models::gradient_check::gradient_check;
+use rusty_nn::models::sequential::Sequential;
+use rusty_nn::optimizers::gradient_clipping::{global_norm, GradientClipping};
+use rusty_nn::optimizers::sgd::Sgd;
+use rusty_nn::regularizers::dense_constraint::DenseConstraint;
+use rusty_nn::regularizers::dense_regularizer::DenseRegularizer;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::TempFiles;
+
+fn weights() -> Matrix {
+    let mut weights = Matrix::new(2, 2);
+    weights.values = vec![1.0, -2.0, 0.5, 0.0];
+    weights
+}
+
+fn model() -> DenseModel {
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(4, 1, 1), DenseShape::new(2, 1, 1)];
+    DenseModel::new_seeded(vec![DenseActivation::Tanh, DenseActivation::Sigmoid],
+        DenseLosses::BinaryCrossEntropy, shapes, Some(5)).unwrap()
+}
+
+fn batch() -> (Matrix, Matrix) {
+    let mut input = Matrix::new(2, 3);
+    input.values = vec![0.5, -1.0, 0.2, 0.3, -0.7, 0.9];
+    let mut output = Matrix::new(2, 2);
+    output.values = vec![1.0, 0.0, 0.0, 1.0];
+    (input, output)
+}
+
+#[test]
+fn penalties_and_gradients() {
+    let weights = weights();
+
+    assert_eq!(DenseRegularizer::NoRegularizer.penalty(&weights), 0.0);
+    assert_eq!(DenseRegularizer::L1(0.1).penalty(&weights), 0.1 * 3.5);
+    assert_eq!(DenseRegularizer::L2(0.1).penalty(&weights), 0.1 * 5.25);
+    assert_eq!(DenseRegularizer::ElasticNet(0.1, 0.2).penalty(&weights), 0.1 * 3.5 + 0.2 * 5.25);
+
+    assert_eq!(DenseRegularizer::L1(0.1).gradient(&weights).values, vec![0.1, -0.1, 0.1, 0.0]);
+    assert_eq!(DenseRegularizer::L2(0.5).gradient(&weights).values, vec![1.0, -2.0, 0.5, 0.0]);
+}
+
+#[test]
+fn names_round_trip() {
+    for regularizer in [DenseRegularizer::NoRegularizer, DenseRegularizer::L1(0.01), DenseRegularizer::L2(0.5),
+        DenseRegularizer::ElasticNet(0.01, 0.02)] {
+        assert_eq!(regularizer.to_string().parse::<DenseRegularizer>().unwrap(), regularizer);
+    }
+    for constraint in [DenseConstraint::NoConstraint, DenseConstraint::MaxNorm(3.0)] {
+        assert_eq!(constraint.to_string().parse::<DenseConstraint>().unwrap(), constraint);
+    }
+
+    assert!("L2(-1)".parse::<DenseRegularizer>().is_err());
+    assert!("L3(1)".parse::<DenseRegularizer>().is_err());
+    assert!("MaxNorm(0)".parse::<DenseConstraint>().is_err());
+}
+
+#[test]
+fn regularized_gradients_match_numerical_ones() {
+    let (input, output) = batch();
+
+    for regularizer in [DenseRegularizer::L1(0.01), DenseRegularizer::L2(0.1), DenseRegularizer::ElasticNet(0.01, 0.1)] {
+        let mut model = model();
+        model.set_regularizer(0, regularizer).unwrap();
+        model.set_regularizer(1, DenseRegularizer::L2(0.05)).unwrap();
+
+        for (l, check) in gradient_check(&mut model, &input, &output, 1e-5).unwrap().iter().enumerate() {
+            assert!(check.max_error() < 1e-4, "{regularizer} layer {l}: {}", check.max_error());
+        }
+    }
+}
+
+#[test]
+fn regularization_loss_sums_the_layers() {
+    let mut model = model();
+    assert_eq!(model.regularization_loss(), 0.0);
+
+    model.set_regularizer(1, DenseRegularizer::L2(0.1)).unwrap();
+    let parameters = model.copy_parameters();
+    assert_eq!(model.regularization_loss(), DenseRegularizer::L2(0.1).penalty(&parameters[2]));
+
+    assert!(model.set_regularizer(2, DenseRegularizer::L2(0.1)).is_err());
+    assert!(model.set_constraint(2, DenseConstraint::MaxNorm(1.0)).is_err());
+
+    // the factors that parsing rejects are rejected by the setters too, leaving the model unchanged
+    for regularizer in [DenseRegularizer::L1(-0.1), DenseRegularizer::L2(-1.0), DenseRegularizer::ElasticNet(0.1, -0.1),
+        DenseRegularizer::L2(f64::NAN)] {
+        assert!(matches!(model.set_regularizer(0, regularizer), Err(NnError::UnsupportedRegularizer(_))), "{regularizer}");
+    }
+    for constraint in [DenseConstraint::MaxNorm(0.0), DenseConstraint::MaxNorm(-1.0), DenseConstraint::MaxNorm(f64::NAN)] {
+        assert!(matches!(model.set_constraint(0, constraint), Err(NnError::UnsupportedConstraint(_))), "{constraint}");
+    }
+    assert_eq!(model.regularizers(), &[DenseRegularizer::NoRegularizer, DenseRegularizer::L2(0.1)]);
+    assert_eq!(model.constraints(), &[DenseConstraint::NoConstraint; 2]);
+
+    let layers: Vec<Box<dyn Layer>> = vec![Box::new(Dense::new(2).constrained(DenseConstraint::MaxNorm(-1.0)))];
+    assert!(matches!(Sequential::new(3, layers, Box::new(DenseLosses::MeanSquaredError), None),
+        Err(NnError::UnsupportedConstraint(_))));
+}
+
+#[test]
+fn max_norm_rescales_rows() {
+    let mut weights = Matrix::new(2, 2);
+    weights.values = vec![3.0, 4.0, 0.3, 0.4];
+    DenseConstraint::MaxNorm(1.0).apply(&mut weights);
+
+    let expected = [0.6, 0.8, 0.3, 0.4];
+    for (w, e) in weights.values.iter().zip(expected.iter()) {
+        assert!((w - e).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn constraints_hold_after_updates() {
+    let (input, output) = batch();
+    let mut model = model();
+    model.set_constraint(0, DenseConstraint::MaxNorm(0.5)).unwrap();
+
+    for _ in 0..10 {
+        model.feed_forward(&input).unwrap();
+        let deltas = model.back_propagate(&output).unwrap();
+        let gradients = model.gradients(&deltas).unwrap();
+        model.update_weights(&gradients, &mut Sgd::new(), 1.0).unwrap();
+
+        let weights = &model.copy_parameters()[0];
+        for y in 0..weights.y_length {
+            let norm: f64 = (0..weights.x_length).map(|x| weights.values[y * weights.x_length + x].powi(2)).sum::<f64>().sqrt();
+            assert!(norm <= 0.5 + 1e-12);
+        }
+    }
+}
+
+#[test]
+fn clipping_by_value_and_by_global_norm() {
+    let mut gradients = vec![weights(), Matrix::vec_to_col_mat(&[2.0])];
+    GradientClipping::Value(1.0).apply(&mut gradients);
+    assert_eq!(gradients[0].values, vec![1.0, -1.0, 0.5, 0.0]);
+    assert_eq!(gradients[1].values, vec![1.0]);
+
+    // norm of 5 scaled down to 1, keeping the direction
+    let mut gradients = vec![Matrix::vec_to_col_mat(&[3.0]), Matrix::vec_to_col_mat(&[0.0, 4.0])];
+    GradientClipping::GlobalNorm(1.0).apply(&mut gradients);
+    assert!((global_norm(&gradients) - 1.0).abs() < 1e-12);
+    assert!((gradients[0].values[0] - 0.6).abs() < 1e-12);
+    assert!((gradients[1].values[1] - 0.8).abs() < 1e-12);
+
+    // below the limit, nothing changes
+    GradientClipping::GlobalNorm(2.0).apply(&mut gradients);
+    assert!((gradients[1].values[1] - 0.8).abs() < 1e-12);
+
+    // rejected by Session::train before any update
+    assert!(GradientClipping::Value(0.5).check().is_ok());
+    for clipping in [GradientClipping::Value(-1.0), GradientClipping::Value(f64::NAN), GradientClipping::GlobalNorm(0.0),
+        GradientClipping::GlobalNorm(f64::INFINITY)] {
+        assert!(matches!(clipping.check(), Err(NnError::UnsupportedClipping(_))), "{clipping:?}");
+    }
+}
+
+#[test]
+fn regularizers_and_constraints_are_saved() {
+    let mut model = model();
+    model.set_regularizer(0, DenseRegularizer::ElasticNet(0.01, 0.02)).unwrap();
+    model.set_constraint(1, DenseConstraint::MaxNorm(2.0)).unwrap();
+
+    let files = TempFiles::new("regularized_model");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+    let loaded = DenseModel::load_model(&filename).unwrap();
+
+    assert_eq!(loaded.regularizers(), model.regularizers());
+    assert_eq!(loaded.constraints(), model.constraints());
+    assert_eq!(loaded.regularization_loss(), model.regularization_loss());
+}
@@ -0,0 +1,105 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::layers::dense_layer::Dense;
+use rusty_nn::layers::dropout_layer::Dropout;
+use rusty_nn::layers::layer::Layer;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::models::model::Model;
+use rusty_nn::normalizations::dense_normalization::DenseNormalization;
+use rusty_nn::optimizers::adam::Adam;
+use rusty_nn::regularizers::dense_constraint::DenseConstraint;
+use rusty_nn::regularizers::dense_dropout::DenseDropout;
+use rusty_nn::regularizers::dense_regularizer::DenseRegularizer;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::TempFiles;
+
+// trains the model with a shuffled, mini-batched and split dataset, then returns the bits of all its parameters
+fn train(model: &mut dyn Model, session_seed: u64) -> Vec<Vec<u64>> {
+    let files = TempFiles::new("reproducibility");
+    let inputs: Vec<Vec<f64>> = (0..12).map(|i| vec![(i as f64 * 0.7).sin(), (i as f64 * 1.3).cos(), i as f64 / 12.0]).collect();
+    let outputs: Vec<Vec<f64>> = (0..12).map(|i| if i % 3 == 0 {vec![1.0, 0.0]} else {vec![0.0, 1.0]}).collect();
+
+    let mut session = common::session(&files, DenseShape::new(3, 1, 1), &inputs, DenseShape::new(2, 1, 1), &outputs, 8, 0.05);
+    session.set_seed(session_seed);
+    session.batch_size = 3;
+    session.optimizer = Box::new(Adam::default());
+    session.split_validation(0.25, true).unwrap();
+
+    session.train(model).unwrap();
+
+    let mut parameters = model.copy_parameters();
+    parameters.extend(model.running_statistics());
+    parameters.iter().map(|p| p.values.iter().map(|x| x.to_bits()).collect()).collect()
+}
+
+#[test]
+fn seeded_dense_models_train_identically() {
+    let model = |seed: u64| {
+        let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(6, 1, 1), DenseShape::new(2, 1, 1)];
+        let mut model = DenseModel::new_seeded(vec![DenseActivation::Relu, DenseActivation::Softmax],
+            DenseLosses::CategoricalCrossEntropy, shapes, Some(seed)).unwrap();
+        model.set_dropout(0, DenseDropout::Dropout(0.3)).unwrap();
+        model.set_normalization(0, DenseNormalization::BatchNorm(0.9)).unwrap();
+        model
+    };
+
+    let reference = train(&mut model(1), 2);
+    assert_eq!(train(&mut model(1), 2), reference);
+
+    // both the model and the session seeds matter
+    assert_ne!(train(&mut model(3), 2), reference);
+    assert_ne!(train(&mut model(1), 4), reference);
+}
+
+#[test]
+fn configuring_a_model_does_not_draw_from_its_generator() {
+    let model = |reversed: bool, unchanged_layers: bool| {
+        let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(6, 1, 1), DenseShape::new(2, 1, 1)];
+        let mut model = DenseModel::new_seeded(vec![DenseActivation::Relu, DenseActivation::Softmax],
+            DenseLosses::CategoricalCrossEntropy, shapes, Some(1)).unwrap();
+
+        let mut settings: Vec<fn(&mut DenseModel)> = vec![
+            |model| model.set_dropout(0, DenseDropout::Dropout(0.3)).unwrap(),
+            |model| model.set_regularizer(0, DenseRegularizer::L2(0.01)).unwrap(),
+            |model| model.set_constraint(0, DenseConstraint::MaxNorm(2.0)).unwrap(),
+            |model| model.set_normalization(0, DenseNormalization::BatchNorm(0.9)).unwrap()
+        ];
+        if reversed {
+            settings.reverse();
+        }
+        // settings that leave the layers as they were, but rebuild them
+        if unchanged_layers {
+            settings.push(|model| model.set_regularizer(1, DenseRegularizer::NoRegularizer).unwrap());
+            settings.push(|model| model.set_constraint(1, DenseConstraint::NoConstraint).unwrap());
+        }
+        settings.iter().for_each(|set| set(&mut model));
+        model
+    };
+
+    let reference = train(&mut model(false, false), 2);
+    assert_eq!(train(&mut model(true, false), 2), reference);
+    assert_eq!(train(&mut model(false, true), 2), reference);
+
+    assert!(matches!(DenseModel::new_seeded(vec![DenseActivation::Relu], DenseLosses::MeanSquaredError,
+        vec![DenseShape::new(3, 1, 1)], Some(1)), Err(NnError::ShapeMismatch { .. })));
+}
+
+#[test]
+fn seeded_sequential_models_train_identically() {
+    let model = |seed: u64| {
+        let layers: Vec<Box<dyn Layer>> = vec![
+            Box::new(Dense::new(6)), common::activation(DenseActivation::Tanh), Box::new(Dropout::new(DenseDropout::Dropout(0.3)))
+        ];
+        common::classifier(DenseShape::new(3, 1, 1), layers, 2, seed)
+    };
+
+    let reference = train(&mut model(1), 2);
+    assert_eq!(train(&mut model(1), 2), reference);
+
+    assert_ne!(train(&mut model(3), 2), reference);
+    assert_ne!(train(&mut model(1), 4), reference);
+}
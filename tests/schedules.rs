@@ -0,0 +1,90 @@
+use rusty_nn::schedules::lr_schedule::{
+    Constant, CosineAnnealing, ExponentialDecay, LinearWarmup, LrSchedule, OneCycle, ReduceOnPlateau, StepDecay
+};
+
+use std::f64::consts::FRAC_1_SQRT_2;
+
+fn assert_rates(schedule: &mut dyn LrSchedule, base_learning_rate: f64, expected: &[(usize, f64)]) {
+    for (epoch, rate) in expected {
+        let value = schedule.learning_rate(*epoch, base_learning_rate);
+        assert!((value - rate).abs() < 1e-12, "epoch {epoch}: {value} instead of {rate}");
+    }
+}
+
+#[test]
+fn decays() {
+    assert_rates(&mut Constant, 0.3, &[(0, 0.3), (50, 0.3)]);
+
+    // halved at epochs 3 and 6
+    assert_rates(&mut StepDecay::new(3, 0.5), 0.8, &[(0, 0.8), (2, 0.8), (3, 0.4), (5, 0.4), (6, 0.2)]);
+
+    assert_rates(&mut ExponentialDecay::new(0.5), 1.0, &[(0, 1.0), (1, 0.5), (3, 0.125)]);
+}
+
+#[test]
+fn cosine_annealing_restarts() {
+    // periods of 2, 4 then 8 epochs, restarting at epochs 2 and 6
+    let mut schedule = CosineAnnealing::new(2, 2, 0.1);
+
+    assert_rates(&mut schedule, 1.1, &[
+        (0, 1.1), (1, 0.6),
+        (2, 1.1), (3, 0.1 + 0.5 * (1.0 + FRAC_1_SQRT_2)), (4, 0.6), (5, 0.1 + 0.5 * (1.0 - FRAC_1_SQRT_2)),
+        (6, 1.1), (10, 0.6)
+    ]);
+
+    // without multiplier, every period has the same length
+    assert_rates(&mut CosineAnnealing::new(4, 1, 0.0), 1.0, &[(2, 0.5), (4, 1.0), (10, 0.5)]);
+}
+
+#[test]
+fn linear_warmup_hands_over_at_its_end() {
+    // the wrapped schedule counts its epochs from the end of the warmup
+    let mut schedule = LinearWarmup::new(4, Box::new(StepDecay::new(2, 0.1)));
+
+    assert_rates(&mut schedule, 1.0, &[(0, 0.25), (1, 0.5), (3, 1.0), (4, 1.0), (5, 1.0), (6, 0.1)]);
+
+    // and sees the losses
+    let mut schedule = LinearWarmup::new(1, Box::new(ReduceOnPlateau::new(0.5, 0, 0.0, 0.0)));
+    schedule.observe_loss(1.0);
+    schedule.observe_loss(1.0);
+    assert_rates(&mut schedule, 1.0, &[(0, 1.0), (1, 0.5)]);
+}
+
+#[test]
+fn one_cycle_peaks_after_its_warmup() {
+    // 3 epochs from 0.1 up to 1, then 6 epochs down to 0.001
+    let mut schedule = OneCycle::new(10, 0.3, 10.0, 100.0);
+
+    assert_rates(&mut schedule, 1.0, &[(0, 0.1), (3, 1.0), (6, 0.001 + 0.5 * 0.999), (9, 0.001), (12, 0.001)]);
+
+    let rates: Vec<f64> = (0..10).map(|epoch| schedule.learning_rate(epoch, 1.0)).collect();
+    assert!(rates[..4].windows(2).all(|w| w[0] < w[1]));
+    assert!(rates[3..].windows(2).all(|w| w[0] > w[1]));
+}
+
+#[test]
+fn reduce_on_plateau_waits_for_its_patience() {
+    let mut schedule = ReduceOnPlateau::new(0.5, 2, 0.01, 0.2);
+
+    // improvements of at most the threshold do not count
+    for loss in [1.0, 0.995, 0.999] {
+        schedule.observe_loss(loss);
+        assert_eq!(schedule.learning_rate(0, 1.0), 1.0);
+    }
+    schedule.observe_loss(1.0);
+    assert_eq!(schedule.learning_rate(0, 1.0), 0.5);
+
+    // an improvement resets the count of bad epochs
+    for loss in [0.5, 0.6, 0.6, 0.4, 0.45, 0.45] {
+        schedule.observe_loss(loss);
+        assert_eq!(schedule.learning_rate(0, 1.0), 0.5);
+    }
+    schedule.observe_loss(0.45);
+    assert_eq!(schedule.learning_rate(0, 1.0), 0.25);
+
+    // the learning rate stops at its minimum
+    for _ in 0..3 {
+        schedule.observe_loss(0.45);
+    }
+    assert_eq!(schedule.learning_rate(0, 1.0), 0.2);
+}
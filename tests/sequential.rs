@@ -0,0 +1,248 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::errors::nn_error::{NnError, NnResult};
+use rusty_nn::layers::dense_layer::Dense;
+use rusty_nn::layers::dropout_layer::Dropout;
+use rusty_nn::layers::flatten_layer::Flatten;
+use rusty_nn::layers::layer::Layer;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::maths::random::NnRng;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::models::gradient_check::parameter_errors;
+use rusty_nn::models::registry::Registry;
+use rusty_nn::models::sequential::Sequential;
+use rusty_nn::optimizers::adam::Adam;
+use rusty_nn::regularizers::dense_constraint::DenseConstraint;
+use rusty_nn::regularizers::dense_dropout::DenseDropout;
+use rusty_nn::regularizers::dense_regularizer::DenseRegularizer;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::{activation, batch, TempFiles};
+
+fn layers(output: DenseActivation) -> Vec<Box<dyn Layer>> {
+    vec![
+        Box::new(Flatten::new()),
+        Box::new(Dense::new(5).regularized(DenseRegularizer::L2(0.01))),
+        activation(DenseActivation::Tanh),
+        Box::new(Dropout::new(DenseDropout::Dropout(0.5))),
+        Box::new(Dense::new(4)),
+        activation(DenseActivation::PRelu),
+        Box::new(Dense::new(3).constrained(DenseConstraint::MaxNorm(10.0))),
+        activation(output)
+    ]
+}
+
+// multiplies its input by a learnable factor
+struct Scale {
+    factor: Matrix,
+    d_factor: Matrix,
+    input: Matrix
+}
+
+impl Scale {
+    fn new(factor: f64) -> Scale {
+        Scale {factor: Matrix::vec_to_col_mat(&[factor]), d_factor: Matrix::new(1, 1), input: Matrix::new(0, 0)}
+    }
+}
+
+impl Layer for Scale {
+    fn tag(&self) -> String {
+        format!("Scale {}", self.factor.values[0])
+    }
+
+    fn build(&mut self, input: &DenseShape, _rng: &mut NnRng) -> NnResult<DenseShape> {
+        Ok(*input)
+    }
+
+    fn forward(&mut self, input: &Matrix, _training: bool, _rng: &mut NnRng) -> NnResult<Matrix> {
+        self.input = input.copy();
+        let mut output = input.copy();
+        output.scale(self.factor.values[0]);
+        Ok(output)
+    }
+
+    fn backward(&mut self, gradient: &Matrix) -> NnResult<Matrix> {
+        let batch_size: f64 = gradient.x_length as f64;
+        self.d_factor.values[0] = gradient.values.iter().zip(self.input.values.iter()).map(|(g, x)| g * x).sum::<f64>() / batch_size;
+
+        let mut input_gradient = gradient.copy();
+        input_gradient.scale(self.factor.values[0]);
+        Ok(input_gradient)
+    }
+
+    fn parameters(&self) -> Vec<&Matrix> {
+        vec![&self.factor]
+    }
+
+    fn parameters_mut(&mut self) -> Vec<&mut Matrix> {
+        vec![&mut self.factor]
+    }
+
+    fn gradients(&self) -> Vec<Matrix> {
+        vec![self.d_factor.copy()]
+    }
+}
+
+#[test]
+fn sequential_gradients_match_numerical_ones() {
+    let (input, output) = batch();
+
+    for (output_activation, loss) in [(DenseActivation::Softmax, DenseLosses::CategoricalCrossEntropy),
+        (DenseActivation::Sigmoid, DenseLosses::BinaryCrossEntropy), (DenseActivation::Identity, DenseLosses::Huber(0.5))] {
+
+        let mut layers = layers(output_activation);
+        layers.insert(5, Box::new(Scale::new(1.5)));
+
+        let mut model = Sequential::new(3, layers, Box::new(loss), Some(8)).unwrap();
+        let (errors, update_errors) = parameter_errors(&mut model, &input, &output, 1e-5).unwrap();
+
+        // 3 dense layers, the PRelu slopes and the scale
+        assert_eq!(errors.len(), 8);
+        for (p, (error, update_error)) in errors.iter().zip(update_errors.iter()).enumerate() {
+            assert!(*error < 1e-4 && *update_error < 1e-4, "{loss}, parameter {p}: {error}, {update_error}");
+        }
+    }
+}
+
+#[test]
+fn sequential_matches_dense_model() {
+    let (input, _) = batch();
+    let shapes = vec![DenseShape::new(3, 1, 1), DenseShape::new(5, 1, 1), DenseShape::new(2, 1, 1)];
+    let mut dense = DenseModel::new_seeded(vec![DenseActivation::Relu, DenseActivation::Sigmoid],
+        DenseLosses::BinaryCrossEntropy, shapes, Some(3)).unwrap();
+
+    let layers: Vec<Box<dyn Layer>> = vec![Box::new(Dense::new(5)), activation(DenseActivation::Relu),
+        Box::new(Dense::new(2)), activation(DenseActivation::Sigmoid)];
+    let mut sequential = Sequential::new(3, layers, Box::new(DenseLosses::BinaryCrossEntropy), None).unwrap();
+
+    sequential.set_parameters(&dense.copy_parameters()[..4]).unwrap();
+
+    dense.feed_forward(&input).unwrap();
+    sequential.feed_forward(&input).unwrap();
+    assert_eq!(dense.result().values, sequential.result().values);
+
+    assert!(sequential.set_parameters(&dense.copy_parameters()[..3]).is_err());
+    assert!(sequential.feed_forward(&Matrix::new(1, 4)).is_err());
+}
+
+#[test]
+fn dropout_only_drops_while_training() {
+    let (input, _) = batch();
+    let mut model = Sequential::new(3, layers(DenseActivation::Sigmoid), Box::new(DenseLosses::BinaryCrossEntropy), Some(1)).unwrap();
+
+    model.feed_forward(&input).unwrap();
+    let inference = model.result();
+    model.feed_forward(&input).unwrap();
+    assert_eq!(model.result().values, inference.values);
+
+    model.set_training(true);
+    model.feed_forward(&input).unwrap();
+    assert_ne!(model.result().values, inference.values);
+}
+
+#[test]
+fn session_trains_a_sequential_model() {
+    let files = TempFiles::new("sequential_session");
+    let inputs: Vec<Vec<f64>> = vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]];
+    let outputs: Vec<Vec<f64>> = vec![vec![0.0], vec![1.0], vec![1.0], vec![0.0]];
+
+    let mut session = common::session(&files, DenseShape::new(2, 1, 1), &inputs, DenseShape::new(1, 1, 1), &outputs, 300, 0.05);
+    session.set_seed(2);
+    session.batch_size = 4;
+    session.log_interval = 1000;
+    session.optimizer = Box::new(Adam::default());
+
+    let layers: Vec<Box<dyn Layer>> = vec![Box::new(Dense::new(8)), activation(DenseActivation::Tanh),
+        Box::new(Dense::new(1)), activation(DenseActivation::Sigmoid)];
+    let mut model = Sequential::new(2, layers, Box::new(DenseLosses::BinaryCrossEntropy), Some(4)).unwrap();
+
+    let (initial_loss, _) = session.evaluate(&mut model, &session.dataset).unwrap();
+    session.train(&mut model).unwrap();
+    let (loss, _) = session.evaluate(&mut model, &session.dataset).unwrap();
+
+    assert!(loss < initial_loss / 2.0, "{initial_loss} -> {loss}");
+    assert!(!model.is_training());
+}
+
+#[test]
+fn sequential_checkpoints_resume_the_training() {
+    let files = TempFiles::new("sequential_checkpoint");
+    let checkpoint = files.path("checkpoint");
+    let inputs: Vec<Vec<f64>> = vec![vec![0.0, 0.0], vec![0.0, 1.0], vec![1.0, 0.0], vec![1.0, 1.0]];
+    let outputs: Vec<Vec<f64>> = vec![vec![0.0], vec![1.0], vec![1.0], vec![0.0]];
+
+    let session = |nb_epochs: usize| {
+        let mut session = common::session(&files, DenseShape::new(2, 1, 1), &inputs, DenseShape::new(1, 1, 1), &outputs, nb_epochs, 0.05);
+        session.batch_size = 4;
+        session.optimizer = Box::new(Adam::default());
+        session
+    };
+    let model = || {
+        let layers: Vec<Box<dyn Layer>> = vec![Box::new(Dense::new(4)), activation(DenseActivation::Tanh),
+            Box::new(Dense::new(1)), activation(DenseActivation::Sigmoid)];
+        Sequential::new(2, layers, Box::new(DenseLosses::BinaryCrossEntropy), Some(3)).unwrap()
+    };
+
+    let mut uninterrupted = model();
+    session(10).train(&mut uninterrupted).unwrap();
+
+    let mut first = session(5);
+    let mut interrupted = model();
+    first.train(&mut interrupted).unwrap();
+    first.save_checkpoint(&interrupted, &checkpoint).unwrap();
+
+    // the whole dataset is a single batch, so only the order of the sums depends on the shuffling
+    let mut second = session(5);
+    let mut resumed: Sequential = second.load_checkpoint(&checkpoint).unwrap();
+    second.train(&mut resumed).unwrap();
+
+    for (resumed, expected) in resumed.copy_parameters().iter().zip(uninterrupted.copy_parameters().iter()) {
+        for (r, e) in resumed.values.iter().zip(expected.values.iter()) {
+            assert!((r - e).abs() < 1e-12, "{r} vs {e}");
+        }
+    }
+
+    // without the state of Adam, the resumed training takes other steps
+    let mut restarted = session(5);
+    let mut model = Sequential::load_model(&checkpoint).unwrap();
+    restarted.train(&mut model).unwrap();
+    assert!((model.copy_parameters()[0].values[0] - uninterrupted.copy_parameters()[0].values[0]).abs() > 1e-6);
+}
+
+#[test]
+fn sequential_models_are_saved() {
+    let (input, _) = batch();
+    let mut layers = layers(DenseActivation::Softmax);
+    layers.push(Box::new(Scale::new(0.5)));
+
+    let mut model = Sequential::new(3, layers, Box::new(DenseLosses::CategoricalCrossEntropy), Some(6)).unwrap();
+
+    let files = TempFiles::new("sequential_model");
+    let filename = files.path("model");
+    model.save(&filename).unwrap();
+
+    assert!(matches!(Sequential::load_model(&filename), Err(NnError::UnsupportedLayer(_))));
+
+    let mut registry = Registry::new();
+    registry.register_layer("Scale", |arguments| {
+        let factor: f64 = arguments.parse().map_err(|_| NnError::UnsupportedLayer(arguments.to_string()))?;
+        Ok(Box::new(Scale::new(factor)))
+    });
+    let mut loaded = Sequential::load_model_with(&filename, &registry).unwrap();
+
+    let tags: Vec<String> = loaded.layers().iter().map(|layer| layer.tag()).collect();
+    assert_eq!(tags, model.layers().iter().map(|layer| layer.tag()).collect::<Vec<String>>());
+    assert_eq!(tags[1], "Dense 5 L2(0.01) NoConstraint");
+    assert_eq!(tags[3], "Dropout Dropout(0.5)");
+    assert_eq!(loaded.loss.name(), "CategoricalCrossEntropy");
+
+    model.feed_forward(&input).unwrap();
+    loaded.feed_forward(&input).unwrap();
+    assert_eq!(model.result().values, loaded.result().values);
+
+    assert!(registry.layer("Dense").is_err());
+    assert!(registry.layer("Activation Unknown").is_err());
+    assert_eq!(registry.layer("Activation Relu").unwrap().activation().map(|a| a.name()), Some("Relu".to_string()));
+}
@@ -0,0 +1,113 @@
+use rusty_nn::data::create_data::Sample;
+use rusty_nn::data::split_data::{class_of, split_dataset, Labels};
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::maths::random::new_rng;
+use rusty_nn::sessions::session::Session;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::TempFiles;
+
+// 10 samples of class 0, 6 of class 1 and 4 of class 2, with one-hot outputs
+fn classified() -> Vec<Sample> {
+    let classes: Vec<usize> = (0..20).map(|i| if i < 10 {0} else if i < 16 {1} else {2}).collect();
+
+    classes.iter().enumerate().map(|(i, class)| {
+        let mut output = vec![0.0; 3];
+        output[*class] = 1.0;
+        Sample::new(vec![i as f64], output)
+    }).collect()
+}
+
+fn inputs(samples: &[Sample]) -> Vec<f64> {
+    let mut inputs: Vec<f64> = samples.iter().map(|sample| sample.input.values[0]).collect();
+    inputs.sort_by(f64::total_cmp);
+    inputs
+}
+
+fn class_counts(samples: &[Sample]) -> Vec<usize> {
+    (0..3).map(|class| samples.iter().filter(|sample| class_of(&sample.output) == class).count()).collect()
+}
+
+#[test]
+fn splits_move_a_fraction_of_the_samples() {
+    let (kept, split) = split_dataset(classified(), 0.3, None, &mut new_rng(Some(1))).unwrap();
+
+    assert_eq!((kept.len(), split.len()), (14, 6));
+
+    // every sample ends up in exactly one of the sets
+    let mut all: Vec<Sample> = kept;
+    all.extend(split);
+    assert_eq!(inputs(&all), (0..20).map(|i| i as f64).collect::<Vec<f64>>());
+
+    let (kept, split) = split_dataset(classified(), 0.0, None, &mut new_rng(Some(1))).unwrap();
+    assert_eq!((kept.len(), split.len()), (20, 0));
+
+    // the same seed gives the same split
+    let split_with = |seed: u64| inputs(&split_dataset(classified(), 0.3, None, &mut new_rng(Some(seed))).unwrap().1);
+    assert_eq!(split_with(4), split_with(4));
+}
+
+#[test]
+fn stratified_splits_keep_the_class_proportions() {
+    let (kept, split) = split_dataset(classified(), 0.5, Some(Labels::Encoded), &mut new_rng(Some(2))).unwrap();
+
+    assert_eq!(class_counts(&kept), vec![5, 3, 2]);
+    assert_eq!(class_counts(&split), vec![5, 3, 2]);
+
+    // the fraction of each class is rounded: 2.5, 1.5 and 1 samples
+    let (kept, split) = split_dataset(classified(), 0.25, Some(Labels::Encoded), &mut new_rng(Some(2))).unwrap();
+    assert_eq!(class_counts(&kept), vec![7, 4, 3]);
+    assert_eq!(class_counts(&split), vec![3, 2, 1]);
+
+    // a single binary output holds two classes
+    let binary: Vec<Sample> = (0..8).map(|i| Sample::new(vec![i as f64], vec![if i < 6 {1.0} else {0.0}])).collect();
+    let (kept, split) = split_dataset(binary, 0.5, Some(Labels::Encoded), &mut new_rng(Some(3))).unwrap();
+    assert_eq!(split.iter().filter(|sample| sample.output.values[0] == 1.0).count(), 3);
+    assert_eq!(kept.iter().filter(|sample| sample.output.values[0] == 0.0).count(), 1);
+}
+
+#[test]
+fn fractions_out_of_range_are_rejected() {
+    for fraction in [-0.1, 1.0, 1.5, f64::NAN] {
+        assert!(matches!(split_dataset(classified(), fraction, None, &mut new_rng(Some(1))), Err(NnError::InvalidDataset(_))),
+            "{fraction}");
+    }
+
+    // as are the labels that are not classes
+    assert!(matches!(split_dataset(classified(), 0.5, Some(Labels::Sparse(3)), &mut new_rng(Some(1))),
+        Err(NnError::InvalidDataset(_))));
+}
+
+#[test]
+fn rejected_splits_leave_the_session_unchanged() {
+    let files = TempFiles::new("split_session");
+    let samples = classified();
+    let values: Vec<Vec<f64>> = samples.iter().map(|sample| sample.input.values.clone()).collect();
+    let classes: Vec<Vec<f64>> = samples.iter().map(|sample| vec![class_of(&sample.output) as f64]).collect();
+
+    let session = || {
+        let mut session = common::session(&files, DenseShape::new(1, 1, 1), &values, DenseShape::new(1, 1, 1), &classes, 1, 0.1);
+        session.set_seed(5);
+        session
+    };
+    let split_inputs = |session: &Session| (inputs(&session.dataset), inputs(&session.validation));
+
+    let mut rejected = session();
+    rejected.labels = Labels::Sparse(2);
+    assert!(matches!(rejected.split_validation(1.0, false), Err(NnError::InvalidDataset(_))));
+    assert!(matches!(rejected.split_validation(0.5, true), Err(NnError::InvalidDataset(_))));
+
+    let all: Vec<f64> = (0..20).map(|i| i as f64).collect();
+    assert_eq!(split_inputs(&rejected), (all, Vec::new()));
+
+    // nor did they draw from its generator
+    rejected.labels = Labels::Sparse(3);
+    rejected.split_validation(0.5, true).unwrap();
+
+    let mut accepted = session();
+    accepted.labels = Labels::Sparse(3);
+    accepted.split_validation(0.5, true).unwrap();
+    assert_eq!(split_inputs(&rejected), split_inputs(&accepted));
+}
@@ -0,0 +1,161 @@
+use rusty_nn::activations::dense_activation::DenseActivation;
+use rusty_nn::data::class_weights::{sample_weights, ClassWeights};
+use rusty_nn::data::create_data::Sample;
+use rusty_nn::data::split_data::{split_dataset, Labels};
+use rusty_nn::errors::nn_error::NnError;
+use rusty_nn::losses::dense_losses::DenseLosses;
+use rusty_nn::losses::loss::Loss;
+use rusty_nn::maths::matrices::Matrix;
+use rusty_nn::maths::random::new_rng;
+use rusty_nn::models::dense_model::DenseModel;
+use rusty_nn::shapes::dense_shape::DenseShape;
+
+mod common;
+
+use common::{batch_of, TempFiles};
+
+fn skewed() -> Vec<Sample> {
+    let mut samples: Vec<Sample> = (0..6).map(|i| Sample::new(vec![i as f64, 1.0], vec![0.0])).collect();
+    samples.push(Sample::new(vec![-1.0, 2.0], vec![1.0]));
+    samples.push(Sample::new(vec![-2.0, 3.0], vec![1.0]).weighted(0.5));
+    samples
+}
+
+fn model() -> DenseModel {
+    let shapes = vec![DenseShape::new(2, 1, 1), DenseShape::new(3, 1, 1), DenseShape::new(1, 1, 1)];
+    DenseModel::new_seeded(vec![DenseActivation::Tanh, DenseActivation::Sigmoid],
+        DenseLosses::BinaryCrossEntropy, shapes, Some(2)).unwrap()
+}
+
+#[test]
+fn balanced_class_weights() {
+    let samples = skewed();
+
+    // 8 samples, 6 of class 0 and 2 of class 1
+    assert_eq!(ClassWeights::Balanced.compute(&samples, Labels::Encoded).unwrap(), vec![8.0 / 12.0, 2.0]);
+    assert_eq!(ClassWeights::Uniform.compute(&samples, Labels::Encoded).unwrap(), vec![1.0, 1.0]);
+    assert_eq!(ClassWeights::Manual(vec![1.0, 5.0]).compute(&samples, Labels::Encoded).unwrap(), vec![1.0, 5.0]);
+    assert!(ClassWeights::Manual(vec![1.0, 5.0, 2.0]).compute(&samples, Labels::Encoded).is_err());
+
+    let one_hot = vec![Sample::new(vec![0.0], vec![0.0, 0.0, 1.0]), Sample::new(vec![0.0], vec![1.0, 0.0, 0.0])];
+    assert_eq!(ClassWeights::Balanced.compute(&one_hot, Labels::Encoded).unwrap(), vec![2.0 / 3.0, 1.0, 2.0 / 3.0]);
+}
+
+#[test]
+fn sample_weights_combine_both_weights() {
+    let samples = skewed();
+    let weights = sample_weights(&samples, Some(&[1.0, 4.0]), Labels::Encoded).unwrap();
+
+    assert_eq!(weights.values, vec![1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 4.0, 2.0]);
+    assert_eq!(sample_weights(&samples, None, Labels::Encoded).unwrap().values[7], 0.5);
+    assert!(sample_weights(&samples, Some(&[1.0]), Labels::Encoded).is_err());
+}
+
+#[test]
+fn sparse_labels_are_class_indices() {
+    // 6 samples of class 2, 3 of class 0 and 1 of class 1, where a binary threshold would only see two classes
+    let labels: Vec<f64> = vec![2.0, 2.0, 0.0, 2.0, 1.0, 2.0, 0.0, 2.0, 0.0, 2.0];
+    let samples: Vec<Sample> = labels.iter().map(|label| Sample::new(vec![*label], vec![*label])).collect();
+    let sparse = Labels::Sparse(4);
+
+    assert_eq!(ClassWeights::Balanced.compute(&samples, sparse).unwrap(), vec![10.0 / 12.0, 2.5, 10.0 / 24.0, 1.0]);
+    assert_eq!(ClassWeights::Uniform.compute(&samples, sparse).unwrap(), vec![1.0; 4]);
+    assert!(ClassWeights::Manual(vec![1.0, 5.0]).compute(&samples, sparse).is_err());
+
+    let weights = sample_weights(&samples, Some(&[1.0, 2.0, 3.0, 4.0]), sparse).unwrap();
+    assert_eq!(weights.values, vec![3.0, 3.0, 1.0, 3.0, 2.0, 3.0, 1.0, 3.0, 1.0, 3.0]);
+
+    // half of each class (rounded up) is split off
+    let (kept, split) = split_dataset(samples, 0.5, Some(sparse), &mut new_rng(Some(3))).unwrap();
+    for (set, expected) in [(&kept, [1.0, 0.0, 3.0]), (&split, [2.0, 1.0, 3.0])] {
+        let counts: Vec<f64> = (0..3).map(|class| set.iter().filter(|s| s.output.values[0] == class as f64).count() as f64).collect();
+        assert_eq!(counts, expected);
+    }
+
+    for invalid in [4.0, 1.5, -1.0] {
+        let samples = vec![Sample::new(vec![0.0], vec![invalid])];
+        assert!(matches!(ClassWeights::Balanced.compute(&samples, sparse), Err(NnError::InvalidDataset(_))), "{invalid}");
+    }
+    assert!(Labels::Sparse(3).class_of(&Matrix::vec_to_col_mat(&[0.0, 1.0])).is_err());
+}
+
+#[test]
+fn sessions_need_sparse_labels_for_sparse_losses() {
+    let files = TempFiles::new("weights_sparse_session");
+    let inputs: Vec<Vec<f64>> = vec![vec![0.5, -0.2], vec![-0.7, 0.3], vec![0.1, 0.9], vec![-0.4, -0.6]];
+    let labels: Vec<Vec<f64>> = vec![vec![2.0], vec![0.0], vec![2.0], vec![1.0]];
+    let mut session = common::session(&files, DenseShape::new(2, 1, 1), &inputs, DenseShape::new(1, 1, 1), &labels, 5, 0.1);
+    session.class_weights = ClassWeights::Balanced;
+
+    let shapes = vec![DenseShape::new(2, 1, 1), DenseShape::new(3, 1, 1)];
+    let mut model = DenseModel::new_seeded(vec![DenseActivation::Softmax], DenseLosses::SparseCategoricalCrossEntropy, shapes, Some(4)).unwrap();
+
+    assert!(matches!(session.train(&mut model), Err(NnError::InvalidDataset(_))));
+
+    session.labels = Labels::Sparse(3);
+    session.train(&mut model).unwrap();
+}
+
+#[test]
+fn weighted_error_of_uniform_weights_is_the_error() {
+    let values = Matrix::from_columns(&[&Matrix::vec_to_col_mat(&[0.2]), &Matrix::vec_to_col_mat(&[0.9])]).unwrap();
+    let desired = Matrix::from_columns(&[&Matrix::vec_to_col_mat(&[0.0]), &Matrix::vec_to_col_mat(&[0.0])]).unwrap();
+    let loss = DenseLosses::BinaryCrossEntropy;
+
+    let mut weights = Matrix::new(2, 1);
+    weights.values = vec![1.0, 1.0];
+    assert!((loss.weighted_error(&values, &desired, &weights).unwrap() - loss.error(&values, &desired).unwrap()).abs() < 1e-12);
+
+    weights.values = vec![3.0, 0.0];
+    let first = loss.error(&values.column(0), &desired.column(0)).unwrap();
+    assert!((loss.weighted_error(&values, &desired, &weights).unwrap() - 1.5 * first).abs() < 1e-12);
+
+    assert!(loss.weighted_error(&values, &desired, &Matrix::new(3, 1)).is_err());
+}
+
+// a sample of weight 2 counts as the same sample given twice
+#[test]
+fn weighted_gradients_match_repeated_samples() {
+    let samples = vec![Sample::new(vec![0.5, -1.0], vec![1.0]), Sample::new(vec![-0.3, 0.8], vec![0.0])];
+    let repeated = vec![Sample::new(vec![0.5, -1.0], vec![1.0]), Sample::new(vec![0.5, -1.0], vec![1.0]),
+        Sample::new(vec![-0.3, 0.8], vec![0.0])];
+
+    let mut model = model();
+
+    let (input, output) = batch_of(&samples);
+    model.feed_forward(&input).unwrap();
+    let mut weights = Matrix::new(2, 1);
+    weights.values = vec![2.0, 1.0];
+    let deltas = model.back_propagate_weighted(&output, &weights).unwrap();
+    let weighted = model.gradients(&deltas).unwrap();
+
+    let (input, output) = batch_of(&repeated);
+    model.feed_forward(&input).unwrap();
+    let deltas = model.back_propagate(&output).unwrap();
+    let expected = model.gradients(&deltas).unwrap();
+
+    // both are averaged over their own number of samples
+    for (weighted, expected) in weighted.iter().zip(expected.iter()) {
+        for (w, e) in weighted.values.iter().zip(expected.values.iter()) {
+            assert!((w * 2.0 / 3.0 - e).abs() < 1e-12, "{w} vs {e}");
+        }
+    }
+}
+
+#[test]
+fn weighted_fused_softmax_gradients_are_scaled() {
+    let shapes = vec![DenseShape::new(2, 1, 1), DenseShape::new(3, 1, 1)];
+    let mut model = DenseModel::new_seeded(vec![DenseActivation::Softmax],
+        DenseLosses::CategoricalCrossEntropy, shapes, Some(4)).unwrap();
+
+    let input = Matrix::vec_to_col_mat(&[0.3, -0.6]);
+    let output = Matrix::vec_to_col_mat(&[0.0, 1.0, 0.0]);
+
+    model.feed_forward(&input).unwrap();
+    let plain = model.back_propagate(&output).unwrap();
+    let weighted = model.back_propagate_weighted(&output, &Matrix::vec_to_col_mat(&[3.0])).unwrap();
+
+    for (p, w) in plain[1].values.iter().zip(weighted[1].values.iter()) {
+        assert!((3.0 * p - w).abs() < 1e-12);
+    }
+}